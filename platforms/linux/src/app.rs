@@ -1,39 +1,56 @@
 //! Application lifecycle and GTK setup
 
-use crate::models::Preferences;
-use crate::services::{CursorFinderService, ShakeDetector, SynergyMonitor};
-use crate::ui::{HighlightOverlay, SettingsWindow, TrayIcon};
+use crate::models::{
+    Color, LoadReport, Paint, Preferences, PreferencesStore, SharedSettings, ShortcutAction,
+    Trigger,
+};
+use crate::services::{
+    is_wayland_session, show_notification, CursorFinderService, DesktopAppearanceMonitor,
+    DisplayLayoutMonitor, GlobalShortcutsService, ModifierTapDetector, PreferencesWatcher,
+    ShakeDetector, SynergyEvent, SynergyMonitor, CURSOR_ICON, WARNING_ICON,
+};
+use crate::ui::{HighlightOverlay, SettingsWindow, TrayCommand, TrayIcon, TrayState};
 use anyhow::Result;
 use gtk4::prelude::*;
 use gtk4::{gio, glib, Application};
 use libadwaita as adw;
 use std::cell::RefCell;
+use std::path::Path;
 use std::rc::Rc;
-use std::sync::Arc;
 use tokio::sync::mpsc;
 
 const APP_ID: &str = "com.cursorhome.linux";
 
 /// Main application state
 pub struct AppState {
-    pub preferences: Arc<Preferences>,
+    pub preferences: PreferencesStore,
+    pub preferences_watcher: Rc<RefCell<PreferencesWatcher>>,
+    pub display_layout: Rc<RefCell<DisplayLayoutMonitor>>,
+    pub desktop_appearance: Rc<RefCell<DesktopAppearanceMonitor>>,
     pub cursor_finder: Rc<RefCell<CursorFinderService>>,
     pub synergy_monitor: Rc<RefCell<SynergyMonitor>>,
     pub shake_detector: Rc<RefCell<ShakeDetector>>,
+    pub modifier_tap: Rc<RefCell<ModifierTapDetector>>,
+    pub global_shortcuts: Rc<RefCell<GlobalShortcutsService>>,
+    pub tray_icon: Rc<RefCell<TrayIcon>>,
     pub highlight_overlay: Rc<RefCell<Option<HighlightOverlay>>>,
 }
 
 impl AppState {
-    pub fn new() -> Self {
-        let preferences = Arc::new(Preferences::load());
+    pub fn new(preferences: PreferencesStore) -> Self {
+        let shake_sensitivity = preferences.with(|p| p.shake_sensitivity);
 
         Self {
-            preferences: preferences.clone(),
             cursor_finder: Rc::new(RefCell::new(CursorFinderService::new(preferences.clone()))),
             synergy_monitor: Rc::new(RefCell::new(SynergyMonitor::new())),
-            shake_detector: Rc::new(RefCell::new(ShakeDetector::new(
-                preferences.shake_sensitivity,
-            ))),
+            shake_detector: Rc::new(RefCell::new(ShakeDetector::new(shake_sensitivity))),
+            modifier_tap: Rc::new(RefCell::new(ModifierTapDetector::new())),
+            global_shortcuts: Rc::new(RefCell::new(GlobalShortcutsService::new())),
+            preferences_watcher: Rc::new(RefCell::new(PreferencesWatcher::new())),
+            display_layout: Rc::new(RefCell::new(DisplayLayoutMonitor::new())),
+            desktop_appearance: Rc::new(RefCell::new(DesktopAppearanceMonitor::new())),
+            preferences,
+            tray_icon: Rc::new(RefCell::new(TrayIcon::new())),
             highlight_overlay: Rc::new(RefCell::new(None)),
         }
     }
@@ -77,11 +94,79 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
+/// Switch the running instance to a preset, or update the saved
+/// preferences if CursorHome is not running
+pub fn select_preset(name: &str) -> Result<()> {
+    let mut preferences = read_saved_preferences()?;
+    let Some(index) = preferences.preset_index(name) else {
+        let names: Vec<String> = preferences.presets.iter().map(|p| p.slug()).collect();
+        anyhow::bail!(
+            "No preset named \"{}\" (available: {})",
+            name,
+            names.join(", ")
+        );
+    };
+
+    let app = Application::builder().application_id(APP_ID).build();
+    app.register(gio::Cancellable::NONE)?;
+
+    if app.is_remote() {
+        // Forwarded over D-Bus to the primary instance
+        app.activate_action("select-preset", Some(&name.to_variant()));
+        if let Some(connection) = app.dbus_connection() {
+            connection.flush_sync(gio::Cancellable::NONE)?;
+        }
+    } else {
+        preferences.apply_preset(index);
+        preferences.save()?;
+    }
+
+    println!("Switched to preset \"{}\"", preferences.presets[index].name);
+    Ok(())
+}
+
+/// Write the style and behavior settings to `path`, in the format of the
+/// shared schemas
+pub fn export_settings(path: &Path) -> Result<()> {
+    let preferences = read_saved_preferences()?;
+    SharedSettings::from_preferences(&preferences).write(path)?;
+
+    println!("Exported settings to {}", path.display());
+    Ok(())
+}
+
+/// Import a settings file into the saved preferences, which a running
+/// instance picks up from the file
+pub fn import_settings(path: &Path) -> Result<()> {
+    let settings = SharedSettings::read(path)?;
+    let mut preferences = read_saved_preferences()?;
+    for correction in settings.apply_to(&mut preferences) {
+        println!("Out of range, corrected {}", correction);
+    }
+    preferences.save()?;
+
+    println!("Imported settings from {}", path.display());
+    Ok(())
+}
+
+/// The saved preferences, or defaults if there is no file yet
+///
+/// Unlike [`Preferences::load`] this fails on a file that doesn't parse, so
+/// commands never act on (or write back) defaults in its place.
+fn read_saved_preferences() -> Result<Preferences> {
+    match Preferences::config_file().filter(|file| file.exists()) {
+        Some(file) => Ok(Preferences::read(&file)?.0),
+        None => Ok(Preferences::default()),
+    }
+}
+
 fn setup_app(app: &Application) {
-    let state = Rc::new(AppState::new());
+    let (preferences, load_report) = PreferencesStore::load();
+    let state = Rc::new(AppState::new(preferences));
+    report_load(&load_report);
 
     // Setup system tray icon
-    setup_tray_icon(app, state.clone());
+    setup_tray_icon(app, state.clone(), load_report.error);
 
     // Setup Synergy monitoring
     setup_synergy_monitoring(state.clone());
@@ -89,31 +174,315 @@ fn setup_app(app: &Application) {
     // Setup shake detection
     setup_shake_detection(state.clone());
 
+    // Follow the desktop accent color
+    setup_desktop_appearance(state.clone());
+
+    // Setup modifier double-tap trigger
+    setup_modifier_tap(state.clone());
+
     // Setup keyboard shortcuts
     setup_shortcuts(app, state.clone());
+    apply_shortcuts(app, &state);
+
+    // Apply settings changed at runtime to the running services
+    let app_clone = app.clone();
+    let state_clone = state.clone();
+    state.preferences.subscribe(move |old, new| {
+        apply_preferences(&app_clone, &state_clone, old, new);
+    });
+
+    // Switch presets to match the connected monitors
+    setup_display_profiles(state.clone());
+
+    // Pick up edits made to preferences.json outside the app
+    setup_preferences_watcher(state.clone());
+
+    // Write out any change still waiting for the save delay
+    let preferences = state.preferences.clone();
+    app.connect_shutdown(move |_| preferences.flush());
 
     tracing::info!("CursorHome initialized successfully");
 }
 
-fn setup_tray_icon(_app: &Application, state: Rc<AppState>) {
-    let tray = TrayIcon::new(state.clone());
-    tray.init();
+fn setup_tray_icon(app: &Application, state: Rc<AppState>, config_error: Option<String>) {
+    let tray_state = state.preferences.with(|p| TrayState {
+        enabled: p.enabled,
+        follow_mode: state.cursor_finder.borrow().is_follow_mode(),
+        presets: p.presets.iter().map(|preset| preset.name.clone()).collect(),
+        active_preset: p.active_preset(),
+        color: tray_color(&state, &p.cursor_style.color),
+        away_on: None,
+        config_error,
+    });
+
+    // Menu items map onto the application actions where one exists
+    let app = app.clone();
+    let state_clone = state.clone();
+    state
+        .tray_icon
+        .borrow_mut()
+        .start_with_callback(tray_state, move |command| match command {
+            TrayCommand::FindCursor => app.activate_action("find-cursor", None),
+            TrayCommand::SetEnabled(enabled) => {
+                if enabled != state_clone.cursor_finder.borrow().is_enabled() {
+                    app.activate_action("toggle-enabled", None);
+                }
+            }
+            TrayCommand::SetFollowMode(follow) => {
+                if follow != state_clone.cursor_finder.borrow().is_follow_mode() {
+                    app.activate_action("follow-mode", None);
+                }
+            }
+            TrayCommand::SelectPreset(name) => {
+                app.activate_action("select-preset", Some(&name.to_variant()))
+            }
+            TrayCommand::ShowSettings => app.activate_action("show-settings", None),
+            TrayCommand::Quit => app.activate_action("quit", None),
+        });
+}
+
+fn setup_desktop_appearance(state: Rc<AppState>) {
+    let state_clone = state.clone();
+    state
+        .desktop_appearance
+        .borrow_mut()
+        .start_with_callback(move |appearance| {
+            let state = &state_clone;
+            state.cursor_finder.borrow_mut().set_appearance(appearance);
+
+            let color = state
+                .preferences
+                .with(|p| tray_color(state, &p.cursor_style.color));
+            state.tray_icon.borrow().update(|tray| tray.color = color);
+        });
+}
+
+/// The color the tray icon shows for the highlight paint
+fn tray_color(state: &AppState, paint: &Paint) -> Color {
+    if paint.is_accent() {
+        let appearance = state.cursor_finder.borrow().appearance();
+        appearance.highlight_color(paint.base_color())
+    } else {
+        paint.base_color()
+    }
+}
+
+fn setup_display_profiles(state: Rc<AppState>) {
+    // The monitors may have changed since the last run
+    apply_display_profile(&state, &DisplayLayoutMonitor::current_fingerprint());
+
+    let state_clone = state.clone();
+    let result = state
+        .display_layout
+        .borrow_mut()
+        .start_with_callback(move |layout| {
+            state_clone.cursor_finder.borrow_mut().refresh_displays();
+            apply_display_profile(&state_clone, layout);
+        });
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to watch monitor layout: {}", e);
+    }
+}
+
+/// Switch to the preset configured for a monitor layout, if there is one
+fn apply_display_profile(state: &AppState, layout: &str) {
+    state.preferences.update_automatically(|p| {
+        let Some(preset) = p.preset_for_layout(layout).map(str::to_string) else {
+            return;
+        };
+
+        match p.preset_index(&preset) {
+            Some(index) => {
+                p.apply_preset(index);
+                tracing::info!("Monitor layout has a profile, switched to \"{}\"", preset);
+            }
+            None => tracing::warn!("Display profile refers to missing preset \"{}\"", preset),
+        }
+    });
+}
+
+fn setup_preferences_watcher(state: Rc<AppState>) {
+    let state_clone = state.clone();
+    let result = state
+        .preferences_watcher
+        .borrow_mut()
+        .start_with_callback(move |result| match result {
+            Ok((preferences, report)) => {
+                tracing::info!("Preferences file changed, applying");
+                report_load(&report);
+                state_clone
+                    .tray_icon
+                    .borrow()
+                    .update(|tray| tray.config_error = None);
+                state_clone.preferences.reload(preferences, &report);
+            }
+            Err(e) => {
+                // Keep the current settings, but make it obvious they are stale
+                let message = format!("{:#}", e);
+                tracing::warn!("{}; keeping current settings", message);
+                show_notification(
+                    "Could not load preferences.json",
+                    format!("{}\n\nThe previous settings are still in use.", message),
+                    WARNING_ICON,
+                );
+                state_clone
+                    .tray_icon
+                    .borrow()
+                    .update(|tray| tray.config_error = Some(message));
+            }
+        });
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to watch preferences file: {}", e);
+    }
+}
+
+/// Tell the user about a preferences file that could not be loaded or is
+/// from a newer version, or about out-of-range values that were corrected
+/// on load
+fn report_load(report: &LoadReport) {
+    if report.is_clean() {
+        return;
+    }
+
+    if let Some(error) = &report.error {
+        tracing::warn!("{}; using defaults", error);
+        show_notification(
+            "Could not load preferences.json",
+            format!(
+                "{}\n\nDefault settings are in use. The file is left as it is until it is \
+                 fixed or you change a setting.",
+                error
+            ),
+            WARNING_ICON,
+        );
+    }
+
+    if report.newer_version.is_some() {
+        show_notification(
+            "preferences.json is from a newer CursorHome",
+            "Settings this version doesn't know are ignored. The file is left as it is until \
+             it changes or you change a setting, which saves it without them."
+                .to_string(),
+            WARNING_ICON,
+        );
+    }
+
+    if let Some(version) = report.migrated_from {
+        tracing::info!(
+            "Preferences upgraded from schema version {}; the file is rewritten on the next change",
+            version
+        );
+    }
+
+    if report.corrections.is_empty() {
+        return;
+    }
+
+    let lines: Vec<String> = report.corrections.iter().map(|c| c.to_string()).collect();
+    show_notification(
+        "Some preferences were out of range",
+        format!("These values were corrected:\n{}", lines.join("\n")),
+        WARNING_ICON,
+    );
 }
 
 fn setup_synergy_monitoring(state: Rc<AppState>) {
+    if !state.preferences.with(|p| p.synergy_enabled) {
+        state.synergy_monitor.borrow_mut().stop();
+        return;
+    }
+
     let state_clone = state.clone();
 
     // Start monitoring Synergy with callback
     let mut monitor = state.synergy_monitor.borrow_mut();
-    let result = monitor.start_with_callback(move |screen_name| {
-        tracing::info!("Cursor returned from {} - triggering highlight", screen_name);
-        if state_clone.preferences.enabled {
-            state_clone.cursor_finder.borrow_mut().find_cursor();
+    monitor.stop();
+    let result = monitor.start_with_callback(move |event| match event {
+        SynergyEvent::CursorLeft(screen_name) => {
+            tracing::info!("Cursor left for {}", screen_name);
+            if state_clone
+                .preferences
+                .with(|p| p.notifications.transitions)
+            {
+                show_notification(
+                    &format!("Cursor moved to {}", screen_name),
+                    "Move it back to this screen to highlight it here.".to_string(),
+                    CURSOR_ICON,
+                );
+            }
+            state_clone
+                .tray_icon
+                .borrow()
+                .update(|tray| tray.away_on = Some(screen_name));
+        }
+        SynergyEvent::CursorReturned(screen_name) => {
+            tracing::info!(
+                "Cursor returned from {} - triggering highlight",
+                screen_name
+            );
+            state_clone
+                .tray_icon
+                .borrow()
+                .update(|tray| tray.away_on = None);
+            if state_clone
+                .preferences
+                .with(|p| p.notifications.transitions)
+            {
+                show_notification(
+                    &format!("Cursor is back from {}", screen_name),
+                    format!("The cursor returned to {}.", glib::host_name()),
+                    CURSOR_ICON,
+                );
+            }
+            if state_clone.cursor_finder.borrow().is_enabled() {
+                let label = state_clone.preferences.with(|p| {
+                    let label = &p.transition_label;
+                    label.enabled.then(|| label.text(&screen_name))
+                });
+                let mut finder = state_clone.cursor_finder.borrow_mut();
+                match label {
+                    Some(text) => finder.find_cursor_with_label(Trigger::Synergy, text),
+                    None => finder.find_cursor(Trigger::Synergy),
+                }
+            }
+        }
+        SynergyEvent::LogLost(path) => {
+            state_clone
+                .tray_icon
+                .borrow()
+                .update(|tray| tray.away_on = None);
+            if state_clone
+                .preferences
+                .with(|p| p.notifications.synergy_log)
+            {
+                show_notification(
+                    "Lost the Synergy log",
+                    format!(
+                        "{} was removed or renamed. Machine transitions will be \
+                         highlighted again once Synergy writes a new log there.",
+                        path.display()
+                    ),
+                    WARNING_ICON,
+                );
+            }
         }
     });
 
     if let Err(e) = result {
         tracing::warn!("Failed to start Synergy monitoring: {}", e);
+        if state.preferences.with(|p| p.notifications.synergy_log) {
+            show_notification(
+                "Synergy log not found",
+                format!(
+                    "{:#}. Machine transitions are not highlighted until Synergy \
+                     highlighting is turned off and on again with Synergy running.",
+                    e
+                ),
+                WARNING_ICON,
+            );
+        }
     }
 }
 
@@ -126,27 +495,156 @@ fn setup_shake_detection(state: Rc<AppState>) {
 
         let state = state_clone.clone();
         glib::idle_add_local_once(move || {
-            if state.preferences.enabled && state.preferences.shake_enabled {
-                state.cursor_finder.borrow_mut().find_cursor();
+            if state.preferences.with(|p| p.shake_enabled) {
+                state.cursor_finder.borrow_mut().find_cursor(Trigger::Shake);
             }
         });
     }));
 
+    if !state.preferences.with(|p| p.shake_enabled) {
+        return;
+    }
+
     if let Err(e) = detector.start() {
         tracing::warn!("Failed to start shake detection: {}", e);
     }
 }
 
+fn setup_modifier_tap(state: Rc<AppState>) {
+    let tap_trigger = state.preferences.with(|p| p.tap_trigger.clone());
+    if !tap_trigger.enabled {
+        state.modifier_tap.borrow_mut().stop();
+        return;
+    }
+
+    // XInput2 raw events under XWayland only cover X11 clients
+    if is_wayland_session() {
+        tracing::info!("Modifier double-tap trigger is only available on X11");
+        return;
+    }
+
+    let state_clone = state.clone();
+    let result = state.modifier_tap.borrow_mut().start_with_callback(
+        tap_trigger.modifier,
+        tap_trigger.interval,
+        move || {
+            state_clone
+                .cursor_finder
+                .borrow_mut()
+                .find_cursor(Trigger::Hotkey);
+        },
+    );
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to start modifier double-tap detection: {}", e);
+    }
+}
+
 fn setup_shortcuts(app: &Application, state: Rc<AppState>) {
     // Add application actions for keyboard shortcuts
     let find_action = gio::SimpleAction::new("find-cursor", None);
     let state_clone = state.clone();
     find_action.connect_activate(move |_, _| {
         tracing::debug!("Find cursor action triggered");
-        state_clone.cursor_finder.borrow_mut().find_cursor();
+        state_clone
+            .cursor_finder
+            .borrow_mut()
+            .find_cursor(Trigger::Hotkey);
     });
     app.add_action(&find_action);
 
+    // "Test on Screen" in the settings window: the default style, even while
+    // highlighting is disabled
+    let test_action = gio::SimpleAction::new("test-highlight", None);
+    let state_clone = state.clone();
+    test_action.connect_activate(move |_, _| {
+        state_clone.cursor_finder.borrow_mut().test_highlight();
+    });
+    app.add_action(&test_action);
+
+    // Entry point for other applications over D-Bus (org.gtk.Actions), with
+    // the trigger id so the highlight uses that trigger's style
+    let find_from_action =
+        gio::SimpleAction::new("find-cursor-from", Some(glib::VariantTy::STRING));
+    let state_clone = state.clone();
+    find_from_action.connect_activate(move |_, parameter| {
+        let Some(id) = parameter.and_then(|v| v.get::<String>()) else {
+            return;
+        };
+        let Some(trigger) = Trigger::from_id(&id) else {
+            tracing::warn!("Unknown highlight trigger \"{}\"", id);
+            return;
+        };
+        state_clone.cursor_finder.borrow_mut().find_cursor(trigger);
+    });
+    app.add_action(&find_from_action);
+
+    // The action state follows the preference (see apply_preferences)
+    let enabled = state.preferences.with(|p| p.enabled);
+    let enabled_action =
+        gio::SimpleAction::new_stateful("toggle-enabled", None, &enabled.to_variant());
+    let state_clone = state.clone();
+    enabled_action.connect_activate(move |_, _| {
+        state_clone.preferences.update(|p| p.enabled = !p.enabled);
+    });
+    app.add_action(&enabled_action);
+
+    let teleport_action = gio::SimpleAction::new("teleport", None);
+    let state_clone = state.clone();
+    teleport_action.connect_activate(move |_, _| {
+        tracing::debug!("Teleport action triggered");
+        if let Err(e) = state_clone.cursor_finder.borrow_mut().teleport() {
+            tracing::warn!("Failed to move cursor: {}", e);
+        }
+    });
+    app.add_action(&teleport_action);
+
+    let follow_action = gio::SimpleAction::new_stateful("follow-mode", None, &false.to_variant());
+    let state_clone = state.clone();
+    follow_action.connect_activate(move |action, _| {
+        let mut finder = state_clone.cursor_finder.borrow_mut();
+        let follow = !finder.is_follow_mode();
+        finder.set_follow_mode(follow);
+        let follow_mode = finder.is_follow_mode();
+        action.set_state(&follow_mode.to_variant());
+        state_clone
+            .tray_icon
+            .borrow()
+            .update(|tray| tray.follow_mode = follow_mode);
+    });
+    app.add_action(&follow_action);
+
+    let next_preset_action = gio::SimpleAction::new("next-preset", None);
+    let state_clone = state.clone();
+    next_preset_action.connect_activate(move |_, _| {
+        state_clone.preferences.update(|p| {
+            if let Some(name) = p.apply_next_preset() {
+                tracing::info!("Switched to preset \"{}\"", name);
+            }
+        });
+    });
+    app.add_action(&next_preset_action);
+
+    // Takes the preset name; also used by `cursorhome preset <name>`
+    let select_preset_action =
+        gio::SimpleAction::new("select-preset", Some(glib::VariantTy::STRING));
+    let state_clone = state.clone();
+    select_preset_action.connect_activate(move |_, parameter| {
+        let Some(name) = parameter.and_then(|v| v.get::<String>()) else {
+            return;
+        };
+        state_clone
+            .preferences
+            .update(|p| match p.preset_index(&name) {
+                Some(index) => {
+                    p.apply_preset(index);
+                    tracing::info!("Switched to preset \"{}\"", p.presets[index].name);
+                }
+                None => tracing::warn!("No preset named \"{}\"", name),
+            });
+    });
+    app.add_action(&select_preset_action);
+
     let settings_action = gio::SimpleAction::new("show-settings", None);
     let state_clone = state.clone();
     settings_action.connect_activate(move |_, _| {
@@ -163,8 +661,112 @@ fn setup_shortcuts(app: &Application, state: Rc<AppState>) {
     });
     app.add_action(&quit_action);
 
-    // Set accelerators
-    app.set_accels_for_action("app.find-cursor", &["<Primary><Shift>f"]);
+    // Set accelerators (shortcut actions are bound in apply_shortcuts)
     app.set_accels_for_action("app.show-settings", &["<Primary>comma"]);
     app.set_accels_for_action("app.quit", &["<Primary>q"]);
 }
+
+/// Bind the current shortcuts as application accelerators and, on Wayland,
+/// (re-)register them with the GlobalShortcuts portal
+fn apply_shortcuts(app: &Application, state: &Rc<AppState>) {
+    let bindings = state.preferences.with(|p| p.shortcuts.clone());
+
+    for action in ShortcutAction::all() {
+        let accels: Vec<&str> = bindings.get(*action).into_iter().collect();
+        app.set_accels_for_action(&format!("app.{}", action.id()), &accels);
+    }
+
+    if !is_wayland_session() {
+        tracing::debug!("Not a Wayland session, skipping GlobalShortcuts portal");
+        return;
+    }
+
+    // Shortcut ids match the application action names
+    let app = app.clone();
+    state
+        .global_shortcuts
+        .borrow_mut()
+        .start_with_callback(&bindings, move |action| {
+            app.activate_action(action.id(), None);
+        });
+}
+
+/// React to a preference change by updating the affected services
+fn apply_preferences(
+    app: &Application,
+    state: &Rc<AppState>,
+    old: &Preferences,
+    new: &Preferences,
+) {
+    if old.enabled != new.enabled {
+        tracing::info!(
+            "CursorHome {}",
+            if new.enabled { "enabled" } else { "disabled" }
+        );
+        if !new.enabled {
+            state.cursor_finder.borrow_mut().set_follow_mode(false);
+        }
+
+        let follow_mode = state.cursor_finder.borrow().is_follow_mode();
+        set_action_state(app, "toggle-enabled", new.enabled);
+        set_action_state(app, "follow-mode", follow_mode);
+        state.tray_icon.borrow().update(|tray| {
+            tray.enabled = new.enabled;
+            tray.follow_mode = follow_mode;
+        });
+    }
+
+    let active_preset = new.active_preset();
+    if old.cursor_style != new.cursor_style
+        || old.presets != new.presets
+        || old.active_preset() != active_preset
+    {
+        let presets = new.presets.iter().map(|p| p.name.clone()).collect();
+        let color = tray_color(state, &new.cursor_style.color);
+        state.tray_icon.borrow().update(|tray| {
+            tray.presets = presets;
+            tray.active_preset = active_preset;
+            tray.color = color;
+        });
+    }
+
+    if old.shake_sensitivity != new.shake_sensitivity {
+        state
+            .shake_detector
+            .borrow_mut()
+            .set_sensitivity(new.shake_sensitivity);
+    }
+
+    if old.shake_enabled != new.shake_enabled {
+        let mut detector = state.shake_detector.borrow_mut();
+        if new.shake_enabled {
+            if let Err(e) = detector.start() {
+                tracing::warn!("Failed to start shake detection: {}", e);
+            }
+        } else {
+            detector.stop();
+        }
+    }
+
+    if old.tap_trigger != new.tap_trigger {
+        setup_modifier_tap(state.clone());
+    }
+
+    if old.synergy_enabled != new.synergy_enabled {
+        setup_synergy_monitoring(state.clone());
+    }
+
+    if old.shortcuts != new.shortcuts {
+        apply_shortcuts(app, state);
+    }
+}
+
+/// Update the state of a stateful application action
+fn set_action_state(app: &Application, name: &str, value: bool) {
+    if let Some(action) = app
+        .lookup_action(name)
+        .and_then(|action| action.downcast::<gio::SimpleAction>().ok())
+    {
+        action.set_state(&value.to_variant());
+    }
+}
@@ -0,0 +1,142 @@
+//! Command-line interface
+//!
+//! Without arguments CursorHome starts normally. Most subcommands act on the
+//! running instance (or on the saved preferences if it is not running);
+//! `render-preview` works on its own, without a display, and the settings
+//! file commands work on the saved preferences.
+
+use crate::ui::{PreviewFormat, PreviewOptions};
+use anyhow::Result;
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: cursorhome [COMMAND]
+
+Commands:
+  preset <name>     Switch to a style preset (e.g. subtle, high-visibility, presenter)
+  render-preview    Render a style to PNG files, without a display:
+      --style <file>        Cursor style JSON (required)
+      --animation <file>    Animation style JSON (default animation if omitted)
+      --out <dir>           Directory to write to (required)
+      --frames <n>          Frames per animation period (default 30)
+      --apng                Write one animated preview.png instead of frame-NNN.png
+  export-settings <file>
+                    Write style and behavior settings to a file that
+                    conforms to the shared/models schemas
+  import-settings <file>
+                    Read settings from such a file
+  help              Show this message
+
+Run without a command to start CursorHome.";
+
+/// Frames rendered per animation period unless `--frames` says otherwise
+const DEFAULT_PREVIEW_FRAMES: u32 = 30;
+
+/// What the process was asked to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Start the application
+    Run,
+    /// Switch to the named style preset
+    Preset(String),
+    /// Render a style to image files
+    RenderPreview(PreviewOptions),
+    /// Write style and behavior settings to a file
+    ExportSettings(PathBuf),
+    /// Read settings from a file written by `ExportSettings`
+    ImportSettings(PathBuf),
+    /// Print usage
+    Help,
+}
+
+impl Command {
+    /// Parse the process arguments
+    pub fn from_args() -> Result<Self> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let Some(command) = args.next() else {
+            return Ok(Command::Run);
+        };
+
+        let command = match command.as_str() {
+            "preset" => {
+                let name: Vec<String> = args.by_ref().collect();
+                if name.is_empty() {
+                    anyhow::bail!("Missing preset name\n\n{}", USAGE);
+                }
+                // Allow unquoted names with spaces, e.g. `preset High Visibility`
+                Command::Preset(name.join(" "))
+            }
+            "render-preview" => Command::RenderPreview(Self::parse_preview(&mut args)?),
+            "export-settings" => Command::ExportSettings(Self::parse_file(&mut args)?),
+            "import-settings" => Command::ImportSettings(Self::parse_file(&mut args)?),
+            "help" | "--help" | "-h" => Command::Help,
+            other => anyhow::bail!("Unknown command \"{}\"\n\n{}", other, USAGE),
+        };
+
+        if let Some(extra) = args.next() {
+            anyhow::bail!("Unexpected argument \"{}\"\n\n{}", extra, USAGE);
+        }
+
+        Ok(command)
+    }
+
+    /// Parse the options of `render-preview`
+    fn parse_preview(args: &mut impl Iterator<Item = String>) -> Result<PreviewOptions> {
+        let mut style = None;
+        let mut animation = None;
+        let mut out_dir = None;
+        let mut frames = DEFAULT_PREVIEW_FRAMES;
+        let mut format = PreviewFormat::Frames;
+
+        while let Some(option) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow::anyhow!("Missing value for {}\n\n{}", option, USAGE))
+            };
+            match option.as_str() {
+                "--style" => style = Some(PathBuf::from(value()?)),
+                "--animation" => animation = Some(PathBuf::from(value()?)),
+                "--out" => out_dir = Some(PathBuf::from(value()?)),
+                "--frames" => {
+                    let count = value()?;
+                    frames = match count.parse() {
+                        Ok(count) if count > 0 => count,
+                        _ => anyhow::bail!("Invalid frame count \"{}\"\n\n{}", count, USAGE),
+                    };
+                }
+                "--apng" => format = PreviewFormat::Animated,
+                other => anyhow::bail!("Unknown option \"{}\"\n\n{}", other, USAGE),
+            }
+        }
+
+        let Some(style) = style else {
+            anyhow::bail!("Missing --style\n\n{}", USAGE);
+        };
+        let Some(out_dir) = out_dir else {
+            anyhow::bail!("Missing --out\n\n{}", USAGE);
+        };
+        Ok(PreviewOptions {
+            style,
+            animation,
+            out_dir,
+            frames,
+            format,
+        })
+    }
+
+    /// Parse the file argument of the settings file commands
+    fn parse_file(args: &mut impl Iterator<Item = String>) -> Result<PathBuf> {
+        match args.next() {
+            Some(path) => Ok(PathBuf::from(path)),
+            None => anyhow::bail!("Missing file name\n\n{}", USAGE),
+        }
+    }
+
+    /// Usage text
+    pub fn usage() -> &'static str {
+        USAGE
+    }
+}
@@ -4,22 +4,45 @@
 //! cross-machine cursor tracking.
 
 mod app;
+mod cli;
 mod models;
 mod services;
+#[cfg(test)]
+mod test_bus;
 mod ui;
 
 use anyhow::Result;
+use cli::Command;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 fn main() -> Result<()> {
+    let command = Command::from_args()?;
+
     // Initialize logging
     tracing_subscriber::registry()
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    tracing::info!("Starting CursorHome for Linux");
+    match command {
+        Command::Run => {
+            tracing::info!("Starting CursorHome for Linux");
 
-    // Run the GTK application
-    app::run()
+            // Run the GTK application
+            app::run()
+        }
+        Command::Preset(name) => app::select_preset(&name),
+        Command::RenderPreview(options) => {
+            for path in ui::render_preview(&options)? {
+                println!("{}", path.display());
+            }
+            Ok(())
+        }
+        Command::ExportSettings(path) => app::export_settings(&path),
+        Command::ImportSettings(path) => app::import_settings(&path),
+        Command::Help => {
+            println!("{}", Command::usage());
+            Ok(())
+        }
+    }
 }
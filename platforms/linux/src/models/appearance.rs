@@ -0,0 +1,243 @@
+//! Desktop accent color, light/dark preference and accessibility settings
+
+use super::{AnimationStyle, AnimationType, Color, CursorStyle, Layer, Paint, Pattern, Shape};
+
+/// Highlight color under high contrast, drawn over a black outline
+const HIGH_CONTRAST_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 0,
+    a: 1.0,
+};
+
+/// Width of the black outline around each shape under high contrast, in
+/// pixels
+const OUTLINE_WIDTH: f64 = 3.0;
+
+/// The desktop's preferred color scheme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorScheme {
+    #[default]
+    NoPreference,
+    Dark,
+    Light,
+}
+
+/// Appearance settings shared by the desktop
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DesktopAppearance {
+    /// Accent color, if the desktop has one
+    pub accent_color: Option<Color>,
+    pub color_scheme: ColorScheme,
+    /// The desktop asks for animations to be kept to a minimum
+    pub reduce_motion: bool,
+    /// The desktop uses a high-contrast theme
+    pub high_contrast: bool,
+}
+
+impl DesktopAppearance {
+    /// Highlight color for an accent paint, with `fallback` for desktops
+    /// without an accent color
+    ///
+    /// Dark accents are lightened under a dark scheme and light accents
+    /// darkened under a light one, so the highlight stands out from windows
+    /// drawn in that scheme.
+    pub fn highlight_color(&self, fallback: Color) -> Color {
+        let color = match self.accent_color {
+            Some(accent) => Color::new(accent.r, accent.g, accent.b, fallback.a),
+            None => fallback,
+        };
+
+        let white = Color::new(255, 255, 255, color.a);
+        let black = Color::new(0, 0, 0, color.a);
+        match self.color_scheme {
+            ColorScheme::Dark if color.luminance() < 0.2 => color.mix(&white, 0.4),
+            ColorScheme::Light if color.luminance() > 0.6 => color.mix(&black, 0.4),
+            _ => color,
+        }
+    }
+
+    /// Replace accent paints in `style` with the current accent color
+    pub fn apply_to(&self, style: &mut CursorStyle) {
+        let resolve = |paint: &mut Paint| {
+            if let Paint::Pattern(Pattern::Accent { color }) = *paint {
+                *paint = Paint::Solid(self.highlight_color(color));
+            }
+        };
+
+        resolve(&mut style.color);
+        for layer in &mut style.layers {
+            resolve(&mut layer.color);
+        }
+    }
+    /// Make `style` static under reduced motion and opaque yellow on black
+    /// under high contrast
+    pub fn apply_accessibility(&self, style: &mut CursorStyle, animation: &mut AnimationStyle) {
+        if self.reduce_motion {
+            animation.animation_type = AnimationType::None;
+
+            // Color cycles move too, so hold them at their first color
+            let freeze = |paint: &mut Paint| {
+                if paint.is_cycling() {
+                    *paint = Paint::Solid(paint.base_color());
+                }
+            };
+            freeze(&mut style.color);
+            for layer in &mut style.layers {
+                freeze(&mut layer.color);
+            }
+        }
+
+        if self.high_contrast {
+            style.glow_enabled = false;
+            style.layers = style
+                .effective_layers()
+                .into_iter()
+                .flat_map(high_contrast_layers)
+                .collect();
+        }
+    }
+}
+
+/// A layer in the high-contrast color over a black outline; images are
+/// left as they are
+fn high_contrast_layers(layer: Layer) -> Vec<Layer> {
+    if matches!(layer.shape, Shape::Image(_)) {
+        return vec![layer];
+    }
+
+    // A larger copy with a thicker line, so the outline shows on both sides
+    // of strokes (which are centered on the same path) and around fills
+    let outline = Layer {
+        size: layer.size + OUTLINE_WIDTH * 2.0,
+        color: Paint::Solid(Color::new(0, 0, 0, 1.0)),
+        border_weight: layer.border_weight + OUTLINE_WIDTH * 2.0,
+        ..layer.clone()
+    };
+    let layer = Layer {
+        color: Paint::Solid(HIGH_CONTRAST_COLOR),
+        ..layer
+    };
+    vec![outline, layer]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ImageShape;
+
+    const BLACK: Color = Color {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 1.0,
+    };
+
+    fn layer(shape: Shape) -> Layer {
+        Layer {
+            shape,
+            size: 60.0,
+            border_weight: 4.0,
+            ..Layer::default()
+        }
+    }
+
+    #[test]
+    fn high_contrast_outlines_every_shape_on_both_sides() {
+        for shape in [
+            Shape::Circle,
+            Shape::Ring,
+            Shape::Crosshair,
+            Shape::Spotlight,
+            Shape::Reticle,
+            Shape::RoundedSquare,
+            Shape::Starburst,
+            Shape::PointerArrows,
+        ] {
+            let layers = high_contrast_layers(layer(shape.clone()));
+            let [outline, top] = layers.as_slice() else {
+                panic!("{:?} gave {} layers", shape, layers.len());
+            };
+
+            assert_eq!(outline.shape, shape);
+            assert_eq!(outline.color, Paint::Solid(BLACK));
+            assert_eq!(outline.size, 60.0 + OUTLINE_WIDTH * 2.0);
+            assert_eq!(outline.border_weight, 4.0 + OUTLINE_WIDTH * 2.0);
+            assert_eq!(top.color, Paint::Solid(HIGH_CONTRAST_COLOR));
+            assert_eq!((top.size, top.border_weight), (60.0, 4.0));
+
+            // Strokes are centered at `size / 2 - border_weight / 2`, so the
+            // outline's stroke reaches OUTLINE_WIDTH past the top one's edges
+            let center = |l: &Layer| l.size / 2.0 - l.border_weight / 2.0;
+            assert_eq!(center(outline), center(top));
+            assert_eq!(outline.size / 2.0 - top.size / 2.0, OUTLINE_WIDTH);
+        }
+    }
+
+    #[test]
+    fn high_contrast_leaves_images_alone() {
+        let image = layer(Shape::Image(ImageShape {
+            path: "/tmp/logo.png".into(),
+            tint: false,
+        }));
+        assert_eq!(high_contrast_layers(image.clone()), vec![image]);
+    }
+
+    #[test]
+    fn high_contrast_replaces_glow_and_layers() {
+        let appearance = DesktopAppearance {
+            high_contrast: true,
+            ..Default::default()
+        };
+        let mut style = CursorStyle {
+            glow_enabled: true,
+            ..CursorStyle::default()
+        };
+        let mut animation = AnimationStyle::default();
+        appearance.apply_accessibility(&mut style, &mut animation);
+
+        assert!(!style.glow_enabled);
+        assert_eq!(style.layers.len(), 2);
+        assert_eq!(style.layers[0].color, Paint::Solid(BLACK));
+        assert_eq!(style.layers[1].color, Paint::Solid(HIGH_CONTRAST_COLOR));
+        assert_eq!(animation, AnimationStyle::default());
+    }
+
+    #[test]
+    fn reduced_motion_freezes_animation_and_cycling_paints() {
+        let appearance = DesktopAppearance {
+            reduce_motion: true,
+            ..Default::default()
+        };
+        let cycle = Paint::Pattern(Pattern::HueCycle {
+            period: 2.0,
+            saturation: 1.0,
+            value: 1.0,
+            alpha: 0.5,
+        });
+        let gradient = Paint::Pattern(Pattern::RadialGradient { stops: Vec::new() });
+        let mut style = CursorStyle {
+            color: cycle.clone(),
+            layers: vec![
+                Layer {
+                    color: cycle.clone(),
+                    ..Layer::default()
+                },
+                Layer {
+                    color: gradient.clone(),
+                    ..Layer::default()
+                },
+            ],
+            ..CursorStyle::default()
+        };
+        let mut animation = AnimationStyle::default();
+        appearance.apply_accessibility(&mut style, &mut animation);
+
+        assert_eq!(animation.animation_type, AnimationType::None);
+        let frozen = Paint::Solid(cycle.base_color());
+        assert_eq!(style.color, frozen);
+        assert_eq!(style.layers[0].color, frozen);
+        assert_eq!(style.layers[1].color, gradient);
+        assert!(!style.color.is_cycling());
+    }
+}
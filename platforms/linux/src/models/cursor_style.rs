@@ -1,9 +1,11 @@
 //! Cursor highlight style definitions
 
+use super::Paint;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Shape of the cursor highlight
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum Shape {
     Circle,
@@ -11,11 +13,42 @@ pub enum Shape {
     Ring,
     Crosshair,
     Spotlight,
+    /// A ring with tick marks and a gap at the center
+    Reticle,
+    #[serde(rename = "rounded_square")]
+    RoundedSquare,
+    /// Rays of alternating length around the cursor
+    Starburst,
+    /// Arrows from the center of each monitor toward the cursor
+    #[serde(rename = "pointer_arrows")]
+    PointerArrows,
+    /// A PNG or SVG file, written as `{ "image": { "path": ... } }`
+    Image(ImageShape),
+}
+
+/// A picture drawn in place of a built-in shape
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageShape {
+    pub path: PathBuf,
+    /// Fill the picture's opaque parts with the highlight color instead of
+    /// drawing its own colors
+    #[serde(default)]
+    pub tint: bool,
 }
 
 impl Shape {
+    /// The built-in shapes
     pub fn all() -> &'static [Shape] {
-        &[Shape::Circle, Shape::Ring, Shape::Crosshair, Shape::Spotlight]
+        &[
+            Shape::Circle,
+            Shape::Ring,
+            Shape::Crosshair,
+            Shape::Spotlight,
+            Shape::Reticle,
+            Shape::RoundedSquare,
+            Shape::Starburst,
+            Shape::PointerArrows,
+        ]
     }
 
     pub fn display_name(&self) -> &'static str {
@@ -24,12 +57,18 @@ impl Shape {
             Shape::Ring => "Ring",
             Shape::Crosshair => "Crosshair",
             Shape::Spotlight => "Spotlight",
+            Shape::Reticle => "Reticle",
+            Shape::RoundedSquare => "Rounded Square",
+            Shape::Starburst => "Star Burst",
+            Shape::PointerArrows => "Pointer Arrows",
+            Shape::Image(_) => "Image",
         }
     }
 }
 
 /// RGBA color
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -54,6 +93,16 @@ impl Color {
         Self { r, g, b, a }
     }
 
+    /// Convert from GTK RGBA format
+    pub fn from_gdk_rgba(rgba: &gtk4::gdk::RGBA) -> Self {
+        Self {
+            r: (rgba.red() * 255.0).round() as u8,
+            g: (rgba.green() * 255.0).round() as u8,
+            b: (rgba.blue() * 255.0).round() as u8,
+            a: rgba.alpha(),
+        }
+    }
+
     /// Convert to GTK RGBA format
     pub fn to_gdk_rgba(&self) -> gtk4::gdk::RGBA {
         gtk4::gdk::RGBA::new(
@@ -82,6 +131,75 @@ impl Color {
             self.a as f64,
         )
     }
+
+    /// Build from hue, saturation and value, each 0.0-1.0
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64, a: f32) -> Self {
+        let h = hue.rem_euclid(1.0) * 6.0;
+        let c = value * saturation;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = value - c;
+        let channel = |v: f64| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Self::new(channel(r), channel(g), channel(b), a)
+    }
+
+    /// Hue, saturation and value, each 0.0-1.0
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.to_cairo_rgb();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            ((g - b) / delta).rem_euclid(6.0) / 6.0
+        } else if max == g {
+            ((b - r) / delta + 2.0) / 6.0
+        } else {
+            ((r - g) / delta + 4.0) / 6.0
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+        (hue, saturation, max)
+    }
+
+    /// Relative luminance (WCAG), 0.0 for black to 1.0 for white
+    pub fn luminance(&self) -> f64 {
+        let linear = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// Contrast ratio (WCAG) against `other`, from 1.0 to 21.0
+    pub fn contrast_ratio(&self, other: &Color) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Blend towards `other` by `t` (0.0 = self, 1.0 = other)
+    pub fn mix(&self, other: &Color, t: f64) -> Self {
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Self {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: self.a + (other.a - self.a) * t as f32,
+        }
+    }
 }
 
 /// Border style for ring and crosshair shapes
@@ -94,17 +212,88 @@ pub enum BorderStyle {
     Dotted,
 }
 
+impl BorderStyle {
+    pub fn all() -> &'static [BorderStyle] {
+        &[BorderStyle::Solid, BorderStyle::Dashed, BorderStyle::Dotted]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            BorderStyle::Solid => "Solid",
+            BorderStyle::Dashed => "Dashed",
+            BorderStyle::Dotted => "Dotted",
+        }
+    }
+
+    /// Dash pattern in multiples of the line width, empty for solid lines
+    pub fn dash_pattern(&self) -> &'static [f64] {
+        match self {
+            BorderStyle::Solid => &[],
+            BorderStyle::Dashed => &[3.0, 2.0],
+            BorderStyle::Dotted => &[1.0, 1.0],
+        }
+    }
+}
+
+/// Lines through the cursor that run to the edges of the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GuideLines {
+    #[default]
+    Off,
+    /// Across every monitor
+    Desktop,
+    /// Across the monitor the cursor is on
+    Monitor,
+}
+
+impl GuideLines {
+    pub fn all() -> &'static [GuideLines] {
+        &[GuideLines::Off, GuideLines::Desktop, GuideLines::Monitor]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            GuideLines::Off => "Off",
+            GuideLines::Desktop => "Across All Monitors",
+            GuideLines::Monitor => "Current Monitor Only",
+        }
+    }
+}
+
+/// One shape of a layered highlight
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layer {
+    pub shape: Shape,
+    pub size: f64,
+    pub color: Paint,
+    pub border_weight: f64,
+    pub border_style: BorderStyle,
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        CursorStyle::default().base_layer()
+    }
+}
+
 /// Cursor highlight style configuration
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CursorStyle {
     pub shape: Shape,
     pub size: f64,
-    pub color: Color,
+    pub color: Paint,
     pub border_weight: f64,
     pub border_style: BorderStyle,
     pub glow_enabled: bool,
     pub glow_intensity: f64,
     pub glow_radius: f64,
+    /// Shapes drawn in order, bottom first, instead of the single shape above
+    pub layers: Vec<Layer>,
+    /// Full-length lines through the cursor, drawn like the top layer
+    pub guide_lines: GuideLines,
 }
 
 impl Default for CursorStyle {
@@ -112,14 +301,49 @@ impl Default for CursorStyle {
         Self {
             shape: Shape::Ring,
             size: 60.0,
-            color: Color::default(),
+            color: Paint::default(),
             border_weight: 4.0,
             border_style: BorderStyle::Solid,
             glow_enabled: true,
             glow_intensity: 0.5,
             glow_radius: 10.0,
+            layers: Vec::new(),
+            guide_lines: GuideLines::Off,
+        }
+    }
+}
+
+impl CursorStyle {
+    /// The single shape described by the top-level fields
+    pub fn base_layer(&self) -> Layer {
+        Layer {
+            shape: self.shape.clone(),
+            size: self.size,
+            color: self.color.clone(),
+            border_weight: self.border_weight,
+            border_style: self.border_style,
+        }
+    }
+
+    /// Layers to draw, bottom first
+    ///
+    /// Styles without layers (including every file written before layers
+    /// existed) draw their single top-level shape.
+    pub fn effective_layers(&self) -> Vec<Layer> {
+        if self.layers.is_empty() {
+            vec![self.base_layer()]
+        } else {
+            self.layers.clone()
         }
     }
+
+    /// Diameter of the largest layer, which the glow surrounds
+    pub fn outer_size(&self) -> f64 {
+        self.effective_layers()
+            .iter()
+            .map(|layer| layer.size)
+            .fold(0.0, f64::max)
+    }
 }
 
 /// Animation type
@@ -168,6 +392,24 @@ pub enum Easing {
 }
 
 impl Easing {
+    pub fn all() -> &'static [Easing] {
+        &[
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Easing::Linear => "Linear",
+            Easing::EaseIn => "Ease In",
+            Easing::EaseOut => "Ease Out",
+            Easing::EaseInOut => "Ease In and Out",
+        }
+    }
+
     /// Apply easing function to a progress value (0.0 to 1.0)
     pub fn apply(&self, t: f64) -> f64 {
         match self {
@@ -187,6 +429,7 @@ impl Easing {
 
 /// Animation style configuration
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AnimationStyle {
     pub animation_type: AnimationType,
     pub duration: f64,
@@ -206,3 +449,37 @@ impl Default for AnimationStyle {
         }
     }
 }
+
+impl AnimationStyle {
+    /// Seconds until the animation looks the same again: one cycle, or two
+    /// when it plays back in reverse
+    pub fn period(&self) -> f64 {
+        if self.auto_reverse {
+            self.duration * 2.0
+        } else {
+            self.duration
+        }
+    }
+
+    /// Eased progress through the current cycle, `elapsed` seconds in
+    pub fn progress_at(&self, elapsed: f64) -> f64 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+
+        let raw_progress = (elapsed % self.duration) / self.duration;
+
+        let progress = if self.auto_reverse {
+            let cycle = (elapsed / self.duration) as u32;
+            if cycle % 2 == 1 {
+                1.0 - raw_progress
+            } else {
+                raw_progress
+            }
+        } else {
+            raw_progress
+        };
+
+        self.easing.apply(progress)
+    }
+}
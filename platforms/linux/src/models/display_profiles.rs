@@ -0,0 +1,64 @@
+//! Style presets chosen automatically by monitor layout
+
+use super::Preferences;
+use serde::{Deserialize, Serialize};
+
+/// Applies a style preset whenever a particular set of monitors is connected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplayProfile {
+    /// Layout fingerprint: connector names and resolutions, sorted, e.g.
+    /// `DP-1:2560x1440,DP-2:2560x1440,eDP-1:1920x1200`
+    pub layout: String,
+
+    /// Name of the preset to switch to
+    pub preset: String,
+}
+
+impl DisplayProfile {
+    /// Human-readable form of the layout, e.g. "DP-1 2560×1440, eDP-1 1920×1200"
+    pub fn describe_layout(layout: &str) -> String {
+        if layout.is_empty() {
+            return "No monitors".to_string();
+        }
+
+        layout
+            .split(',')
+            .map(|monitor| match monitor.rsplit_once(':') {
+                Some((connector, size)) => format!("{} {}", connector, size.replace('x', "×")),
+                None => monitor.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Preferences {
+    /// Preset configured for a monitor layout, if any
+    pub fn preset_for_layout(&self, layout: &str) -> Option<&str> {
+        self.display_profiles
+            .iter()
+            .find(|profile| profile.layout == layout)
+            .map(|profile| profile.preset.as_str())
+    }
+
+    /// Use `preset` whenever `layout` is connected, or stop switching
+    /// presets for that layout with `None`
+    pub fn set_preset_for_layout(&mut self, layout: &str, preset: Option<String>) {
+        let existing = self
+            .display_profiles
+            .iter()
+            .position(|profile| profile.layout == layout);
+
+        match (existing, preset) {
+            (Some(index), Some(preset)) => self.display_profiles[index].preset = preset,
+            (Some(index), None) => {
+                self.display_profiles.remove(index);
+            }
+            (None, Some(preset)) => self.display_profiles.push(DisplayProfile {
+                layout: layout.to_string(),
+                preset,
+            }),
+            (None, None) => {}
+        }
+    }
+}
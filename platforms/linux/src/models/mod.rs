@@ -1,7 +1,34 @@
 //! Data models for CursorHome
 
+mod appearance;
 mod cursor_style;
+mod display_profiles;
+mod notifications;
+mod paint;
 mod preferences;
+mod preferences_store;
+mod presets;
+mod schema;
+mod shared_settings;
+mod shortcuts;
+mod transition_label;
+mod triggers;
 
-pub use cursor_style::{AnimationStyle, AnimationType, CursorStyle, Easing, Shape};
+pub use appearance::{ColorScheme, DesktopAppearance};
+pub use cursor_style::{
+    AnimationStyle, AnimationType, BorderStyle, Color, CursorStyle, Easing, GuideLines, ImageShape,
+    Layer, Shape,
+};
+pub use display_profiles::DisplayProfile;
+pub use notifications::Notifications;
+pub use paint::{ColorStop, Paint, Pattern};
 pub use preferences::Preferences;
+pub use preferences_store::{PreferencesStore, SubscriptionId};
+pub use presets::StylePreset;
+pub use schema::{Correction, LoadReport};
+pub use shared_settings::SharedSettings;
+pub use shortcuts::{
+    ShortcutAction, ShortcutBindings, TapModifier, TapTrigger, RESERVED_ACCELERATORS,
+};
+pub use transition_label::{LabelPosition, TransitionLabel};
+pub use triggers::{HighlightStyle, StyleOverride, Trigger, TriggerStyles};
@@ -0,0 +1,29 @@
+//! Which desktop notifications CursorHome shows
+
+use serde::{Deserialize, Serialize};
+
+/// Categories of desktop notifications, each of which can be turned off
+///
+/// Problems with the preferences file are always reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Notifications {
+    /// The cursor moving to or returning from another Synergy machine
+    pub transitions: bool,
+
+    /// The Synergy log could not be found, or stopped being written
+    pub synergy_log: bool,
+
+    /// The highlight overlay could not be shown
+    pub overlay_errors: bool,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            transitions: false,
+            synergy_log: true,
+            overlay_errors: true,
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! Paints for filling and stroking highlight shapes
+
+use super::Color;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// How a highlight shape is colored
+///
+/// A plain color object is a solid paint, so files written before paints
+/// existed keep loading. Other paints are objects with a `type` field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Paint {
+    Solid(Color),
+    Pattern(Pattern),
+}
+
+impl<'de> Deserialize<'de> for Paint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Decide by the `type` field rather than trying each variant, so a
+        // mistyped gradient is reported instead of loading as a default color
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let paint = if value.get("type").is_some() {
+            Pattern::deserialize(value).map(Paint::Pattern)
+        } else {
+            Color::deserialize(value).map(Paint::Solid)
+        };
+        paint.map_err(serde::de::Error::custom)
+    }
+}
+
+/// Paints other than a solid color
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Pattern {
+    /// Colors blended along a line across the shape
+    LinearGradient {
+        stops: Vec<ColorStop>,
+        /// Direction in degrees, 0 running left to right, 90 top to bottom
+        #[serde(default)]
+        angle: f64,
+    },
+    /// Colors blended from the center of the shape to its edge
+    RadialGradient { stops: Vec<ColorStop> },
+    /// One color that cycles through every hue
+    HueCycle {
+        /// Seconds for one trip around the color wheel
+        #[serde(default = "default_hue_period")]
+        period: f64,
+        #[serde(default = "default_unit")]
+        saturation: f64,
+        #[serde(default = "default_unit")]
+        value: f64,
+        #[serde(default = "default_alpha")]
+        alpha: f32,
+    },
+    /// The desktop's accent color, adjusted for its light or dark scheme
+    Accent {
+        /// Color used when the desktop has no accent color
+        #[serde(default)]
+        color: Color,
+    },
+    /// A color that changes to stand out from what is under the cursor
+    Adaptive {
+        /// Color used whenever it already contrasts with the background
+        #[serde(default)]
+        color: Color,
+    },
+}
+
+fn default_hue_period() -> f64 {
+    3.0
+}
+
+fn default_unit() -> f64 {
+    1.0
+}
+
+fn default_alpha() -> f32 {
+    1.0
+}
+
+/// A color at a position along a gradient
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorStop {
+    /// Position from 0.0 (start) to 1.0 (end)
+    pub offset: f64,
+    pub color: Color,
+}
+
+impl Default for Paint {
+    fn default() -> Self {
+        Paint::Solid(Color::default())
+    }
+}
+
+impl From<Color> for Paint {
+    fn from(color: Color) -> Self {
+        Paint::Solid(color)
+    }
+}
+
+impl Paint {
+    /// A single color standing in for this paint, e.g. for the tray icon
+    /// and the settings color button
+    pub fn base_color(&self) -> Color {
+        self.sample(0.0, 0.0, 0.0)
+    }
+
+    /// Color at a point of a shape at `elapsed` seconds into the highlight
+    ///
+    /// `x` and `y` run from -1.0 to 1.0 across the shape's bounding box.
+    pub fn sample(&self, x: f64, y: f64, elapsed: f64) -> Color {
+        match self {
+            Paint::Solid(color) => *color,
+            Paint::Pattern(Pattern::LinearGradient { stops, angle }) => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let along = x * cos + y * sin;
+                interpolate(stops, (along + 1.0) / 2.0)
+            }
+            Paint::Pattern(Pattern::RadialGradient { stops }) => {
+                interpolate(stops, (x * x + y * y).sqrt())
+            }
+            Paint::Pattern(Pattern::HueCycle {
+                period,
+                saturation,
+                value,
+                alpha,
+            }) => {
+                let hue = if *period > 0.0 {
+                    (elapsed / period).fract()
+                } else {
+                    0.0
+                };
+                Color::from_hsv(hue, *saturation, *value, *alpha)
+            }
+            // Replaced with the desktop's accent color before drawing
+            Paint::Pattern(Pattern::Accent { color }) => *color,
+            // Renderers that can see the screen replace this per frame
+            Paint::Pattern(Pattern::Adaptive { color }) => *color,
+        }
+    }
+
+    /// Whether the color follows the desktop's accent color
+    pub fn is_accent(&self) -> bool {
+        matches!(self, Paint::Pattern(Pattern::Accent { .. }))
+    }
+
+    /// Whether the color depends on the pixels under the cursor
+    pub fn is_adaptive(&self) -> bool {
+        matches!(self, Paint::Pattern(Pattern::Adaptive { .. }))
+    }
+
+    /// Whether the color changes over time
+    pub fn is_cycling(&self) -> bool {
+        matches!(self, Paint::Pattern(Pattern::HueCycle { .. }))
+    }
+
+    /// Whether the color differs across the shape
+    pub fn is_gradient(&self) -> bool {
+        matches!(
+            self,
+            Paint::Pattern(Pattern::LinearGradient { .. } | Pattern::RadialGradient { .. })
+        )
+    }
+
+    /// Gradient stops, sorted by offset, for gradient paints
+    pub fn stops(&self) -> Option<Vec<ColorStop>> {
+        match self {
+            Paint::Pattern(
+                Pattern::LinearGradient { stops, .. } | Pattern::RadialGradient { stops },
+            ) => {
+                let mut stops = stops.clone();
+                stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+                Some(stops)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Blend the stops on either side of `position`
+fn interpolate(stops: &[ColorStop], position: f64) -> Color {
+    let mut sorted = stops.to_vec();
+    sorted.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+    let position = position.clamp(0.0, 1.0);
+    let (Some(first), Some(last)) = (sorted.first(), sorted.last()) else {
+        return Color::default();
+    };
+    if position <= first.offset {
+        return first.color;
+    }
+    if position >= last.offset {
+        return last.color;
+    }
+
+    sorted
+        .windows(2)
+        .find(|pair| position <= pair[1].offset)
+        .map(|pair| {
+            let span = pair[1].offset - pair[0].offset;
+            let t = if span > 0.0 {
+                (position - pair[0].offset) / span
+            } else {
+                1.0
+            };
+            pair[0].color.mix(&pair[1].color, t)
+        })
+        .unwrap_or(last.color)
+}
+
+/// Minimum contrast ratio for the preferred adaptive color to be kept
+const MIN_CONTRAST: f64 = 3.0;
+
+/// Minimum hue difference (as a fraction of the color wheel) from a
+/// saturated background for the preferred adaptive color to be kept
+const MIN_HUE_DISTANCE: f64 = 0.1;
+
+/// Background saturation below which hue differences are ignored
+const GREY_SATURATION: f64 = 0.2;
+
+/// Steps for blending an adaptive color towards black or white
+const SHADE_STEPS: u32 = 10;
+
+impl Color {
+    /// A color that stands out from `background`: this one when it
+    /// already does, otherwise one of opposite hue or brightness
+    pub fn contrasting(&self, background: &Color) -> Color {
+        let (hue, saturation, _) = self.to_hsv();
+        let (bg_hue, bg_saturation, _) = background.to_hsv();
+
+        let hue_distance = {
+            let d = (hue - bg_hue).abs();
+            d.min(1.0 - d)
+        };
+        let same_hue = bg_saturation >= GREY_SATURATION && hue_distance < MIN_HUE_DISTANCE;
+        if !same_hue && self.contrast_ratio(background) >= MIN_CONTRAST {
+            return *self;
+        }
+
+        // Opposite hue on colored backgrounds, the preferred hue on grey ones
+        let hue = if bg_saturation >= GREY_SATURATION {
+            bg_hue + 0.5
+        } else {
+            hue
+        };
+
+        // Above this luminance black contrasts more than white does
+        let (value, extreme) = if background.luminance() > 0.18 {
+            (0.35, Color::new(0, 0, 0, self.a))
+        } else {
+            (1.0, Color::new(255, 255, 255, self.a))
+        };
+        let shade = Color::from_hsv(hue, saturation.max(0.8), value, self.a);
+
+        // Saturated colors can be as bright as a mid grey, so blend towards
+        // black or white, which always contrast enough, until it stands out
+        (0..=SHADE_STEPS)
+            .map(|step| shade.mix(&extreme, step as f64 / SHADE_STEPS as f64))
+            .find(|color| color.contrast_ratio(background) >= MIN_CONTRAST)
+            .unwrap_or(extreme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color {
+        r: 255,
+        g: 0,
+        b: 0,
+        a: 1.0,
+    };
+    const BLUE: Color = Color {
+        r: 0,
+        g: 0,
+        b: 255,
+        a: 1.0,
+    };
+
+    fn stop(offset: f64, color: Color) -> ColorStop {
+        ColorStop { offset, color }
+    }
+
+    #[test]
+    fn interpolate_sorts_stops_by_offset() {
+        let stops = [stop(1.0, BLUE), stop(0.0, RED)];
+        assert_eq!(interpolate(&stops, 0.0), RED);
+        assert_eq!(interpolate(&stops, 1.0), BLUE);
+        assert_eq!(interpolate(&stops, 0.5), RED.mix(&BLUE, 0.5));
+    }
+
+    #[test]
+    fn interpolate_clamps_outside_the_stops() {
+        let stops = [stop(0.25, RED), stop(0.75, BLUE)];
+        assert_eq!(interpolate(&stops, -1.0), RED);
+        assert_eq!(interpolate(&stops, 0.1), RED);
+        assert_eq!(interpolate(&stops, 0.9), BLUE);
+        assert_eq!(interpolate(&stops, 2.0), BLUE);
+        assert_eq!(interpolate(&stops, 0.5), RED.mix(&BLUE, 0.5));
+    }
+
+    #[test]
+    fn interpolate_with_one_or_no_stops() {
+        assert_eq!(interpolate(&[stop(0.5, BLUE)], 0.0), BLUE);
+        assert_eq!(interpolate(&[stop(0.5, BLUE)], 1.0), BLUE);
+        assert_eq!(interpolate(&[], 0.5), Color::default());
+    }
+
+    #[test]
+    fn gradients_sample_across_the_shape() {
+        let stops = vec![stop(0.0, RED), stop(1.0, BLUE)];
+        let linear = Paint::Pattern(Pattern::LinearGradient {
+            stops: stops.clone(),
+            angle: 90.0,
+        });
+        assert_eq!(linear.sample(0.0, -1.0, 0.0), RED);
+        assert_eq!(linear.sample(0.0, 1.0, 0.0), BLUE);
+
+        let radial = Paint::Pattern(Pattern::RadialGradient { stops });
+        assert_eq!(radial.sample(0.0, 0.0, 0.0), RED);
+        // Corners of the bounding box lie past the edge
+        assert_eq!(radial.sample(1.0, 1.0, 0.0), BLUE);
+    }
+
+    #[test]
+    fn hue_cycle_wraps_around_each_period() {
+        let paint = Paint::Pattern(Pattern::HueCycle {
+            period: 2.0,
+            saturation: 1.0,
+            value: 1.0,
+            alpha: 1.0,
+        });
+        assert_eq!(paint.sample(0.0, 0.0, 0.0), RED);
+        assert_eq!(paint.sample(0.0, 0.0, 2.0), RED);
+        assert_eq!(paint.sample(0.0, 0.0, 5.0), paint.sample(0.0, 0.0, 1.0));
+        assert_ne!(paint.sample(0.0, 0.0, 1.0), RED);
+    }
+
+    #[test]
+    fn bare_colors_parse_as_solid_paints() {
+        let paint: Paint =
+            serde_json::from_str(r#"{ "r": 0, "g": 0, "b": 255, "a": 1.0 }"#).unwrap();
+        assert_eq!(paint, Paint::Solid(BLUE));
+        assert_eq!(serde_json::to_value(&paint).unwrap()["b"], 255);
+
+        let paint: Paint = serde_json::from_str(r#"{ "type": "hue_cycle" }"#).unwrap();
+        assert!(paint.is_cycling());
+    }
+
+    #[test]
+    fn contrasting_colors_stand_out_from_the_background() {
+        let orange = Color::new(255, 149, 0, 1.0);
+        let backgrounds = [
+            ("orange", orange),
+            ("white", Color::new(255, 255, 255, 1.0)),
+            ("black", Color::new(0, 0, 0, 1.0)),
+            ("mid grey", Color::new(128, 128, 128, 1.0)),
+            ("dark grey", Color::new(100, 100, 100, 1.0)),
+            ("blue", BLUE),
+        ];
+        let preferred = [orange, RED, BLUE, Color::new(128, 128, 128, 0.5)];
+
+        for (name, background) in backgrounds {
+            for color in preferred {
+                let result = color.contrasting(&background);
+                let ratio = result.contrast_ratio(&background);
+                assert!(
+                    ratio >= MIN_CONTRAST,
+                    "{:?} on {} gave {:?}, contrast {:.2}",
+                    color,
+                    name,
+                    result,
+                    ratio
+                );
+                assert_eq!(result.a, color.a);
+            }
+        }
+    }
+
+    #[test]
+    fn contrasting_keeps_colors_that_already_stand_out() {
+        let orange = Color::new(255, 149, 0, 1.0);
+        let black = Color::new(0, 0, 0, 1.0);
+        assert_eq!(orange.contrasting(&black), orange);
+        assert_eq!(BLUE.contrasting(&Color::new(255, 255, 255, 1.0)), BLUE);
+
+        // Bright enough against orange, but the same hue
+        let on_orange = Color::new(100, 60, 0, 1.0).contrasting(&orange);
+        let (hue, _, _) = on_orange.to_hsv();
+        let (orange_hue, _, _) = orange.to_hsv();
+        let distance = (hue - orange_hue).abs();
+        assert!(distance.min(1.0 - distance) >= MIN_HUE_DISTANCE);
+    }
+
+    #[test]
+    fn mistyped_patterns_are_errors() {
+        assert!(serde_json::from_str::<Paint>(r#"{ "type": "linear_gradient" }"#).is_err());
+        assert!(serde_json::from_str::<Paint>(r#"{ "type": "plaid" }"#).is_err());
+    }
+}
@@ -1,14 +1,26 @@
 //! User preferences storage
 
-use super::{AnimationStyle, CursorStyle};
+use super::schema::{self, clamp_field, Correction, LoadReport};
+use super::{
+    AnimationStyle, CursorStyle, DisplayProfile, Notifications, Paint, Pattern, ShortcutBindings,
+    StylePreset, TapTrigger, TransitionLabel, Trigger, TriggerStyles,
+};
+use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// User preferences for CursorHome
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Missing fields take their default value, so files written by older
+/// versions keep loading as fields are added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Preferences {
+    /// Schema version the preferences follow, and are saved with
+    pub version: u32,
+
     /// Whether CursorHome is enabled
     pub enabled: bool,
 
@@ -21,6 +33,19 @@ pub struct Preferences {
     /// Duration to show highlight (seconds)
     pub highlight_duration: f64,
 
+    /// Hold the highlight still under the desktop's reduced-motion setting
+    /// and draw it in high contrast under its high-contrast setting
+    pub follow_desktop_accessibility: bool,
+
+    /// Style overrides for highlights from particular triggers
+    pub trigger_styles: TriggerStyles,
+
+    /// Saved styles to switch between
+    pub presets: Vec<StylePreset>,
+
+    /// Presets to switch to automatically for known monitor layouts
+    pub display_profiles: Vec<DisplayProfile>,
+
     /// Enable shake-to-find
     pub shake_enabled: bool,
 
@@ -35,20 +60,49 @@ pub struct Preferences {
 
     /// Launch at login
     pub launch_at_login: bool,
+
+    /// Highlight the cursor when it returns from another Synergy machine
+    pub synergy_enabled: bool,
+
+    /// Label shown next to the cursor when it returns from another machine
+    pub transition_label: TransitionLabel,
+
+    /// Which desktop notifications to show
+    pub notifications: Notifications,
+
+    /// Draw frame rate and timing on the overlay, for diagnosing stutter
+    pub show_frame_stats: bool,
+
+    /// Keyboard shortcut bindings
+    pub shortcuts: ShortcutBindings,
+
+    /// Double-tap a modifier to find the cursor
+    pub tap_trigger: TapTrigger,
 }
 
 impl Default for Preferences {
     fn default() -> Self {
         Self {
+            version: schema::CURRENT_VERSION,
             enabled: true,
             cursor_style: CursorStyle::default(),
             animation_style: AnimationStyle::default(),
             highlight_duration: 5.0,
+            follow_desktop_accessibility: true,
+            trigger_styles: TriggerStyles::default(),
+            presets: StylePreset::builtin(),
+            display_profiles: Vec::new(),
             shake_enabled: true,
             shake_sensitivity: 0.5,
             magnifier_zoom: 2.0,
             magnifier_size: 150.0,
             launch_at_login: false,
+            synergy_enabled: true,
+            transition_label: TransitionLabel::default(),
+            notifications: Notifications::default(),
+            show_frame_stats: false,
+            shortcuts: ShortcutBindings::default(),
+            tap_trigger: TapTrigger::default(),
         }
     }
 }
@@ -61,64 +115,513 @@ impl Preferences {
     }
 
     /// Get the configuration file path
-    fn config_file() -> Option<PathBuf> {
+    pub fn config_file() -> Option<PathBuf> {
         Self::config_dir().map(|dir| dir.join("preferences.json"))
     }
 
     /// Load preferences from disk, or return defaults
-    pub fn load() -> Self {
+    ///
+    /// A file that exists but cannot be loaded is left alone, and the
+    /// report says why defaults are in use instead.
+    pub fn load() -> (Self, LoadReport) {
         let Some(path) = Self::config_file() else {
             tracing::warn!("Could not determine config directory");
-            return Self::default();
+            return (Self::default(), LoadReport::default());
         };
 
-        match fs::read_to_string(&path) {
-            Ok(content) => match serde_json::from_str(&content) {
-                Ok(prefs) => {
-                    tracing::info!("Loaded preferences from {:?}", path);
-                    prefs
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to parse preferences: {}", e);
-                    Self::default()
-                }
-            },
-            Err(_) => {
-                tracing::info!("No existing preferences, using defaults");
-                let prefs = Self::default();
-                prefs.save();
-                prefs
+        if !path.exists() {
+            tracing::info!("No existing preferences, using defaults");
+            let prefs = Self::default();
+            if let Err(e) = prefs.save() {
+                tracing::error!("{:#}", e);
+            }
+            return (prefs, LoadReport::default());
+        }
+
+        match Self::read(&path) {
+            Ok((prefs, report)) => {
+                tracing::info!("Loaded preferences from {:?}", path);
+                (prefs, report)
+            }
+            Err(e) => {
+                tracing::warn!("{:#}", e);
+                let report = LoadReport {
+                    error: Some(format!("{:#}", e)),
+                    ..Default::default()
+                };
+                (Self::default(), report)
             }
         }
     }
 
-    /// Save preferences to disk
-    pub fn save(&self) {
-        let Some(dir) = Self::config_dir() else {
-            tracing::warn!("Could not determine config directory");
-            return;
-        };
+    /// Read, migrate and validate a preferences file
+    pub fn read(path: &Path) -> Result<(Self, LoadReport)> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut value: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
 
-        if let Err(e) = fs::create_dir_all(&dir) {
-            tracing::error!("Failed to create config directory: {}", e);
-            return;
-        }
+        let version = schema::migrate(&mut value)
+            .with_context(|| format!("Failed to migrate {}", path.display()))?;
+        let mut prefs: Self = serde_json::from_value(value)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        // Only the fields this version knows were read
+        prefs.version = schema::CURRENT_VERSION;
 
-        let Some(path) = Self::config_file() else {
-            return;
+        let report = LoadReport {
+            migrated_from: (version < schema::CURRENT_VERSION).then_some(version),
+            corrections: prefs.validate(),
+            error: None,
+            newer_version: (version > schema::CURRENT_VERSION).then_some(version),
         };
+        for correction in &report.corrections {
+            tracing::warn!("Preference out of range, corrected {}", correction);
+        }
+
+        Ok((prefs, report))
+    }
+
+    /// Clamp values to their allowed ranges, returning what was changed
+    pub fn validate(&mut self) -> Vec<Correction> {
+        let defaults = Self::default();
+        let mut corrections = Vec::new();
+        let c = &mut corrections;
 
-        match serde_json::to_string_pretty(self) {
-            Ok(content) => {
-                if let Err(e) = fs::write(&path, content) {
-                    tracing::error!("Failed to write preferences: {}", e);
-                } else {
-                    tracing::debug!("Saved preferences to {:?}", path);
-                }
+        validate_style(
+            c,
+            "",
+            &mut self.cursor_style,
+            &mut self.animation_style,
+            &mut self.highlight_duration,
+            &defaults,
+        );
+        for &trigger in Trigger::all() {
+            let prefix = format!("trigger_styles.{}.", trigger.id());
+            let overrides = self.trigger_styles.get_mut(trigger);
+            if let Some(style) = &mut overrides.cursor_style {
+                validate_cursor_style(c, &prefix, style, &defaults);
             }
-            Err(e) => {
-                tracing::error!("Failed to serialize preferences: {}", e);
+            if let Some(animation) = &mut overrides.animation_style {
+                validate_animation(c, &prefix, animation, &defaults);
+            }
+            if let Some(duration) = &mut overrides.highlight_duration {
+                validate_highlight_duration(c, &prefix, duration, &defaults);
             }
         }
+        for preset in &mut self.presets {
+            let prefix = format!("presets.{}.", preset.slug());
+            validate_style(
+                c,
+                &prefix,
+                &mut preset.cursor_style,
+                &mut preset.animation_style,
+                &mut preset.highlight_duration,
+                &defaults,
+            );
+        }
+
+        clamp_field(
+            c,
+            "shake_sensitivity",
+            &mut self.shake_sensitivity,
+            schema::UNIT_RANGE,
+            defaults.shake_sensitivity,
+        );
+        clamp_field(
+            c,
+            "magnifier_zoom",
+            &mut self.magnifier_zoom,
+            schema::MAGNIFIER_ZOOM_RANGE,
+            defaults.magnifier_zoom,
+        );
+        clamp_field(
+            c,
+            "magnifier_size",
+            &mut self.magnifier_size,
+            schema::MAGNIFIER_SIZE_RANGE,
+            defaults.magnifier_size,
+        );
+        clamp_field(
+            c,
+            "tap_trigger.interval",
+            &mut self.tap_trigger.interval,
+            schema::TAP_INTERVAL_RANGE,
+            defaults.tap_trigger.interval,
+        );
+        validate_alpha(
+            c,
+            "transition_label.text_color.a",
+            &mut self.transition_label.text_color.a,
+        );
+        validate_alpha(
+            c,
+            "transition_label.background.a",
+            &mut self.transition_label.background.a,
+        );
+
+        corrections
+    }
+
+    /// Save preferences to disk
+    pub fn save(&self) -> Result<()> {
+        let dir = Self::config_dir().context("Could not determine config directory")?;
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let path = dir.join("preferences.json");
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+
+        tracing::debug!("Saved preferences to {:?}", path);
+        Ok(())
+    }
+}
+
+/// Clamp the settings a style preset covers, naming fields with `prefix`
+fn validate_style(
+    c: &mut Vec<Correction>,
+    prefix: &str,
+    style: &mut CursorStyle,
+    animation: &mut AnimationStyle,
+    highlight_duration: &mut f64,
+    defaults: &Preferences,
+) {
+    validate_cursor_style(c, prefix, style, defaults);
+    validate_animation(c, prefix, animation, defaults);
+    validate_highlight_duration(c, prefix, highlight_duration, defaults);
+}
+
+fn validate_cursor_style(
+    c: &mut Vec<Correction>,
+    prefix: &str,
+    style: &mut CursorStyle,
+    defaults: &Preferences,
+) {
+    let field = |name: &str| format!("{}{}", prefix, name);
+    let default_style = &defaults.cursor_style;
+
+    clamp_field(
+        c,
+        &field("cursor_style.size"),
+        &mut style.size,
+        schema::SIZE_RANGE,
+        default_style.size,
+    );
+    clamp_field(
+        c,
+        &field("cursor_style.border_weight"),
+        &mut style.border_weight,
+        schema::BORDER_WEIGHT_RANGE,
+        default_style.border_weight,
+    );
+    clamp_field(
+        c,
+        &field("cursor_style.glow_intensity"),
+        &mut style.glow_intensity,
+        schema::UNIT_RANGE,
+        default_style.glow_intensity,
+    );
+    clamp_field(
+        c,
+        &field("cursor_style.glow_radius"),
+        &mut style.glow_radius,
+        schema::GLOW_RADIUS_RANGE,
+        default_style.glow_radius,
+    );
+
+    validate_paint(c, &field("cursor_style.color"), &mut style.color);
+
+    for (i, layer) in style.layers.iter_mut().enumerate() {
+        let field = |name: &str| format!("{}cursor_style.layers.{}.{}", prefix, i, name);
+
+        clamp_field(
+            c,
+            &field("size"),
+            &mut layer.size,
+            schema::SIZE_RANGE,
+            default_style.size,
+        );
+        clamp_field(
+            c,
+            &field("border_weight"),
+            &mut layer.border_weight,
+            schema::BORDER_WEIGHT_RANGE,
+            default_style.border_weight,
+        );
+        validate_paint(c, &field("color"), &mut layer.color);
+    }
+}
+
+/// Clamp the alphas, offsets and timings of a paint
+fn validate_paint(c: &mut Vec<Correction>, field: &str, paint: &mut Paint) {
+    match paint {
+        Paint::Solid(color) => validate_alpha(c, &format!("{}.a", field), &mut color.a),
+        Paint::Pattern(
+            Pattern::LinearGradient { stops, .. } | Pattern::RadialGradient { stops },
+        ) => {
+            for (i, stop) in stops.iter_mut().enumerate() {
+                let stop_field = format!("{}.stops.{}", field, i);
+                clamp_field(
+                    c,
+                    &format!("{}.offset", stop_field),
+                    &mut stop.offset,
+                    schema::UNIT_RANGE,
+                    0.0,
+                );
+                validate_alpha(c, &format!("{}.color.a", stop_field), &mut stop.color.a);
+            }
+        }
+        Paint::Pattern(Pattern::HueCycle {
+            period,
+            saturation,
+            value,
+            alpha,
+        }) => {
+            clamp_field(
+                c,
+                &format!("{}.period", field),
+                period,
+                schema::HUE_PERIOD_RANGE,
+                3.0,
+            );
+            clamp_field(
+                c,
+                &format!("{}.saturation", field),
+                saturation,
+                schema::UNIT_RANGE,
+                1.0,
+            );
+            clamp_field(
+                c,
+                &format!("{}.value", field),
+                value,
+                schema::UNIT_RANGE,
+                1.0,
+            );
+            validate_alpha(c, &format!("{}.alpha", field), alpha);
+        }
+        Paint::Pattern(Pattern::Accent { color } | Pattern::Adaptive { color }) => {
+            validate_alpha(c, &format!("{}.color.a", field), &mut color.a)
+        }
+    }
+}
+
+/// Alpha is stored as f32
+fn validate_alpha(c: &mut Vec<Correction>, field: &str, alpha: &mut f32) {
+    let mut value = *alpha as f64;
+    clamp_field(c, field, &mut value, schema::UNIT_RANGE, 1.0);
+    *alpha = value as f32;
+}
+
+fn validate_animation(
+    c: &mut Vec<Correction>,
+    prefix: &str,
+    animation: &mut AnimationStyle,
+    defaults: &Preferences,
+) {
+    clamp_field(
+        c,
+        &format!("{}animation_style.duration", prefix),
+        &mut animation.duration,
+        schema::ANIMATION_DURATION_RANGE,
+        defaults.animation_style.duration,
+    );
+}
+
+fn validate_highlight_duration(
+    c: &mut Vec<Correction>,
+    prefix: &str,
+    highlight_duration: &mut f64,
+    defaults: &Preferences,
+) {
+    clamp_field(
+        c,
+        &format!("{}highlight_duration", prefix),
+        highlight_duration,
+        schema::HIGHLIGHT_DURATION_RANGE,
+        defaults.highlight_duration,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AnimationType, Color, ColorStop, Layer};
+
+    /// Where a correction for `field` put its value, if there is one
+    fn corrected(corrections: &[Correction], field: &str) -> Option<f64> {
+        corrections
+            .iter()
+            .find(|correction| correction.field == field)
+            .map(|correction| correction.corrected)
+    }
+
+    /// Write `content` to a fresh file and read it as preferences
+    fn read_str(name: &str, content: &str) -> (Preferences, LoadReport) {
+        let path =
+            std::env::temp_dir().join(format!("cursorhome-{}-{}.json", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        let result = Preferences::read(&path);
+        fs::remove_file(&path).ok();
+        result.unwrap()
+    }
+
+    #[test]
+    fn defaults_need_no_corrections() {
+        assert!(Preferences::default().validate().is_empty());
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped_and_reported() {
+        let mut prefs = Preferences::default();
+        prefs.cursor_style.size = 500.0;
+        prefs.cursor_style.border_weight = 0.0;
+        prefs.cursor_style.layers = vec![Layer {
+            size: 5.0,
+            ..Layer::default()
+        }];
+        prefs.trigger_styles.shake.highlight_duration = Some(60.0);
+        prefs.presets[0].animation_style.duration = 0.0;
+        prefs.magnifier_zoom = 20.0;
+        prefs.tap_trigger.interval = 3.0;
+
+        let corrections = prefs.validate();
+        let slug = prefs.presets[0].slug();
+        assert_eq!(corrections.len(), 7, "{:?}", corrections);
+        assert_eq!(corrected(&corrections, "cursor_style.size"), Some(200.0));
+        assert_eq!(
+            corrected(&corrections, "cursor_style.border_weight"),
+            Some(1.0)
+        );
+        assert_eq!(
+            corrected(&corrections, "cursor_style.layers.0.size"),
+            Some(20.0)
+        );
+        assert_eq!(
+            corrected(&corrections, "trigger_styles.shake.highlight_duration"),
+            Some(30.0)
+        );
+        assert_eq!(
+            corrected(
+                &corrections,
+                &format!("presets.{}.animation_style.duration", slug)
+            ),
+            Some(0.1)
+        );
+        assert_eq!(corrected(&corrections, "magnifier_zoom"), Some(10.0));
+        assert_eq!(corrected(&corrections, "tap_trigger.interval"), Some(1.0));
+
+        assert_eq!(prefs.cursor_style.size, 200.0);
+        assert_eq!(prefs.cursor_style.layers[0].size, 20.0);
+        assert_eq!(prefs.trigger_styles.shake.highlight_duration, Some(30.0));
+        assert!(prefs.validate().is_empty());
+    }
+
+    #[test]
+    fn non_finite_values_fall_back_to_defaults() {
+        let mut prefs = Preferences::default();
+        prefs.cursor_style.color = Paint::Pattern(Pattern::HueCycle {
+            period: f64::NAN,
+            saturation: f64::INFINITY,
+            value: 1.0,
+            alpha: f32::NAN,
+        });
+        prefs.cursor_style.layers = vec![Layer {
+            color: Paint::Pattern(Pattern::LinearGradient {
+                stops: vec![ColorStop {
+                    offset: f64::NEG_INFINITY,
+                    color: Color {
+                        a: f32::INFINITY,
+                        ..Color::default()
+                    },
+                }],
+                angle: 0.0,
+            }),
+            ..Layer::default()
+        }];
+        prefs.animation_style.duration = f64::NAN;
+        prefs.highlight_duration = f64::INFINITY;
+        prefs.transition_label.background.a = f32::NAN;
+
+        let corrections = prefs.validate();
+        assert_eq!(corrections.len(), 8, "{:?}", corrections);
+        assert_eq!(
+            corrected(&corrections, "cursor_style.color.period"),
+            Some(3.0)
+        );
+        assert_eq!(
+            corrected(&corrections, "cursor_style.color.saturation"),
+            Some(1.0)
+        );
+        assert_eq!(
+            corrected(&corrections, "cursor_style.color.alpha"),
+            Some(1.0)
+        );
+        assert_eq!(
+            corrected(&corrections, "cursor_style.layers.0.color.stops.0.offset"),
+            Some(0.0)
+        );
+        assert_eq!(
+            corrected(&corrections, "cursor_style.layers.0.color.stops.0.color.a"),
+            Some(1.0)
+        );
+        assert_eq!(
+            corrected(&corrections, "animation_style.duration"),
+            Some(0.8)
+        );
+        assert_eq!(corrected(&corrections, "highlight_duration"), Some(5.0));
+        assert_eq!(
+            corrected(&corrections, "transition_label.background.a"),
+            Some(1.0)
+        );
+        assert!(prefs.validate().is_empty());
+    }
+
+    #[test]
+    fn alpha_is_clamped_to_unit_range() {
+        let mut prefs = Preferences::default();
+        prefs.cursor_style.color = Paint::Solid(Color {
+            a: 1.5,
+            ..Color::default()
+        });
+        prefs.transition_label.text_color.a = -0.5;
+
+        let corrections = prefs.validate();
+        assert_eq!(corrected(&corrections, "cursor_style.color.a"), Some(1.0));
+        assert_eq!(
+            corrected(&corrections, "transition_label.text_color.a"),
+            Some(0.0)
+        );
+        assert_eq!(prefs.cursor_style.color.base_color().a, 1.0);
+    }
+
+    #[test]
+    fn read_migrates_and_corrects_v0_files() {
+        let (prefs, report) = read_str(
+            "v0",
+            r#"{ "animation_style": { "type": "ripple" }, "magnifier_size": 10 }"#,
+        );
+        assert_eq!(prefs.version, schema::CURRENT_VERSION);
+        assert_eq!(prefs.animation_style.animation_type, AnimationType::Ripple);
+        assert_eq!(report.migrated_from, Some(0));
+        assert_eq!(corrected(&report.corrections, "magnifier_size"), Some(50.0));
+        assert!(!report.holds_saves());
+    }
+
+    #[test]
+    fn read_reports_newer_files() {
+        let newer = schema::CURRENT_VERSION + 1;
+        let (prefs, report) = read_str(
+            "newer",
+            &format!(
+                r#"{{ "version": {}, "enabled": false, "future_setting": true }}"#,
+                newer
+            ),
+        );
+        assert!(!prefs.enabled);
+        assert_eq!(prefs.version, schema::CURRENT_VERSION);
+        assert_eq!(report.newer_version, Some(newer));
+        assert_eq!(report.migrated_from, None);
+        assert!(report.holds_saves());
+        assert!(!report.is_clean());
     }
 }
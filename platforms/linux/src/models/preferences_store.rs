@@ -0,0 +1,280 @@
+//! Shared, observable preferences
+
+use super::{LoadReport, Preferences};
+use gtk4::glib;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Delay between the last change and writing preferences to disk
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Callback invoked with the previous and the new preferences
+type Subscriber = Rc<dyn Fn(&Preferences, &Preferences)>;
+
+/// Handle returned by [`PreferencesStore::subscribe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionId(u64);
+
+struct StoreInner {
+    preferences: RefCell<Preferences>,
+    subscribers: RefCell<Vec<(SubscriptionId, Subscriber)>>,
+    next_id: Cell<u64>,
+    pending_save: RefCell<Option<glib::SourceId>>,
+    /// Preferences as last written to or read from disk
+    on_disk: RefCell<Option<Preferences>>,
+    /// The file on disk could not be loaded or is from a newer version, so
+    /// nothing is written over it until it changes or the user changes a
+    /// setting
+    saves_held: Cell<bool>,
+}
+
+impl StoreInner {
+    fn save(&self) {
+        let preferences = self.preferences.borrow().clone();
+        match preferences.save() {
+            Ok(()) => *self.on_disk.borrow_mut() = Some(preferences),
+            Err(e) => tracing::error!("{:#}", e),
+        }
+    }
+}
+
+/// Preferences shared between the UI and services on the main thread
+///
+/// Cloning the store is cheap and every clone sees the same preferences.
+/// Changes made through [`update`](Self::update) notify subscribers right
+/// away and are written to disk after a short delay, so dragging a slider
+/// does not rewrite the file on every step.
+#[derive(Clone)]
+pub struct PreferencesStore {
+    inner: Rc<StoreInner>,
+}
+
+impl PreferencesStore {
+    /// Create a store holding the given preferences
+    pub fn new(preferences: Preferences) -> Self {
+        Self {
+            inner: Rc::new(StoreInner {
+                preferences: RefCell::new(preferences),
+                subscribers: RefCell::new(Vec::new()),
+                next_id: Cell::new(0),
+                pending_save: RefCell::new(None),
+                on_disk: RefCell::new(None),
+                saves_held: Cell::new(false),
+            }),
+        }
+    }
+
+    /// Create a store from the preferences saved on disk
+    ///
+    /// If the file could not be loaded the store holds defaults, which are
+    /// not saved over the file until the user changes a setting. Neither is
+    /// a file from a newer version, whose unknown settings would be lost.
+    pub fn load() -> (Self, LoadReport) {
+        let (preferences, report) = Preferences::load();
+        (Self::loaded(preferences, &report), report)
+    }
+
+    /// Create a store holding preferences read as described by `report`
+    fn loaded(preferences: Preferences, report: &LoadReport) -> Self {
+        let store = Self::new(preferences.clone());
+        store.inner.saves_held.set(report.holds_saves());
+        if report.error.is_none() {
+            *store.inner.on_disk.borrow_mut() = Some(preferences);
+        }
+        store
+    }
+
+    /// Get a copy of the current preferences
+    pub fn get(&self) -> Preferences {
+        self.inner.preferences.borrow().clone()
+    }
+
+    /// Read the current preferences without copying them
+    pub fn with<R>(&self, f: impl FnOnce(&Preferences) -> R) -> R {
+        f(&self.inner.preferences.borrow())
+    }
+
+    /// Modify the preferences, then notify subscribers and schedule a save
+    ///
+    /// Nothing happens if `f` leaves the preferences unchanged.
+    pub fn update(&self, f: impl FnOnce(&mut Preferences)) {
+        let mut new = self.get();
+        f(&mut new);
+        self.replace(new);
+    }
+
+    /// Replace all preferences, then notify subscribers and schedule a save
+    pub fn replace(&self, preferences: Preferences) {
+        if self.swap(preferences) {
+            self.inner.saves_held.set(false);
+            self.schedule_save();
+        }
+    }
+
+    /// Modify the preferences on the app's own initiative rather than the
+    /// user's
+    ///
+    /// Like [`update`](Self::update), except that while the file on disk
+    /// could not be loaded or is from a newer version the change is not
+    /// saved over it.
+    pub fn update_automatically(&self, f: impl FnOnce(&mut Preferences)) {
+        let mut new = self.get();
+        f(&mut new);
+        if self.swap(new) && !self.inner.saves_held.get() {
+            self.schedule_save();
+        }
+    }
+
+    /// Apply preferences that were read back from disk
+    ///
+    /// The file already holds these values, so nothing is written back.
+    /// Reading back the store's own save is recognised and ignored, so a
+    /// change made while that save was in flight is not undone. An edit on
+    /// disk wins over changes still waiting to be saved.
+    pub fn reload(&self, preferences: Preferences, report: &LoadReport) {
+        self.inner.saves_held.set(report.holds_saves());
+        if self.inner.on_disk.borrow().as_ref() == Some(&preferences) {
+            return;
+        }
+
+        if let Some(source) = self.inner.pending_save.borrow_mut().take() {
+            source.remove();
+        }
+        *self.inner.on_disk.borrow_mut() = Some(preferences.clone());
+        self.swap(preferences);
+    }
+
+    /// Store new preferences and notify subscribers; false if unchanged
+    fn swap(&self, preferences: Preferences) -> bool {
+        let old = {
+            let mut current = self.inner.preferences.borrow_mut();
+            if *current == preferences {
+                return false;
+            }
+            std::mem::replace(&mut *current, preferences.clone())
+        };
+
+        self.notify(&old, &preferences);
+        true
+    }
+
+    /// Register a callback for preference changes
+    pub fn subscribe<F>(&self, callback: F) -> SubscriptionId
+    where
+        F: Fn(&Preferences, &Preferences) + 'static,
+    {
+        let id = SubscriptionId(self.inner.next_id.get());
+        self.inner.next_id.set(id.0 + 1);
+        self.inner
+            .subscribers
+            .borrow_mut()
+            .push((id, Rc::new(callback)));
+        id
+    }
+
+    /// Remove a callback registered with [`subscribe`](Self::subscribe)
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        self.inner
+            .subscribers
+            .borrow_mut()
+            .retain(|(subscriber_id, _)| *subscriber_id != id);
+    }
+
+    /// Write pending changes to disk immediately
+    pub fn flush(&self) {
+        if let Some(source) = self.inner.pending_save.borrow_mut().take() {
+            source.remove();
+            self.inner.save();
+        }
+    }
+
+    fn notify(&self, old: &Preferences, new: &Preferences) {
+        // Subscribers may update the store themselves, so don't hold the borrow
+        let subscribers: Vec<Subscriber> = self
+            .inner
+            .subscribers
+            .borrow()
+            .iter()
+            .map(|(_, subscriber)| subscriber.clone())
+            .collect();
+
+        for subscriber in subscribers {
+            subscriber(old, new);
+        }
+    }
+
+    fn schedule_save(&self) {
+        if let Some(source) = self.inner.pending_save.borrow_mut().take() {
+            source.remove();
+        }
+
+        let weak = Rc::downgrade(&self.inner);
+        let source = glib::timeout_add_local_once(SAVE_DELAY, move || {
+            if let Some(inner) = weak.upgrade() {
+                inner.pending_save.borrow_mut().take();
+                inner.save();
+            }
+        });
+        *self.inner.pending_save.borrow_mut() = Some(source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schema::CURRENT_VERSION;
+
+    /// Whether a save is waiting to be written
+    fn save_pending(store: &PreferencesStore) -> bool {
+        store.inner.pending_save.borrow().is_some()
+    }
+
+    #[test]
+    fn newer_files_are_not_saved_over_automatically() {
+        let report = LoadReport {
+            newer_version: Some(CURRENT_VERSION + 1),
+            ..Default::default()
+        };
+        let store = PreferencesStore::loaded(Preferences::default(), &report);
+
+        store.update_automatically(|p| p.enabled = false);
+        assert!(!store.get().enabled);
+        assert!(!save_pending(&store));
+        assert!(store.inner.saves_held.get());
+    }
+
+    #[test]
+    fn broken_files_are_not_saved_over_automatically() {
+        let report = LoadReport {
+            error: Some("expected value at line 1 column 1".to_string()),
+            ..Default::default()
+        };
+        let store = PreferencesStore::loaded(Preferences::default(), &report);
+        assert!(store.inner.on_disk.borrow().is_none());
+
+        store.update_automatically(|p| p.magnifier_zoom = 4.0);
+        assert_eq!(store.get().magnifier_zoom, 4.0);
+        assert!(!save_pending(&store));
+    }
+
+    #[test]
+    fn reloading_a_newer_file_holds_saves() {
+        let store = PreferencesStore::new(Preferences::default());
+        let edited = Preferences {
+            enabled: false,
+            ..Preferences::default()
+        };
+        let report = LoadReport {
+            newer_version: Some(CURRENT_VERSION + 1),
+            ..Default::default()
+        };
+
+        store.reload(edited.clone(), &report);
+        assert_eq!(store.get(), edited);
+        assert!(store.inner.saves_held.get());
+
+        store.reload(Preferences::default(), &LoadReport::default());
+        assert!(!store.inner.saves_held.get());
+    }
+}
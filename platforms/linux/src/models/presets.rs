@@ -0,0 +1,263 @@
+//! Named style presets
+
+use super::{
+    AnimationStyle, AnimationType, Color, CursorStyle, Easing, Preferences, Shape, StyleOverride,
+    Trigger,
+};
+use serde::{Deserialize, Serialize};
+
+/// A named highlight look that can be switched to in one step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StylePreset {
+    pub name: String,
+    pub cursor_style: CursorStyle,
+    pub animation_style: AnimationStyle,
+    /// Duration to show highlight (seconds)
+    pub highlight_duration: f64,
+}
+
+impl Default for StylePreset {
+    fn default() -> Self {
+        let defaults = Preferences::default();
+        Self::from_preferences("Preset", &defaults)
+    }
+}
+
+impl StylePreset {
+    /// Capture the current style settings under a new name
+    pub fn from_preferences(name: &str, preferences: &Preferences) -> Self {
+        Self {
+            name: name.to_string(),
+            cursor_style: preferences.cursor_style.clone(),
+            animation_style: preferences.animation_style.clone(),
+            highlight_duration: preferences.highlight_duration,
+        }
+    }
+
+    /// Copy this preset's style settings into `preferences`
+    pub fn apply_to(&self, preferences: &mut Preferences) {
+        preferences.cursor_style = self.cursor_style.clone();
+        preferences.animation_style = self.animation_style.clone();
+        preferences.highlight_duration = self.highlight_duration;
+    }
+
+    /// Whether `preferences` currently use exactly this preset's style
+    pub fn is_applied_to(&self, preferences: &Preferences) -> bool {
+        preferences.cursor_style == self.cursor_style
+            && preferences.animation_style == self.animation_style
+            && preferences.highlight_duration == self.highlight_duration
+    }
+
+    /// Name as typed on the command line: lowercase, spaces as hyphens
+    pub fn slug(&self) -> String {
+        slugify(&self.name)
+    }
+
+    /// Presets shipped with CursorHome
+    pub fn builtin() -> Vec<StylePreset> {
+        vec![
+            StylePreset {
+                name: "Subtle".to_string(),
+                cursor_style: CursorStyle {
+                    shape: Shape::Ring,
+                    size: 40.0,
+                    color: Color::new(255, 149, 0, 0.7).into(),
+                    border_weight: 2.0,
+                    glow_enabled: false,
+                    ..CursorStyle::default()
+                },
+                animation_style: AnimationStyle {
+                    animation_type: AnimationType::Fade,
+                    duration: 0.4,
+                    easing: Easing::EaseOut,
+                    repeat_count: 1,
+                    auto_reverse: false,
+                },
+                highlight_duration: 1.5,
+            },
+            StylePreset {
+                name: "High Visibility".to_string(),
+                cursor_style: CursorStyle {
+                    shape: Shape::Ring,
+                    size: 120.0,
+                    color: Color::new(255, 214, 10, 1.0).into(),
+                    border_weight: 8.0,
+                    glow_enabled: true,
+                    glow_intensity: 0.9,
+                    glow_radius: 20.0,
+                    ..CursorStyle::default()
+                },
+                animation_style: AnimationStyle {
+                    animation_type: AnimationType::Pulse,
+                    duration: 0.6,
+                    repeat_count: 4,
+                    ..AnimationStyle::default()
+                },
+                highlight_duration: 4.0,
+            },
+            StylePreset {
+                name: "Presenter".to_string(),
+                cursor_style: CursorStyle {
+                    shape: Shape::Spotlight,
+                    size: 160.0,
+                    color: Color::new(255, 59, 48, 0.35).into(),
+                    glow_enabled: true,
+                    glow_intensity: 0.6,
+                    glow_radius: 30.0,
+                    ..CursorStyle::default()
+                },
+                animation_style: AnimationStyle {
+                    animation_type: AnimationType::Scale,
+                    duration: 1.0,
+                    repeat_count: 2,
+                    ..AnimationStyle::default()
+                },
+                highlight_duration: 8.0,
+            },
+        ]
+    }
+}
+
+fn slugify(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+impl Preferences {
+    /// Find a preset by display name or slug, ignoring case
+    pub fn preset_index(&self, name: &str) -> Option<usize> {
+        let slug = slugify(name);
+        self.presets.iter().position(|preset| preset.slug() == slug)
+    }
+
+    /// Index of the preset matching the current style, if any
+    pub fn active_preset(&self) -> Option<usize> {
+        self.presets
+            .iter()
+            .position(|preset| preset.is_applied_to(self))
+    }
+
+    /// Switch to the preset at `index`; false if there is none
+    pub fn apply_preset(&mut self, index: usize) -> bool {
+        let Some(preset) = self.presets.get(index).cloned() else {
+            return false;
+        };
+        preset.apply_to(self);
+        true
+    }
+
+    /// Rename the preset at `index`, keeping display profiles that use it
+    pub fn rename_preset(&mut self, index: usize, name: String) {
+        let old = std::mem::replace(&mut self.presets[index].name, name.clone());
+        for profile in &mut self.display_profiles {
+            if profile.preset == old {
+                profile.preset = name.clone();
+            }
+        }
+    }
+
+    /// Delete the preset at `index`, along with the display profiles that
+    /// switch to it and the trigger overrides chosen from it
+    ///
+    /// An override is kept if another preset has the same settings, since
+    /// it still names that one.
+    pub fn delete_preset(&mut self, index: usize) {
+        let preset = self.presets.remove(index);
+        let slug = preset.slug();
+        self.display_profiles
+            .retain(|profile| slugify(&profile.preset) != slug);
+
+        for trigger in Trigger::all() {
+            let overrides = self.trigger_styles.get_mut(*trigger);
+            let still_named = self
+                .presets
+                .iter()
+                .any(|other| overrides.matches_preset(other));
+            if overrides.matches_preset(&preset) && !still_named {
+                *overrides = StyleOverride::default();
+            }
+        }
+    }
+
+    /// Switch to the preset after the active one, wrapping around
+    pub fn apply_next_preset(&mut self) -> Option<&str> {
+        if self.presets.is_empty() {
+            return None;
+        }
+
+        let next = self
+            .active_preset()
+            .map_or(0, |index| (index + 1) % self.presets.len());
+        self.apply_preset(next);
+        Some(&self.presets[next].name)
+    }
+
+    /// `base`, or `base` with a number appended if a preset already uses it
+    pub fn unique_preset_name(&self, base: &str) -> String {
+        let base = base.trim();
+        if self.preset_index(base).is_none() {
+            return base.to_string();
+        }
+
+        (2..)
+            .map(|n| format!("{} {}", base, n))
+            .find(|name| self.preset_index(name).is_none())
+            .expect("unbounded range always finds a free name")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DisplayProfile;
+
+    fn profile(layout: &str, preset: &str) -> DisplayProfile {
+        DisplayProfile {
+            layout: layout.to_string(),
+            preset: preset.to_string(),
+        }
+    }
+
+    #[test]
+    fn delete_preset_drops_profiles_and_overrides_using_it() {
+        let mut prefs = Preferences::default();
+        assert!(prefs.presets.len() >= 2);
+        let deleted = prefs.presets[0].clone();
+        let kept = prefs.presets[1].clone();
+        prefs.display_profiles = vec![
+            profile("DP-1:2560x1440", &deleted.name),
+            profile("eDP-1:1920x1200", &deleted.name.to_uppercase()),
+            profile("HDMI-1:1920x1080", &kept.name),
+        ];
+        prefs.trigger_styles.shake = StyleOverride::from_preset(&deleted);
+        prefs.trigger_styles.synergy = StyleOverride::from_preset(&kept);
+        prefs.trigger_styles.peer.highlight_duration = Some(9.0);
+
+        prefs.delete_preset(0);
+
+        assert_eq!(prefs.preset_index(&deleted.name), None);
+        assert_eq!(
+            prefs.display_profiles,
+            vec![profile("HDMI-1:1920x1080", &kept.name)]
+        );
+        assert!(prefs.trigger_styles.shake.is_empty());
+        assert!(prefs.trigger_styles.synergy.matches_preset(&kept));
+        assert_eq!(prefs.trigger_styles.peer.highlight_duration, Some(9.0));
+    }
+
+    #[test]
+    fn delete_preset_keeps_overrides_matching_a_duplicate() {
+        let mut prefs = Preferences::default();
+        let mut copy = prefs.presets[0].clone();
+        copy.name = prefs.unique_preset_name(&copy.name);
+        prefs.presets.push(copy.clone());
+        prefs.trigger_styles.hotkey = StyleOverride::from_preset(&copy);
+
+        prefs.delete_preset(0);
+
+        assert!(prefs.trigger_styles.hotkey.matches_preset(&copy));
+    }
+}
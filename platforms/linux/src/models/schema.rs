@@ -0,0 +1,265 @@
+//! Preferences file versioning, migration and value ranges
+//!
+//! Ranges follow the shared model definitions in `shared/models/`.
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Version written to new preferences files
+///
+/// Files saved before versioning was introduced have no `version` field and
+/// are treated as version 0.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Highlight diameter in pixels
+pub const SIZE_RANGE: RangeInclusive<f64> = 20.0..=200.0;
+/// Border thickness in pixels
+pub const BORDER_WEIGHT_RANGE: RangeInclusive<f64> = 1.0..=10.0;
+/// Color and glow opacity
+pub const UNIT_RANGE: RangeInclusive<f64> = 0.0..=1.0;
+/// Glow blur radius in pixels
+pub const GLOW_RADIUS_RANGE: RangeInclusive<f64> = 0.0..=50.0;
+/// Duration of one animation cycle in seconds
+pub const ANIMATION_DURATION_RANGE: RangeInclusive<f64> = 0.1..=5.0;
+/// Time the highlight stays on screen in seconds
+pub const HIGHLIGHT_DURATION_RANGE: RangeInclusive<f64> = 1.0..=30.0;
+/// Maximum time between modifier taps in seconds
+pub const TAP_INTERVAL_RANGE: RangeInclusive<f64> = 0.1..=1.0;
+/// Seconds for a hue-cycle paint to go around the color wheel
+pub const HUE_PERIOD_RANGE: RangeInclusive<f64> = 0.5..=60.0;
+/// Magnifier zoom factor
+pub const MAGNIFIER_ZOOM_RANGE: RangeInclusive<f64> = 1.0..=10.0;
+/// Magnifier window size in pixels
+pub const MAGNIFIER_SIZE_RANGE: RangeInclusive<f64> = 50.0..=500.0;
+
+/// Migration from version `n` to `n + 1`, at index `n`
+type Migration = fn(&mut Map<String, Value>);
+
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+/// Version 0 → 1: accept the shared schema's `type` key for the animation
+/// type, as written by other CursorHome platforms
+fn migrate_v0_to_v1(root: &mut Map<String, Value>) {
+    if let Some(Value::Object(animation)) = root.get_mut("animation_style") {
+        if !animation.contains_key("animation_type") {
+            if let Some(animation_type) = animation.remove("type") {
+                animation.insert("animation_type".to_string(), animation_type);
+            }
+        }
+    }
+}
+
+/// Bring a parsed preferences file up to [`CURRENT_VERSION`]
+///
+/// Returns the version the file was written with. Files from a newer
+/// version are left alone; unknown fields are ignored when they are loaded,
+/// and the loaded preferences follow [`CURRENT_VERSION`].
+pub fn migrate(value: &mut Value) -> Result<u32> {
+    let Value::Object(root) = value else {
+        anyhow::bail!("Preferences must be a JSON object");
+    };
+
+    let version = match root.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| anyhow::anyhow!("Invalid preferences version: {}", version))?,
+    };
+
+    if version > CURRENT_VERSION {
+        tracing::warn!(
+            "Preferences were written by a newer version (schema {}, expected {})",
+            version,
+            CURRENT_VERSION
+        );
+        return Ok(version);
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        tracing::info!(
+            "Migrating preferences from version {} to {}",
+            from,
+            from + 1
+        );
+        migration(root);
+    }
+    root.insert("version".to_string(), CURRENT_VERSION.into());
+
+    Ok(version)
+}
+
+/// A value that was out of range and has been replaced
+#[derive(Debug, Clone, PartialEq)]
+pub struct Correction {
+    pub field: String,
+    pub found: f64,
+    pub corrected: f64,
+}
+
+impl fmt::Display for Correction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} → {}", self.field, self.found, self.corrected)
+    }
+}
+
+/// Clamp `value` into `range`, recording a correction if it changed
+///
+/// Non-finite values are replaced with `fallback`.
+pub(crate) fn clamp_field(
+    corrections: &mut Vec<Correction>,
+    field: &str,
+    value: &mut f64,
+    range: RangeInclusive<f64>,
+    fallback: f64,
+) {
+    let corrected = if value.is_finite() {
+        value.clamp(*range.start(), *range.end())
+    } else {
+        fallback
+    };
+
+    if corrected != *value {
+        corrections.push(Correction {
+            field: field.to_string(),
+            found: *value,
+            corrected,
+        });
+        *value = corrected;
+    }
+}
+
+/// What had to be done to load a preferences file
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    /// Schema version the file was written with, if it was migrated
+    pub migrated_from: Option<u32>,
+    /// Out-of-range values that were clamped
+    pub corrections: Vec<Correction>,
+    /// Why the file could not be loaded, in which case defaults are in use
+    pub error: Option<String>,
+    /// Schema version of a file written by a newer CursorHome, whose
+    /// settings this version doesn't know were left out
+    pub newer_version: Option<u32>,
+}
+
+impl LoadReport {
+    /// Whether the file was loaded exactly as written
+    pub fn is_clean(&self) -> bool {
+        self.migrated_from.is_none()
+            && self.corrections.is_empty()
+            && self.error.is_none()
+            && self.newer_version.is_none()
+    }
+
+    /// Whether saving would lose part of the file, so it is only written
+    /// over once the user changes a setting
+    pub fn holds_saves(&self) -> bool {
+        self.error.is_some() || self.newer_version.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn v0_files_take_the_animation_type_from_type() {
+        let mut value = json!({ "animation_style": { "type": "ripple", "duration": 1.2 } });
+        assert_eq!(migrate(&mut value).unwrap(), 0);
+        assert_eq!(
+            value,
+            json!({
+                "version": CURRENT_VERSION,
+                "animation_style": { "animation_type": "ripple", "duration": 1.2 }
+            })
+        );
+    }
+
+    #[test]
+    fn v0_migration_keeps_an_existing_animation_type() {
+        let mut value = json!({
+            "version": 0,
+            "animation_style": { "animation_type": "fade", "type": "ripple" }
+        });
+        assert_eq!(migrate(&mut value).unwrap(), 0);
+        assert_eq!(value["animation_style"]["animation_type"], "fade");
+    }
+
+    #[test]
+    fn current_files_are_unchanged() {
+        let original = json!({ "version": CURRENT_VERSION, "animation_style": { "type": "x" } });
+        let mut value = original.clone();
+        assert_eq!(migrate(&mut value).unwrap(), CURRENT_VERSION);
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn newer_files_are_left_alone() {
+        let original = json!({ "version": CURRENT_VERSION + 1, "future": true });
+        let mut value = original.clone();
+        assert_eq!(migrate(&mut value).unwrap(), CURRENT_VERSION + 1);
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn invalid_versions_are_errors() {
+        for version in [
+            json!("1"),
+            json!(-1),
+            json!(1.5),
+            json!(u64::MAX),
+            json!(null),
+        ] {
+            let mut value = json!({ "version": version });
+            assert!(migrate(&mut value).is_err(), "version {}", version);
+        }
+        assert!(migrate(&mut json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn clamp_field_records_corrections() {
+        let mut corrections = Vec::new();
+
+        let mut inside = 5.0;
+        clamp_field(&mut corrections, "inside", &mut inside, 1.0..=10.0, 2.0);
+        assert_eq!(inside, 5.0);
+        assert!(corrections.is_empty());
+
+        let mut above = 12.0;
+        clamp_field(&mut corrections, "above", &mut above, 1.0..=10.0, 2.0);
+        let mut below = 0.5;
+        clamp_field(&mut corrections, "below", &mut below, 1.0..=10.0, 2.0);
+        let mut infinite = f64::INFINITY;
+        clamp_field(&mut corrections, "infinite", &mut infinite, 1.0..=10.0, 2.0);
+        assert_eq!((above, below, infinite), (10.0, 1.0, 2.0));
+        assert_eq!(
+            corrections,
+            [
+                Correction {
+                    field: "above".to_string(),
+                    found: 12.0,
+                    corrected: 10.0
+                },
+                Correction {
+                    field: "below".to_string(),
+                    found: 0.5,
+                    corrected: 1.0
+                },
+                Correction {
+                    field: "infinite".to_string(),
+                    found: f64::INFINITY,
+                    corrected: 2.0
+                },
+            ]
+        );
+
+        let mut nan = f64::NAN;
+        clamp_field(&mut corrections, "nan", &mut nan, 1.0..=10.0, 2.0);
+        assert_eq!(nan, 2.0);
+        assert_eq!(corrections.last().unwrap().field, "nan");
+    }
+}
@@ -0,0 +1,309 @@
+//! Settings file in the format of the shared schemas
+//!
+//! Top-level keys use the names of the macOS `UserPreferences`, and the
+//! cursor and animation styles conform to the schemas in `shared/models/`.
+//! Settings only one platform has (trigger styles, presets, hotkey key
+//! codes) are not part of the file.
+
+use super::schema::Correction;
+use super::{AnimationStyle, AnimationType, CursorStyle, Easing, Preferences};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Style and behavior settings named as in the macOS app
+///
+/// Every field is optional: importing only changes what the file contains.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor_style: Option<CursorStyle>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation_style: Option<SharedAnimationStyle>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_duration: Option<f64>,
+
+    /// `shake_enabled` in [`Preferences`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_highlight_on_shake: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shake_sensitivity: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub magnifier_zoom: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub magnifier_size: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launch_at_login: Option<bool>,
+}
+
+/// Animation style as the shared schema writes it, with the animation type
+/// under `type`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SharedAnimationStyle {
+    #[serde(rename = "type")]
+    pub animation_type: AnimationType,
+    pub duration: f64,
+    pub easing: Easing,
+    pub repeat_count: u32,
+    pub auto_reverse: bool,
+}
+
+impl Default for SharedAnimationStyle {
+    fn default() -> Self {
+        AnimationStyle::default().into()
+    }
+}
+
+impl From<AnimationStyle> for SharedAnimationStyle {
+    fn from(style: AnimationStyle) -> Self {
+        Self {
+            animation_type: style.animation_type,
+            duration: style.duration,
+            easing: style.easing,
+            repeat_count: style.repeat_count,
+            auto_reverse: style.auto_reverse,
+        }
+    }
+}
+
+impl From<SharedAnimationStyle> for AnimationStyle {
+    fn from(style: SharedAnimationStyle) -> Self {
+        Self {
+            animation_type: style.animation_type,
+            duration: style.duration,
+            easing: style.easing,
+            repeat_count: style.repeat_count,
+            auto_reverse: style.auto_reverse,
+        }
+    }
+}
+
+impl SharedSettings {
+    /// Every shared setting, taken from `preferences`
+    pub fn from_preferences(preferences: &Preferences) -> Self {
+        Self {
+            enabled: Some(preferences.enabled),
+            cursor_style: Some(preferences.cursor_style.clone()),
+            animation_style: Some(preferences.animation_style.clone().into()),
+            highlight_duration: Some(preferences.highlight_duration),
+            auto_highlight_on_shake: Some(preferences.shake_enabled),
+            shake_sensitivity: Some(preferences.shake_sensitivity),
+            magnifier_zoom: Some(preferences.magnifier_zoom),
+            magnifier_size: Some(preferences.magnifier_size),
+            launch_at_login: Some(preferences.launch_at_login),
+        }
+    }
+
+    /// Read a settings file, such as one written by `export-settings`
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let settings: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        if settings == Self::default() {
+            anyhow::bail!("No CursorHome settings found in {}", path.display());
+        }
+        Ok(settings)
+    }
+
+    /// Write the settings as pretty-printed JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Copy the settings in the file into `preferences`, then clamp values
+    /// to their allowed ranges, returning what was changed
+    pub fn apply_to(&self, preferences: &mut Preferences) -> Vec<Correction> {
+        if let Some(enabled) = self.enabled {
+            preferences.enabled = enabled;
+        }
+        if let Some(style) = &self.cursor_style {
+            preferences.cursor_style = style.clone();
+        }
+        if let Some(animation) = &self.animation_style {
+            preferences.animation_style = animation.clone().into();
+        }
+        if let Some(duration) = self.highlight_duration {
+            preferences.highlight_duration = duration;
+        }
+        if let Some(shake) = self.auto_highlight_on_shake {
+            preferences.shake_enabled = shake;
+        }
+        if let Some(sensitivity) = self.shake_sensitivity {
+            preferences.shake_sensitivity = sensitivity;
+        }
+        if let Some(zoom) = self.magnifier_zoom {
+            preferences.magnifier_zoom = zoom;
+        }
+        if let Some(size) = self.magnifier_size {
+            preferences.magnifier_size = size;
+        }
+        if let Some(launch) = self.launch_at_login {
+            preferences.launch_at_login = launch;
+        }
+
+        preferences.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const CURSOR_STYLE_SCHEMA: &str = include_str!("../../../../shared/models/cursor_style.json");
+    const ANIMATION_STYLE_SCHEMA: &str =
+        include_str!("../../../../shared/models/animation_style.json");
+
+    /// The `examples` of a schema, without their `name`
+    fn examples(schema: &str) -> Vec<Value> {
+        let schema: Value = serde_json::from_str(schema).unwrap();
+        let examples = schema["examples"].as_array().unwrap().clone();
+        assert!(!examples.is_empty());
+        examples
+            .into_iter()
+            .map(|mut example| {
+                example.as_object_mut().unwrap().remove("name");
+                example
+            })
+            .collect()
+    }
+
+    /// Assert that `instance` conforms to `schema`
+    fn assert_valid(schema: &str, instance: &Value) {
+        let schema: Value = serde_json::from_str(schema).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let errors: Vec<String> = validator
+            .iter_errors(instance)
+            .map(|e| format!("{} at {}", e, e.instance_path))
+            .collect();
+        assert!(errors.is_empty(), "{}: {:?}", instance, errors);
+    }
+
+    /// Every object key in `value` and below, nested ones included
+    fn keys(value: &Value, out: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    out.push(key.clone());
+                    keys(value, out);
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| keys(item, out)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_shared_fields() {
+        let mut original = Preferences::default();
+        let preset = original.presets.last().unwrap().clone();
+        original.enabled = false;
+        original.cursor_style = preset.cursor_style;
+        original.animation_style = preset.animation_style;
+        original.highlight_duration = 7.5;
+        original.shake_enabled = !original.shake_enabled;
+        original.shake_sensitivity = 0.25;
+        original.magnifier_zoom = 3.5;
+        original.magnifier_size = 300.0;
+        original.launch_at_login = !original.launch_at_login;
+
+        let json = serde_json::to_string(&SharedSettings::from_preferences(&original)).unwrap();
+        let settings: SharedSettings = serde_json::from_str(&json).unwrap();
+        let mut imported = Preferences::default();
+        let corrections = settings.apply_to(&mut imported);
+
+        assert!(corrections.is_empty());
+        assert_eq!(imported.enabled, original.enabled);
+        assert_eq!(imported.cursor_style, original.cursor_style);
+        assert_eq!(imported.animation_style, original.animation_style);
+        assert_eq!(imported.highlight_duration, original.highlight_duration);
+        assert_eq!(imported.shake_enabled, original.shake_enabled);
+        assert_eq!(imported.shake_sensitivity, original.shake_sensitivity);
+        assert_eq!(imported.magnifier_zoom, original.magnifier_zoom);
+        assert_eq!(imported.magnifier_size, original.magnifier_size);
+        assert_eq!(imported.launch_at_login, original.launch_at_login);
+    }
+
+    #[test]
+    fn schema_examples_parse() {
+        for example in examples(CURSOR_STYLE_SCHEMA) {
+            let parsed = serde_json::from_value::<CursorStyle>(example.clone());
+            assert!(parsed.is_ok(), "{}: {:?}", example, parsed.err());
+        }
+        for example in examples(ANIMATION_STYLE_SCHEMA) {
+            let parsed = serde_json::from_value::<SharedAnimationStyle>(example.clone());
+            assert!(parsed.is_ok(), "{}: {:?}", example, parsed.err());
+        }
+    }
+
+    #[test]
+    fn schema_examples_conform_to_their_schemas() {
+        for example in examples(CURSOR_STYLE_SCHEMA) {
+            assert_valid(CURSOR_STYLE_SCHEMA, &example);
+        }
+        for example in examples(ANIMATION_STYLE_SCHEMA) {
+            assert_valid(ANIMATION_STYLE_SCHEMA, &example);
+        }
+    }
+
+    #[test]
+    fn exported_styles_conform_to_the_schemas() {
+        let mut preferences = Preferences::default();
+        let presets = preferences.presets.clone();
+        for preset in std::iter::once(None).chain(presets.into_iter().map(Some)) {
+            if let Some(preset) = preset {
+                preferences.cursor_style = preset.cursor_style;
+                preferences.animation_style = preset.animation_style;
+            }
+            let value =
+                serde_json::to_value(SharedSettings::from_preferences(&preferences)).unwrap();
+            assert_valid(CURSOR_STYLE_SCHEMA, &value["cursorStyle"]);
+            assert_valid(ANIMATION_STYLE_SCHEMA, &value["animationStyle"]);
+        }
+    }
+
+    #[test]
+    fn top_level_keys_are_camel_case_and_styles_snake_case() {
+        let value = serde_json::to_value(SharedSettings::from_preferences(&Preferences::default()))
+            .unwrap();
+        let top = value.as_object().unwrap();
+
+        for key in top.keys() {
+            assert!(!key.contains('_'), "top-level key {} is not camelCase", key);
+        }
+        assert!(top.contains_key("autoHighlightOnShake"));
+        assert!(top.contains_key("highlightDuration"));
+
+        for style in ["cursorStyle", "animationStyle"] {
+            let mut nested = Vec::new();
+            keys(&top[style], &mut nested);
+            assert!(!nested.is_empty());
+            for key in nested {
+                assert!(
+                    !key.chars().any(|c| c.is_ascii_uppercase()),
+                    "{} key {} is not snake_case",
+                    style,
+                    key
+                );
+            }
+        }
+        assert!(top["cursorStyle"].get("border_weight").is_some());
+        assert!(top["animationStyle"].get("repeat_count").is_some());
+        assert!(top["animationStyle"].get("type").is_some());
+    }
+}
@@ -0,0 +1,223 @@
+//! Keyboard shortcut and trigger bindings
+
+use serde::{Deserialize, Serialize};
+
+/// An action that can be bound to a global shortcut
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    FindCursor,
+    ToggleEnabled,
+    Teleport,
+    FollowMode,
+    NextPreset,
+}
+
+impl ShortcutAction {
+    pub fn all() -> &'static [ShortcutAction] {
+        &[
+            ShortcutAction::FindCursor,
+            ShortcutAction::ToggleEnabled,
+            ShortcutAction::Teleport,
+            ShortcutAction::FollowMode,
+            ShortcutAction::NextPreset,
+        ]
+    }
+
+    /// Identifier used for both the GIO action and the portal shortcut id
+    pub fn id(&self) -> &'static str {
+        match self {
+            ShortcutAction::FindCursor => "find-cursor",
+            ShortcutAction::ToggleEnabled => "toggle-enabled",
+            ShortcutAction::Teleport => "teleport",
+            ShortcutAction::FollowMode => "follow-mode",
+            ShortcutAction::NextPreset => "next-preset",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::all().iter().copied().find(|action| action.id() == id)
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ShortcutAction::FindCursor => "Find cursor",
+            ShortcutAction::ToggleEnabled => "Enable or disable CursorHome",
+            ShortcutAction::Teleport => "Move cursor to main display",
+            ShortcutAction::FollowMode => "Toggle follow mode",
+            ShortcutAction::NextPreset => "Switch to next style preset",
+        }
+    }
+}
+
+/// Accelerators for each shortcut action, in GTK accelerator syntax
+/// (e.g. `<Primary><Shift>f`). `None` leaves the action unbound.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutBindings {
+    pub find_cursor: Option<String>,
+    pub toggle_enabled: Option<String>,
+    pub teleport: Option<String>,
+    pub follow_mode: Option<String>,
+    pub next_preset: Option<String>,
+}
+
+impl Default for ShortcutBindings {
+    fn default() -> Self {
+        Self {
+            find_cursor: Some("<Primary><Shift>f".to_string()),
+            toggle_enabled: Some("<Primary><Shift>e".to_string()),
+            teleport: Some("<Primary><Shift>h".to_string()),
+            follow_mode: None,
+            next_preset: None,
+        }
+    }
+}
+
+impl ShortcutBindings {
+    /// Get the accelerator bound to an action
+    pub fn get(&self, action: ShortcutAction) -> Option<&str> {
+        match action {
+            ShortcutAction::FindCursor => self.find_cursor.as_deref(),
+            ShortcutAction::ToggleEnabled => self.toggle_enabled.as_deref(),
+            ShortcutAction::Teleport => self.teleport.as_deref(),
+            ShortcutAction::FollowMode => self.follow_mode.as_deref(),
+            ShortcutAction::NextPreset => self.next_preset.as_deref(),
+        }
+    }
+
+    /// Bind an action to an accelerator, or unbind it with `None`
+    pub fn set(&mut self, action: ShortcutAction, accelerator: Option<String>) {
+        let slot = match action {
+            ShortcutAction::FindCursor => &mut self.find_cursor,
+            ShortcutAction::ToggleEnabled => &mut self.toggle_enabled,
+            ShortcutAction::Teleport => &mut self.teleport,
+            ShortcutAction::FollowMode => &mut self.follow_mode,
+            ShortcutAction::NextPreset => &mut self.next_preset,
+        };
+        *slot = accelerator;
+    }
+
+    /// Get the binding for an action as an XDG shortcuts trigger
+    /// (e.g. `CTRL+SHIFT+f`), as expected by the GlobalShortcuts portal
+    pub fn trigger(&self, action: ShortcutAction) -> Option<String> {
+        self.get(action).and_then(accelerator_to_trigger)
+    }
+
+    /// Find what an accelerator would clash with if bound to `action`
+    ///
+    /// Returns the display name of the other action or reserved shortcut
+    /// that already uses the same key combination.
+    pub fn conflict(&self, action: ShortcutAction, accelerator: &str) -> Option<&'static str> {
+        let trigger = accelerator_to_trigger(accelerator)?.to_lowercase();
+        let same = |other: &str| {
+            accelerator_to_trigger(other).is_some_and(|t| t.to_lowercase() == trigger)
+        };
+
+        if let Some((_, name)) = RESERVED_ACCELERATORS.iter().find(|(accel, _)| same(accel)) {
+            return Some(*name);
+        }
+
+        ShortcutAction::all()
+            .iter()
+            .filter(|other| **other != action)
+            .find(|other| self.get(**other).is_some_and(&same))
+            .map(|other| other.display_name())
+    }
+}
+
+/// Application accelerators that cannot be rebound, with their descriptions
+pub const RESERVED_ACCELERATORS: &[(&str, &str)] =
+    &[("<Primary>comma", "Open settings"), ("<Primary>q", "Quit")];
+
+/// Convert a GTK accelerator string to the XDG shortcuts trigger format
+///
+/// Modifiers are emitted in a fixed order so that equivalent accelerators
+/// such as `<Shift><Primary>f` and `<Control><Shift>f` compare equal.
+fn accelerator_to_trigger(accelerator: &str) -> Option<String> {
+    const ORDER: [&str; 4] = ["CTRL", "ALT", "SHIFT", "LOGO"];
+
+    let mut modifiers = [false; 4];
+    let mut rest = accelerator.trim();
+
+    while let Some(stripped) = rest.strip_prefix('<') {
+        let end = stripped.find('>')?;
+        let index = match stripped[..end].to_lowercase().as_str() {
+            "primary" | "control" | "ctrl" | "ctl" => 0,
+            "alt" | "mod1" => 1,
+            "shift" => 2,
+            "super" | "meta" | "hyper" | "mod4" => 3,
+            _ => return None,
+        };
+        modifiers[index] = true;
+        rest = &stripped[end + 1..];
+    }
+
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut parts: Vec<&str> = ORDER
+        .iter()
+        .zip(modifiers)
+        .filter(|(_, set)| *set)
+        .map(|(name, _)| *name)
+        .collect();
+    parts.push(rest);
+    Some(parts.join("+"))
+}
+
+/// Modifier key that can be double-tapped to find the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TapModifier {
+    #[default]
+    Control,
+    Shift,
+    Alt,
+    Super,
+}
+
+impl TapModifier {
+    pub fn all() -> &'static [TapModifier] {
+        &[
+            TapModifier::Control,
+            TapModifier::Shift,
+            TapModifier::Alt,
+            TapModifier::Super,
+        ]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TapModifier::Control => "Ctrl",
+            TapModifier::Shift => "Shift",
+            TapModifier::Alt => "Alt",
+            TapModifier::Super => "Super",
+        }
+    }
+}
+
+/// "Tap a modifier twice" trigger configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TapTrigger {
+    /// Whether double-tapping the modifier finds the cursor
+    pub enabled: bool,
+
+    /// Modifier key to watch (either the left or right key counts)
+    pub modifier: TapModifier,
+
+    /// Maximum time between the two taps, and for each tap to be held (seconds)
+    pub interval: f64,
+}
+
+impl Default for TapTrigger {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            modifier: TapModifier::Control,
+            interval: 0.4,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Text shown next to the cursor after it returns from another machine
+
+use super::Color;
+use gtk4::glib;
+use serde::{Deserialize, Serialize};
+
+/// Where a label sits relative to the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelPosition {
+    #[default]
+    Below,
+    Above,
+    Left,
+    Right,
+}
+
+impl LabelPosition {
+    pub fn all() -> &'static [LabelPosition] {
+        &[
+            LabelPosition::Below,
+            LabelPosition::Above,
+            LabelPosition::Left,
+            LabelPosition::Right,
+        ]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            LabelPosition::Below => "Below",
+            LabelPosition::Above => "Above",
+            LabelPosition::Left => "Left",
+            LabelPosition::Right => "Right",
+        }
+    }
+}
+
+/// Label shown with the highlight when the cursor comes back from another
+/// Synergy machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransitionLabel {
+    pub enabled: bool,
+
+    /// Pango font description, e.g. "Sans Bold 14"
+    pub font: String,
+
+    pub text_color: Color,
+
+    /// Color of the rounded background behind the text
+    pub background: Color,
+
+    pub position: LabelPosition,
+}
+
+impl Default for TransitionLabel {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            font: "Sans Bold 14".to_string(),
+            text_color: Color::new(255, 255, 255, 1.0),
+            background: Color::new(0, 0, 0, 0.75),
+            position: LabelPosition::Below,
+        }
+    }
+}
+
+impl TransitionLabel {
+    /// Text for the cursor arriving on this machine from `screen_name`
+    pub fn text(&self, screen_name: &str) -> String {
+        format!("Back on {} from {}", glib::host_name(), screen_name)
+    }
+}
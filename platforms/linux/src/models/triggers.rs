@@ -0,0 +1,166 @@
+//! Highlight trigger sources and per-trigger style overrides
+
+use super::{AnimationStyle, CursorStyle, Preferences, StylePreset};
+use serde::{Deserialize, Serialize};
+
+/// What caused a highlight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Keyboard shortcut, modifier double-tap or tray icon
+    Hotkey,
+    /// Mouse shake
+    Shake,
+    /// Cursor returned from another Synergy machine
+    Synergy,
+    /// Another application, through the `find-cursor-from` D-Bus action
+    DBus,
+    /// Request from another CursorHome machine
+    Peer,
+}
+
+impl Trigger {
+    pub fn all() -> &'static [Trigger] {
+        &[
+            Trigger::Hotkey,
+            Trigger::Shake,
+            Trigger::Synergy,
+            Trigger::DBus,
+            Trigger::Peer,
+        ]
+    }
+
+    /// Identifier used in preferences and D-Bus calls
+    pub fn id(&self) -> &'static str {
+        match self {
+            Trigger::Hotkey => "hotkey",
+            Trigger::Shake => "shake",
+            Trigger::Synergy => "synergy",
+            Trigger::DBus => "dbus",
+            Trigger::Peer => "peer",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|trigger| trigger.id() == id)
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Trigger::Hotkey => "Shortcut or tray",
+            Trigger::Shake => "Mouse shake",
+            Trigger::Synergy => "Synergy return",
+            Trigger::DBus => "Other applications (D-Bus)",
+            Trigger::Peer => "Peer request",
+        }
+    }
+}
+
+/// Settings that replace the default style for one trigger
+///
+/// Fields left as `None` fall back to the default style.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleOverride {
+    pub cursor_style: Option<CursorStyle>,
+    pub animation_style: Option<AnimationStyle>,
+    /// Duration to show highlight (seconds)
+    pub highlight_duration: Option<f64>,
+}
+
+impl StyleOverride {
+    /// Override every setting with those of a preset
+    pub fn from_preset(preset: &StylePreset) -> Self {
+        Self {
+            cursor_style: Some(preset.cursor_style.clone()),
+            animation_style: Some(preset.animation_style.clone()),
+            highlight_duration: Some(preset.highlight_duration),
+        }
+    }
+
+    /// Whether this overrides anything
+    pub fn is_empty(&self) -> bool {
+        self.cursor_style.is_none()
+            && self.animation_style.is_none()
+            && self.highlight_duration.is_none()
+    }
+
+    /// Whether this overrides everything with exactly the preset's settings
+    pub fn matches_preset(&self, preset: &StylePreset) -> bool {
+        *self == Self::from_preset(preset)
+    }
+}
+
+/// Style overrides for each trigger
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TriggerStyles {
+    pub hotkey: StyleOverride,
+    pub shake: StyleOverride,
+    pub synergy: StyleOverride,
+    pub dbus: StyleOverride,
+    pub peer: StyleOverride,
+}
+
+impl TriggerStyles {
+    pub fn get(&self, trigger: Trigger) -> &StyleOverride {
+        match trigger {
+            Trigger::Hotkey => &self.hotkey,
+            Trigger::Shake => &self.shake,
+            Trigger::Synergy => &self.synergy,
+            Trigger::DBus => &self.dbus,
+            Trigger::Peer => &self.peer,
+        }
+    }
+
+    pub fn get_mut(&mut self, trigger: Trigger) -> &mut StyleOverride {
+        match trigger {
+            Trigger::Hotkey => &mut self.hotkey,
+            Trigger::Shake => &mut self.shake,
+            Trigger::Synergy => &mut self.synergy,
+            Trigger::DBus => &mut self.dbus,
+            Trigger::Peer => &mut self.peer,
+        }
+    }
+}
+
+/// Everything needed to draw one highlight
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightStyle {
+    pub cursor_style: CursorStyle,
+    pub animation_style: AnimationStyle,
+    /// Duration to show highlight (seconds)
+    pub duration: f64,
+}
+
+impl Preferences {
+    /// Style for a highlight without any trigger's overrides
+    pub fn base_highlight_style(&self) -> HighlightStyle {
+        HighlightStyle {
+            cursor_style: self.cursor_style.clone(),
+            animation_style: self.animation_style.clone(),
+            duration: self.highlight_duration,
+        }
+    }
+
+    /// Style for a highlight caused by `trigger`, with its overrides applied
+    pub fn highlight_style(&self, trigger: Trigger) -> HighlightStyle {
+        let overrides = self.trigger_styles.get(trigger);
+
+        HighlightStyle {
+            cursor_style: overrides
+                .cursor_style
+                .clone()
+                .unwrap_or_else(|| self.cursor_style.clone()),
+            animation_style: overrides
+                .animation_style
+                .clone()
+                .unwrap_or_else(|| self.animation_style.clone()),
+            duration: overrides
+                .highlight_duration
+                .unwrap_or(self.highlight_duration),
+        }
+    }
+}
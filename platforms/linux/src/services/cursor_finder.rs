@@ -2,8 +2,11 @@
 //!
 //! Handles cursor highlighting using X11 ARGB overlay.
 
-use crate::models::{AnimationStyle, CursorStyle, Preferences};
-use crate::services::DisplayManager;
+use crate::models::{
+    AnimationStyle, CursorStyle, DesktopAppearance, HighlightStyle, PreferencesStore, Trigger,
+};
+use crate::services::display_manager::{self, DisplayManager};
+use crate::services::notifications::{show_notification, ERROR_ICON};
 use crate::ui::X11Overlay;
 use anyhow::Result;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -12,27 +15,78 @@ use std::thread;
 
 /// Service for finding and highlighting the cursor
 pub struct CursorFinderService {
-    preferences: Arc<Preferences>,
+    preferences: PreferencesStore,
     display_manager: DisplayManager,
+    appearance: DesktopAppearance,
     is_highlighting: Arc<AtomicBool>,
+    is_following: Arc<AtomicBool>,
 }
 
 impl CursorFinderService {
     /// Create a new cursor finder service
-    pub fn new(preferences: Arc<Preferences>) -> Self {
+    ///
+    /// Preferences are read from the store each time a highlight starts, so
+    /// changes apply to the next highlight without restarting the service.
+    pub fn new(preferences: PreferencesStore) -> Self {
         Self {
             preferences,
             display_manager: DisplayManager::new(),
+            appearance: DesktopAppearance::default(),
             is_highlighting: Arc::new(AtomicBool::new(false)),
+            is_following: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Use the desktop's accent color and color scheme for accent paints,
+    /// and its accessibility settings for every highlight
+    pub fn set_appearance(&mut self, appearance: DesktopAppearance) {
+        self.appearance = appearance;
+    }
+
+    /// The desktop appearance accent paints are drawn with
+    pub fn appearance(&self) -> DesktopAppearance {
+        self.appearance
+    }
+
+    /// Whether highlighting is currently enabled
+    pub fn is_enabled(&self) -> bool {
+        self.preferences.with(|p| p.enabled)
+    }
+
     /// Find and highlight the cursor
-    pub fn find_cursor(&mut self) {
-        if !self.preferences.enabled {
+    ///
+    /// The highlight uses the style configured for `trigger`, falling back to
+    /// the default style for anything it does not override.
+    pub fn find_cursor(&mut self, trigger: Trigger) {
+        self.highlight(trigger, None);
+    }
+
+    /// Find and highlight the cursor with `text` shown beside it, in the
+    /// transition label style
+    pub fn find_cursor_with_label(&mut self, trigger: Trigger, text: String) {
+        self.highlight(trigger, Some(text));
+    }
+
+    /// Highlight the cursor with the default style, ignoring trigger
+    /// overrides and whether highlighting is enabled, so the settings being
+    /// edited can be tried out
+    pub fn test_highlight(&mut self) {
+        tracing::debug!("Test highlight requested");
+        let style = self.preferences.with(|p| p.base_highlight_style());
+        self.show(style, None);
+    }
+
+    fn highlight(&mut self, trigger: Trigger, label: Option<String>) {
+        if !self.is_enabled() {
             return;
         }
 
+        tracing::debug!("Highlight triggered by {}", trigger.id());
+        let style = self.preferences.with(|p| p.highlight_style(trigger));
+        self.show(style, label);
+    }
+
+    fn show(&mut self, mut style: HighlightStyle, label: Option<String>) {
         // Don't start a new highlight if one is already running
         if self.is_highlighting.load(Ordering::SeqCst) {
             tracing::debug!("Highlight already in progress, skipping");
@@ -41,9 +95,14 @@ impl CursorFinderService {
 
         // Clone values for the thread
         let is_highlighting = self.is_highlighting.clone();
-        let cursor_style = self.preferences.cursor_style.clone();
-        let animation_style = self.preferences.animation_style.clone();
-        let duration = self.preferences.highlight_duration;
+        self.apply_appearance(&mut style.cursor_style, &mut style.animation_style);
+        let (label_style, notify_errors, show_frame_stats) = self.preferences.with(|p| {
+            (
+                p.transition_label.clone(),
+                p.notifications.overlay_errors,
+                p.show_frame_stats,
+            )
+        });
 
         // Run the highlight in a separate thread (X11 overlay has blocking animation)
         thread::spawn(move || {
@@ -52,13 +111,22 @@ impl CursorFinderService {
             match X11Overlay::new() {
                 Ok(mut overlay) => {
                     tracing::info!("X11 overlay created, starting highlight");
-                    if let Err(e) = overlay.show_highlight(&cursor_style, &animation_style, duration)
-                    {
+                    overlay.set_show_frame_stats(show_frame_stats);
+                    if let Some(text) = &label {
+                        overlay.set_label(text, &label_style);
+                    }
+                    if let Err(e) = overlay.show_highlight(
+                        &style.cursor_style,
+                        &style.animation_style,
+                        style.duration,
+                    ) {
                         tracing::error!("Error during highlight: {}", e);
+                        report_overlay_error(&e, notify_errors);
                     }
                 }
                 Err(e) => {
                     tracing::error!("Failed to create X11 overlay: {}", e);
+                    report_overlay_error(&e, notify_errors);
                 }
             }
 
@@ -67,8 +135,118 @@ impl CursorFinderService {
         });
     }
 
+    /// Move the cursor to the center of the primary display and highlight it
+    pub fn teleport(&mut self) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        self.display_manager.refresh_displays();
+        let Some(display) = self.display_manager.primary_display() else {
+            anyhow::bail!("No display to move the cursor to");
+        };
+
+        let (x, y) = display.center();
+        display_manager::move_cursor_to(x as f64, y as f64)?;
+        tracing::info!(
+            "Moved cursor to center of {} at ({}, {})",
+            display.name,
+            x,
+            y
+        );
+
+        self.find_cursor(Trigger::Hotkey);
+        Ok(())
+    }
+
+    /// Whether follow mode is active
+    pub fn is_follow_mode(&self) -> bool {
+        self.is_following.load(Ordering::SeqCst)
+    }
+
+    /// Turn follow mode on or off
+    ///
+    /// In follow mode the highlight stays on screen and tracks the cursor
+    /// until follow mode is turned off again.
+    pub fn set_follow_mode(&mut self, follow: bool) {
+        if follow == self.is_follow_mode() {
+            return;
+        }
+
+        if !follow {
+            // The overlay thread sees the cleared flag and exits on its next frame
+            self.is_following.store(false, Ordering::SeqCst);
+            tracing::info!("Follow mode stopped");
+            return;
+        }
+
+        if !self.is_enabled() {
+            return;
+        }
+
+        self.is_following.store(true, Ordering::SeqCst);
+
+        let is_following = self.is_following.clone();
+        let (mut cursor_style, mut animation_style, notify_errors, show_frame_stats) =
+            self.preferences.with(|p| {
+                (
+                    p.cursor_style.clone(),
+                    p.animation_style.clone(),
+                    p.notifications.overlay_errors,
+                    p.show_frame_stats,
+                )
+            });
+        self.apply_appearance(&mut cursor_style, &mut animation_style);
+
+        thread::spawn(move || {
+            match X11Overlay::new() {
+                Ok(mut overlay) => {
+                    tracing::info!("Follow mode started");
+                    overlay.set_show_frame_stats(show_frame_stats);
+                    if let Err(e) =
+                        overlay.show_highlight_while(&cursor_style, &animation_style, &is_following)
+                    {
+                        tracing::error!("Error during follow mode: {}", e);
+                        report_overlay_error(&e, notify_errors);
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to create X11 overlay: {}", e);
+                    report_overlay_error(&e, notify_errors);
+                }
+            }
+
+            is_following.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Resolve accent paints, then follow the desktop's accessibility
+    /// settings unless the user has turned that off
+    fn apply_appearance(
+        &self,
+        cursor_style: &mut CursorStyle,
+        animation_style: &mut AnimationStyle,
+    ) {
+        self.appearance.apply_to(cursor_style);
+        if self.preferences.with(|p| p.follow_desktop_accessibility) {
+            self.appearance
+                .apply_accessibility(cursor_style, animation_style);
+        }
+    }
+
     /// Refresh display information
     pub fn refresh_displays(&mut self) {
         self.display_manager.refresh_displays();
     }
 }
+
+/// Tell the user a highlight could not be shown, if they want to know
+fn report_overlay_error(error: &anyhow::Error, notify: bool) {
+    if notify {
+        show_notification(
+            "Could not show the cursor highlight",
+            format!("{:#}", error),
+            ERROR_ICON,
+        );
+    }
+}
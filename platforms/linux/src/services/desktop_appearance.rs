@@ -0,0 +1,433 @@
+//! Desktop appearance through the XDG desktop portal and GTK
+//!
+//! Reads the accent color, light/dark preference and contrast from the
+//! `org.freedesktop.appearance` namespace of `org.freedesktop.portal.Settings`,
+//! and whether animations are enabled from GNOME's interface settings. GTK's
+//! `gtk-enable-animations` and libadwaita's high-contrast flag are merged in,
+//! so desktops without the portal keys are still followed. Changes are
+//! reported as the user switches theme.
+
+use crate::models::{Color, ColorScheme, DesktopAppearance};
+use anyhow::Result;
+use ashpd::desktop::settings::Settings;
+use ashpd::zvariant::Value;
+use futures_util::StreamExt;
+use gtk4::glib::{self, SignalHandlerId};
+use gtk4::prelude::*;
+use libadwaita as adw;
+use std::cell::Cell;
+use std::rc::Rc;
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const ACCENT_COLOR_KEY: &str = "accent-color";
+const COLOR_SCHEME_KEY: &str = "color-scheme";
+const CONTRAST_KEY: &str = "contrast";
+
+const GNOME_INTERFACE_NAMESPACE: &str = "org.gnome.desktop.interface";
+const ENABLE_ANIMATIONS_KEY: &str = "enable-animations";
+
+/// Follows the desktop's accent color, color scheme and accessibility
+/// settings
+pub struct DesktopAppearanceMonitor {
+    task: Option<glib::JoinHandle<()>>,
+    /// Property notifications on GTK and libadwaita settings objects
+    toolkit_handlers: Vec<(glib::Object, SignalHandlerId)>,
+}
+
+impl DesktopAppearanceMonitor {
+    /// Create a new desktop appearance monitor
+    pub fn new() -> Self {
+        Self {
+            task: None,
+            toolkit_handlers: Vec::new(),
+        }
+    }
+
+    /// Start watching, calling `on_change` with the current appearance and
+    /// again whenever it changes
+    pub fn start_with_callback<F>(&mut self, on_change: F)
+    where
+        F: Fn(DesktopAppearance) + 'static,
+    {
+        self.stop();
+
+        // What the portal reports, merged with the toolkit's settings each
+        // time either changes
+        let portal = Rc::new(Cell::new(DesktopAppearance::default()));
+        let reported = Rc::new(Cell::new(None));
+        let report: Rc<dyn Fn()> = {
+            let portal = portal.clone();
+            Rc::new(move || {
+                let appearance = with_toolkit_settings(portal.get());
+                if reported.get() != Some(appearance) {
+                    tracing::info!("Desktop appearance: {:?}", appearance);
+                    reported.set(Some(appearance));
+                    on_change(appearance);
+                }
+            })
+        };
+        report();
+
+        if let Some(settings) = gtk4::Settings::default() {
+            let report = report.clone();
+            let handler = settings.connect_gtk_enable_animations_notify(move |_| report());
+            self.toolkit_handlers.push((settings.upcast(), handler));
+        }
+        let style_manager = adw::StyleManager::default();
+        let report_contrast = report.clone();
+        let handler = style_manager.connect_high_contrast_notify(move |_| report_contrast());
+        self.toolkit_handlers
+            .push((style_manager.upcast(), handler));
+
+        let task = glib::MainContext::default().spawn_local(async move {
+            if let Err(e) = Self::run(&portal, report.as_ref()).await {
+                tracing::info!("Desktop appearance portal unavailable: {}", e);
+            }
+        });
+        self.task = Some(task);
+    }
+
+    /// Stop watching
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        for (object, handler) in self.toolkit_handlers.drain(..) {
+            object.disconnect(handler);
+        }
+    }
+
+    async fn run(portal: &Cell<DesktopAppearance>, report: &dyn Fn()) -> Result<()> {
+        let settings = Settings::new().await?;
+
+        // Subscribe before reading so no change is missed in between
+        let mut changes = settings.receive_setting_changed().await?;
+
+        let mut appearance = DesktopAppearance::default();
+        let namespaces = settings
+            .read_all(&[APPEARANCE_NAMESPACE, GNOME_INTERFACE_NAMESPACE])
+            .await?;
+        for (namespace, values) in &namespaces {
+            for (key, value) in values {
+                Self::update(&mut appearance, namespace, key, value);
+            }
+        }
+        portal.set(appearance);
+        report();
+
+        while let Some(setting) = changes.next().await {
+            Self::update(
+                &mut appearance,
+                setting.namespace(),
+                setting.key(),
+                setting.value(),
+            );
+            portal.set(appearance);
+            report();
+        }
+
+        Ok(())
+    }
+
+    fn update(appearance: &mut DesktopAppearance, namespace: &str, key: &str, value: &Value<'_>) {
+        match (namespace, key) {
+            (APPEARANCE_NAMESPACE, ACCENT_COLOR_KEY) => {
+                appearance.accent_color = parse_accent_color(value)
+            }
+            (APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY) => {
+                appearance.color_scheme = parse_color_scheme(value)
+            }
+            (APPEARANCE_NAMESPACE, CONTRAST_KEY) => {
+                appearance.high_contrast = matches!(unwrap_variant(value), Value::U32(1))
+            }
+            (GNOME_INTERFACE_NAMESPACE, ENABLE_ANIMATIONS_KEY) => {
+                appearance.reduce_motion = matches!(unwrap_variant(value), Value::Bool(false))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Add GTK's animation setting and libadwaita's high-contrast flag to what
+/// the portal reports
+fn with_toolkit_settings(portal: DesktopAppearance) -> DesktopAppearance {
+    let animations = gtk4::Settings::default().is_none_or(|s| s.is_gtk_enable_animations());
+    let high_contrast = adw::StyleManager::default().is_high_contrast();
+    DesktopAppearance {
+        reduce_motion: portal.reduce_motion || !animations,
+        high_contrast: portal.high_contrast || high_contrast,
+        ..portal
+    }
+}
+
+/// Parse an `(ddd)` RGB accent color; components outside 0-1 mean unset
+fn parse_accent_color(value: &Value<'_>) -> Option<Color> {
+    let Value::Structure(structure) = unwrap_variant(value) else {
+        return None;
+    };
+
+    let components: Vec<f64> = structure
+        .fields()
+        .iter()
+        .filter_map(|field| match field {
+            Value::F64(component) => Some(*component),
+            _ => None,
+        })
+        .collect();
+    let &[r, g, b] = components.as_slice() else {
+        return None;
+    };
+    if ![r, g, b].iter().all(|c| (0.0..=1.0).contains(c)) {
+        return None;
+    }
+
+    let channel = |c: f64| (c * 255.0).round() as u8;
+    Some(Color::new(channel(r), channel(g), channel(b), 1.0))
+}
+
+/// Parse a color scheme: 1 prefers dark, 2 prefers light
+fn parse_color_scheme(value: &Value<'_>) -> ColorScheme {
+    match unwrap_variant(value) {
+        Value::U32(1) => ColorScheme::Dark,
+        Value::U32(2) => ColorScheme::Light,
+        _ => ColorScheme::NoPreference,
+    }
+}
+
+/// Values read from the portal may arrive wrapped in a variant
+fn unwrap_variant<'a>(value: &'a Value<'a>) -> &'a Value<'a> {
+    match value {
+        Value::Value(inner) => unwrap_variant(inner),
+        value => value,
+    }
+}
+
+impl Default for DesktopAppearanceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for DesktopAppearanceMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus;
+    use ashpd::zvariant::OwnedValue;
+    use futures_util::future::{self, Either};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::pin::pin;
+    use std::time::{Duration, Instant};
+    use zbus::dbus_interface;
+
+    fn accent(r: f64, g: f64, b: f64) -> Value<'static> {
+        Value::from((r, g, b))
+    }
+
+    fn updated(namespace: &str, key: &str, value: Value<'_>) -> DesktopAppearance {
+        let mut appearance = DesktopAppearance::default();
+        DesktopAppearanceMonitor::update(&mut appearance, namespace, key, &value);
+        appearance
+    }
+
+    #[test]
+    fn accent_color_is_scaled_to_bytes() {
+        assert_eq!(
+            parse_accent_color(&accent(0.2, 0.4, 1.0)),
+            Some(Color::new(51, 102, 255, 1.0))
+        );
+        assert_eq!(
+            parse_accent_color(&Value::Value(Box::new(accent(0.0, 0.5, 1.0)))),
+            Some(Color::new(0, 128, 255, 1.0))
+        );
+    }
+
+    #[test]
+    fn accent_color_outside_range_or_malformed_is_unset() {
+        assert_eq!(parse_accent_color(&accent(-1.0, -1.0, -1.0)), None);
+        assert_eq!(parse_accent_color(&accent(0.5, 1.5, 0.5)), None);
+        assert_eq!(parse_accent_color(&Value::from((0.5, 0.5))), None);
+        assert_eq!(parse_accent_color(&Value::from((1u32, 1u32, 1u32))), None);
+        assert_eq!(parse_accent_color(&Value::U32(1)), None);
+    }
+
+    #[test]
+    fn color_scheme_values() {
+        assert_eq!(
+            parse_color_scheme(&Value::U32(0)),
+            ColorScheme::NoPreference
+        );
+        assert_eq!(parse_color_scheme(&Value::U32(1)), ColorScheme::Dark);
+        assert_eq!(parse_color_scheme(&Value::U32(2)), ColorScheme::Light);
+        assert_eq!(
+            parse_color_scheme(&Value::U32(3)),
+            ColorScheme::NoPreference
+        );
+        assert_eq!(
+            parse_color_scheme(&Value::Value(Box::new(Value::U32(1)))),
+            ColorScheme::Dark
+        );
+        assert_eq!(
+            parse_color_scheme(&Value::I32(1)),
+            ColorScheme::NoPreference
+        );
+    }
+
+    #[test]
+    fn update_sets_each_key() {
+        let appearance = updated(
+            APPEARANCE_NAMESPACE,
+            ACCENT_COLOR_KEY,
+            accent(1.0, 0.0, 0.0),
+        );
+        assert_eq!(appearance.accent_color, Some(Color::new(255, 0, 0, 1.0)));
+
+        let appearance = updated(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY, Value::U32(1));
+        assert_eq!(appearance.color_scheme, ColorScheme::Dark);
+
+        assert!(updated(APPEARANCE_NAMESPACE, CONTRAST_KEY, Value::U32(1)).high_contrast);
+        assert!(!updated(APPEARANCE_NAMESPACE, CONTRAST_KEY, Value::U32(0)).high_contrast);
+
+        let key = ENABLE_ANIMATIONS_KEY;
+        assert!(updated(GNOME_INTERFACE_NAMESPACE, key, Value::Bool(false)).reduce_motion);
+        assert!(!updated(GNOME_INTERFACE_NAMESPACE, key, Value::Bool(true)).reduce_motion);
+    }
+
+    #[test]
+    fn update_ignores_other_keys() {
+        let unchanged = DesktopAppearance::default();
+        assert_eq!(
+            updated("org.example", COLOR_SCHEME_KEY, Value::U32(1)),
+            unchanged
+        );
+        assert_eq!(
+            updated(APPEARANCE_NAMESPACE, "reduced-motion", Value::U32(1)),
+            unchanged
+        );
+        assert_eq!(
+            updated(GNOME_INTERFACE_NAMESPACE, CONTRAST_KEY, Value::U32(1)),
+            unchanged
+        );
+    }
+
+    #[test]
+    fn update_clears_accent_color_when_unset() {
+        let mut appearance = DesktopAppearance {
+            accent_color: Some(Color::new(255, 0, 0, 1.0)),
+            ..Default::default()
+        };
+        let unset = accent(-1.0, -1.0, -1.0);
+        DesktopAppearanceMonitor::update(
+            &mut appearance,
+            APPEARANCE_NAMESPACE,
+            ACCENT_COLOR_KEY,
+            &unset,
+        );
+        assert_eq!(appearance.accent_color, None);
+    }
+
+    /// Stand-in for `org.freedesktop.portal.Settings` with a dark scheme,
+    /// a blue accent and animations turned off
+    struct MockSettings;
+
+    #[dbus_interface(name = "org.freedesktop.portal.Settings")]
+    impl MockSettings {
+        fn read_all(
+            &self,
+            _namespaces: Vec<String>,
+        ) -> HashMap<String, HashMap<String, OwnedValue>> {
+            let variant = |value: Value<'static>| OwnedValue::from(Value::Value(Box::new(value)));
+            HashMap::from([
+                (
+                    APPEARANCE_NAMESPACE.to_string(),
+                    HashMap::from([
+                        (COLOR_SCHEME_KEY.to_string(), variant(Value::U32(1))),
+                        (ACCENT_COLOR_KEY.to_string(), variant(accent(0.2, 0.4, 1.0))),
+                    ]),
+                ),
+                (
+                    GNOME_INTERFACE_NAMESPACE.to_string(),
+                    HashMap::from([(
+                        ENABLE_ANIMATIONS_KEY.to_string(),
+                        variant(Value::Bool(false)),
+                    )]),
+                ),
+            ])
+        }
+
+        #[dbus_interface(property, name = "version")]
+        fn version(&self) -> u32 {
+            1
+        }
+    }
+
+    #[test]
+    fn reads_settings_and_follows_changes() {
+        let Some(portal) = test_bus::portal() else {
+            return;
+        };
+        portal
+            .object_server()
+            .at(test_bus::PORTAL_PATH, MockSettings)
+            .unwrap();
+
+        let current = Cell::new(DesktopAppearance::default());
+        let reports = RefCell::new(Vec::new());
+        let report = || reports.borrow_mut().push(current.get());
+        let run = DesktopAppearanceMonitor::run(&current, &report);
+
+        // Changes are emitted until one is reported, since the monitor
+        // subscribes to them while starting up
+        let change = async {
+            let deadline = Instant::now() + test_bus::TIMEOUT;
+            while Instant::now() < deadline {
+                let light = reports
+                    .borrow()
+                    .iter()
+                    .any(|appearance| appearance.color_scheme == ColorScheme::Light);
+                if light {
+                    break;
+                }
+                if !reports.borrow().is_empty() {
+                    portal
+                        .emit_signal(
+                            None::<&str>,
+                            test_bus::PORTAL_PATH,
+                            "org.freedesktop.portal.Settings",
+                            "SettingChanged",
+                            &(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY, Value::U32(2)),
+                        )
+                        .unwrap();
+                }
+                glib::timeout_future(Duration::from_millis(20)).await;
+            }
+        };
+
+        match glib::MainContext::new().block_on(future::select(pin!(run), pin!(change))) {
+            Either::Left((result, _)) => panic!("settings portal stream ended: {:?}", result),
+            Either::Right(((), _)) => {}
+        }
+
+        let reports = reports.borrow();
+        let initial = DesktopAppearance {
+            accent_color: Some(Color::new(51, 102, 255, 1.0)),
+            color_scheme: ColorScheme::Dark,
+            reduce_motion: true,
+            high_contrast: false,
+        };
+        assert_eq!(reports.first(), Some(&initial));
+        assert_eq!(
+            reports.last(),
+            Some(&DesktopAppearance {
+                color_scheme: ColorScheme::Light,
+                ..initial
+            })
+        );
+    }
+}
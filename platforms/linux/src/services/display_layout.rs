@@ -0,0 +1,208 @@
+//! Monitor layout change detection
+//!
+//! Reports a fingerprint of the connected monitors whenever monitors are
+//! plugged in, unplugged or change resolution.
+
+use crate::services::display_manager::{Display, DisplayManager};
+use anyhow::Result;
+use gtk4::prelude::*;
+use gtk4::{gdk, gio, glib};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Time to wait for a burst of monitor changes (e.g. docking) to settle
+const SETTLE_DELAY: Duration = Duration::from_millis(750);
+
+/// Identify a monitor layout by connector names and resolutions
+///
+/// Monitors are sorted by connector so the fingerprint does not depend on
+/// the order the compositor lists them in, e.g. `DP-1:2560x1440,eDP-1:1920x1200`.
+pub fn layout_fingerprint(displays: &[Display]) -> String {
+    let mut monitors: Vec<String> = displays
+        .iter()
+        .map(|d| format!("{}:{}x{}", d.name, d.width, d.height))
+        .collect();
+    monitors.sort();
+    monitors.join(",")
+}
+
+/// Watches for monitor layout changes
+pub struct DisplayLayoutMonitor {
+    monitors: Option<gio::ListModel>,
+    handler: Option<glib::SignalHandlerId>,
+    geometry_handlers: Rc<RefCell<Vec<(gdk::Monitor, glib::SignalHandlerId)>>>,
+}
+
+impl DisplayLayoutMonitor {
+    /// Create a new display layout monitor
+    pub fn new() -> Self {
+        Self {
+            monitors: None,
+            handler: None,
+            geometry_handlers: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Fingerprint of the monitors connected right now
+    pub fn current_fingerprint() -> String {
+        layout_fingerprint(DisplayManager::new().displays())
+    }
+
+    /// Start watching, calling `on_change` with the new fingerprint once
+    /// the layout has settled after a change
+    pub fn start_with_callback<F>(&mut self, on_change: F) -> Result<()>
+    where
+        F: Fn(&str) + 'static,
+    {
+        self.stop();
+
+        let Some(display) = gdk::Display::default() else {
+            anyhow::bail!("No default display available");
+        };
+        let monitors = display.monitors();
+
+        let on_change: Rc<dyn Fn(&str)> = Rc::new(on_change);
+        let last_fingerprint = Rc::new(RefCell::new(Self::current_fingerprint()));
+        let pending: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+
+        // Coalesce bursts of changes into one check
+        let schedule_check = Rc::new(move || {
+            if let Some(source) = pending.borrow_mut().take() {
+                source.remove();
+            }
+
+            let on_change = on_change.clone();
+            let last_fingerprint = last_fingerprint.clone();
+            let pending_clone = pending.clone();
+            let source = glib::timeout_add_local_once(SETTLE_DELAY, move || {
+                pending_clone.borrow_mut().take();
+
+                let fingerprint = Self::current_fingerprint();
+                if *last_fingerprint.borrow() == fingerprint {
+                    return;
+                }
+                tracing::info!("Monitor layout changed: {}", fingerprint);
+                *last_fingerprint.borrow_mut() = fingerprint.clone();
+                on_change(&fingerprint);
+            });
+            *pending.borrow_mut() = Some(source);
+        });
+
+        // Resolution changes update existing monitors rather than the list
+        let watch_geometry = {
+            let schedule_check = schedule_check.clone();
+            let geometry_handlers = self.geometry_handlers.clone();
+            move |monitor: gdk::Monitor| {
+                let schedule_check = schedule_check.clone();
+                let handler = monitor.connect_geometry_notify(move |_| schedule_check());
+                geometry_handlers.borrow_mut().push((monitor, handler));
+            }
+        };
+
+        for monitor in monitors.iter::<gdk::Monitor>().flatten() {
+            watch_geometry(monitor);
+        }
+
+        let handler = monitors.connect_items_changed(move |list, position, _removed, added| {
+            for i in position..position + added {
+                if let Some(monitor) = list.item(i).and_then(|m| m.downcast::<gdk::Monitor>().ok())
+                {
+                    watch_geometry(monitor);
+                }
+            }
+            schedule_check();
+        });
+
+        self.monitors = Some(monitors);
+        self.handler = Some(handler);
+        Ok(())
+    }
+
+    /// Stop watching
+    pub fn stop(&mut self) {
+        if let (Some(monitors), Some(handler)) = (self.monitors.take(), self.handler.take()) {
+            monitors.disconnect(handler);
+        }
+        for (monitor, handler) in self.geometry_handlers.borrow_mut().drain(..) {
+            monitor.disconnect(handler);
+        }
+    }
+}
+
+impl Default for DisplayLayoutMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for DisplayLayoutMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A monitor with the given connector name and resolution
+    fn display(name: &str, width: i32, height: i32) -> Display {
+        Display {
+            name: name.to_string(),
+            x: 0,
+            y: 0,
+            width,
+            height,
+            scale_factor: 1,
+            is_primary: false,
+        }
+    }
+
+    #[test]
+    fn fingerprint_lists_connectors_and_resolutions() {
+        let displays = [display("eDP-1", 1920, 1200), display("DP-1", 2560, 1440)];
+        assert_eq!(
+            layout_fingerprint(&displays),
+            "DP-1:2560x1440,eDP-1:1920x1200"
+        );
+    }
+
+    #[test]
+    fn fingerprint_does_not_depend_on_monitor_order() {
+        let a = display("eDP-1", 1920, 1200);
+        let b = display("DP-1", 2560, 1440);
+        let c = display("HDMI-A-1", 3840, 2160);
+        let forward = layout_fingerprint(&[a.clone(), b.clone(), c.clone()]);
+        assert_eq!(
+            layout_fingerprint(&[c.clone(), a.clone(), b.clone()]),
+            forward
+        );
+        assert_eq!(layout_fingerprint(&[b, c, a]), forward);
+    }
+
+    #[test]
+    fn fingerprint_ignores_position_and_scale() {
+        let mut moved = display("DP-1", 2560, 1440);
+        moved.x = 1920;
+        moved.scale_factor = 2;
+        moved.is_primary = true;
+        assert_eq!(
+            layout_fingerprint(&[moved]),
+            layout_fingerprint(&[display("DP-1", 2560, 1440)])
+        );
+    }
+
+    #[test]
+    fn fingerprint_changes_with_resolution() {
+        assert_ne!(
+            layout_fingerprint(&[display("DP-1", 2560, 1440)]),
+            layout_fingerprint(&[display("DP-1", 1920, 1080)])
+        );
+    }
+
+    #[test]
+    fn empty_layout_has_an_empty_fingerprint() {
+        assert_eq!(layout_fingerprint(&[]), "");
+    }
+}
@@ -147,13 +147,34 @@ pub fn get_cursor_position() -> Option<(f64, f64)> {
     None
 }
 
-/// Move cursor to a position (Wayland limitation)
+/// Check whether we are running in a Wayland session
+pub fn is_wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|session| session == "wayland")
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Move cursor to a position
 ///
-/// Note: Wayland does not allow cursor warping for security reasons.
-/// This function returns an error explaining the limitation.
-pub fn move_cursor_to(_x: f64, _y: f64) -> Result<()> {
-    anyhow::bail!(
-        "Cursor warping is not supported on Wayland for security reasons. \
-         Some compositors (GNOME, KDE) may support it through extensions."
-    )
+/// Note: Wayland does not allow cursor warping for security reasons, so this
+/// only works in X11 sessions and returns an error explaining the limitation
+/// otherwise.
+pub fn move_cursor_to(x: f64, y: f64) -> Result<()> {
+    use anyhow::Context;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::ConnectionExt;
+
+    if is_wayland_session() {
+        anyhow::bail!(
+            "Cursor warping is not supported on Wayland for security reasons. \
+             Some compositors (GNOME, KDE) may support it through extensions."
+        );
+    }
+
+    let (conn, screen_num) = x11rb::connect(None).context("Failed to connect to X11")?;
+    let root = conn.setup().roots[screen_num].root;
+
+    conn.warp_pointer(x11rb::NONE, root, 0, 0, 0, 0, x as i16, y as i16)?;
+    conn.flush()?;
+
+    Ok(())
 }
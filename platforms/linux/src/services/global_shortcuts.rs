@@ -0,0 +1,289 @@
+//! Global shortcuts through the XDG desktop portal
+//!
+//! Wayland clients cannot grab keys outside their own windows, so on Wayland
+//! sessions the shortcuts are registered with the compositor through
+//! `org.freedesktop.portal.GlobalShortcuts` instead.
+
+use crate::models::{ShortcutAction, ShortcutBindings};
+use anyhow::Result;
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+use ashpd::WindowIdentifier;
+use futures_util::StreamExt;
+use gtk4::glib;
+
+/// Registers CursorHome actions with the GlobalShortcuts portal
+pub struct GlobalShortcutsService {
+    task: Option<glib::JoinHandle<()>>,
+}
+
+impl GlobalShortcutsService {
+    /// Create a new global shortcuts service
+    pub fn new() -> Self {
+        Self { task: None }
+    }
+
+    /// Bind the shortcuts and invoke the callback whenever one is activated
+    ///
+    /// The bindings are passed to the portal as preferred triggers. The portal
+    /// keeps the user's final choice per shortcut id, so calling this at every
+    /// startup re-registers the same shortcuts.
+    pub fn start_with_callback<F>(&mut self, bindings: &ShortcutBindings, on_activated: F)
+    where
+        F: Fn(ShortcutAction) + 'static,
+    {
+        self.stop();
+
+        let shortcuts = new_shortcuts(bindings);
+
+        // The portal futures run on the GTK main loop so the callback can touch UI state
+        let task = glib::MainContext::default().spawn_local(async move {
+            if let Err(e) = Self::run(shortcuts, on_activated).await {
+                tracing::warn!("Global shortcuts portal unavailable: {}", e);
+            }
+        });
+        self.task = Some(task);
+    }
+
+    /// Stop listening for shortcut activations
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    async fn run<F>(shortcuts: Vec<NewShortcut>, on_activated: F) -> Result<()>
+    where
+        F: Fn(ShortcutAction),
+    {
+        let portal = GlobalShortcuts::new().await?;
+        let session = portal.create_session().await?;
+
+        let bound = portal
+            .bind_shortcuts(&session, &shortcuts, &WindowIdentifier::default())
+            .await?
+            .response()?;
+
+        for shortcut in bound.shortcuts() {
+            tracing::info!(
+                "Global shortcut '{}' bound to {}",
+                shortcut.id(),
+                shortcut.trigger_description()
+            );
+        }
+
+        let mut activations = portal.receive_activated().await?;
+        while let Some(activated) = activations.next().await {
+            match ShortcutAction::from_id(activated.shortcut_id()) {
+                Some(action) => {
+                    tracing::debug!("Global shortcut activated: {:?}", action);
+                    on_activated(action);
+                }
+                None => {
+                    tracing::debug!("Ignoring unknown shortcut '{}'", activated.shortcut_id());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Every action as a portal shortcut, preferring its current binding
+fn new_shortcuts(bindings: &ShortcutBindings) -> Vec<NewShortcut> {
+    ShortcutAction::all()
+        .iter()
+        .map(|action| {
+            let trigger = bindings.trigger(*action);
+            NewShortcut::new(action.id(), action.display_name())
+                .preferred_trigger(trigger.as_deref())
+        })
+        .collect()
+}
+
+impl Default for GlobalShortcutsService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for GlobalShortcutsService {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus;
+    use futures_util::future::{self, Either};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::pin::pin;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+    use zbus::{dbus_interface, MessageHeader};
+
+    /// What the client asked of the mock portal
+    #[derive(Default)]
+    struct Calls {
+        session: Option<OwnedObjectPath>,
+        /// Shortcut ids with their preferred triggers, as bound
+        bound: Vec<(String, Option<String>)>,
+    }
+
+    /// Stand-in for `org.freedesktop.portal.GlobalShortcuts` that accepts
+    /// every request
+    struct MockGlobalShortcuts {
+        calls: Arc<Mutex<Calls>>,
+    }
+
+    /// Answer the portal request at `request` with `results`
+    async fn respond(
+        connection: &zbus::Connection,
+        request: &str,
+        results: HashMap<&str, Value<'_>>,
+    ) {
+        connection
+            .emit_signal(
+                None::<&str>,
+                request,
+                "org.freedesktop.portal.Request",
+                "Response",
+                &(0u32, results),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[dbus_interface(name = "org.freedesktop.portal.GlobalShortcuts")]
+    impl MockGlobalShortcuts {
+        async fn create_session(
+            &self,
+            #[zbus(header)] header: MessageHeader<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+            options: HashMap<String, OwnedValue>,
+        ) -> OwnedObjectPath {
+            let sender = header.sender().unwrap().unwrap().to_string();
+            let token = test_bus::option(&options, "handle_token");
+            let request = test_bus::handle_path("request", &sender, token);
+            let token = test_bus::option(&options, "session_handle_token");
+            let session = test_bus::handle_path("session", &sender, token);
+
+            self.calls.lock().unwrap().session = Some(session.clone());
+            let results = HashMap::from([("session_handle", Value::from(session.as_ref()))]);
+            respond(connection, request.as_str(), results).await;
+            request
+        }
+
+        async fn bind_shortcuts(
+            &self,
+            #[zbus(header)] header: MessageHeader<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _session: OwnedObjectPath,
+            shortcuts: Vec<(String, HashMap<String, OwnedValue>)>,
+            _parent_window: String,
+            options: HashMap<String, OwnedValue>,
+        ) -> OwnedObjectPath {
+            let sender = header.sender().unwrap().unwrap().to_string();
+            let request = test_bus::handle_path(
+                "request",
+                &sender,
+                test_bus::option(&options, "handle_token"),
+            );
+
+            let mut bound = Vec::new();
+            for (id, properties) in shortcuts {
+                let trigger = properties
+                    .get("preferred_trigger")
+                    .and_then(|value| <&str>::try_from(value).ok())
+                    .map(str::to_string);
+                let description = HashMap::from([
+                    ("description", Value::from(id.clone())),
+                    (
+                        "trigger_description",
+                        Value::from(trigger.clone().unwrap_or_default()),
+                    ),
+                ]);
+                self.calls.lock().unwrap().bound.push((id.clone(), trigger));
+                bound.push((id, description));
+            }
+
+            let results = HashMap::from([("shortcuts", Value::from(bound))]);
+            respond(connection, request.as_str(), results).await;
+            request
+        }
+
+        #[dbus_interface(property, name = "version")]
+        fn version(&self) -> u32 {
+            1
+        }
+    }
+
+    #[test]
+    fn binds_shortcuts_and_reports_activations() {
+        let Some(portal) = test_bus::portal() else {
+            return;
+        };
+        let calls = Arc::new(Mutex::new(Calls::default()));
+        portal
+            .object_server()
+            .at(
+                test_bus::PORTAL_PATH,
+                MockGlobalShortcuts {
+                    calls: calls.clone(),
+                },
+            )
+            .unwrap();
+
+        let bindings = ShortcutBindings::default();
+        let activations = Rc::new(RefCell::new(Vec::new()));
+        let run = GlobalShortcutsService::run(new_shortcuts(&bindings), {
+            let activations = activations.clone();
+            move |action| activations.borrow_mut().push(action)
+        });
+
+        // Activations are emitted until one arrives, since the client
+        // subscribes to them only after binding
+        let activate = async {
+            let deadline = Instant::now() + test_bus::TIMEOUT;
+            while activations.borrow().is_empty() && Instant::now() < deadline {
+                let session = calls.lock().unwrap().session.clone();
+                if let Some(session) = session {
+                    for id in ["not-a-shortcut", ShortcutAction::Teleport.id()] {
+                        portal
+                            .emit_signal(
+                                None::<&str>,
+                                test_bus::PORTAL_PATH,
+                                "org.freedesktop.portal.GlobalShortcuts",
+                                "Activated",
+                                &(&session, id, 0u64, HashMap::<&str, Value>::new()),
+                            )
+                            .unwrap();
+                    }
+                }
+                glib::timeout_future(Duration::from_millis(20)).await;
+            }
+        };
+
+        match glib::MainContext::new().block_on(future::select(pin!(run), pin!(activate))) {
+            Either::Left((result, _)) => panic!("portal session ended: {:?}", result),
+            Either::Right(((), _)) => {}
+        }
+
+        let calls = calls.lock().unwrap();
+        let expected: Vec<_> = ShortcutAction::all()
+            .iter()
+            .map(|action| (action.id().to_string(), bindings.trigger(*action)))
+            .collect();
+        assert_eq!(calls.bound, expected);
+
+        let activations = activations.borrow();
+        assert!(!activations.is_empty(), "no activation delivered");
+        assert!(activations
+            .iter()
+            .all(|action| *action == ShortcutAction::Teleport));
+    }
+}
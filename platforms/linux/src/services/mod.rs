@@ -1,11 +1,23 @@
 //! Services for CursorHome
 
 mod cursor_finder;
+mod desktop_appearance;
+mod display_layout;
 mod display_manager;
+mod global_shortcuts;
+mod modifier_tap;
+mod notifications;
+mod preferences_watcher;
 mod shake_detector;
 mod synergy_monitor;
 
 pub use cursor_finder::CursorFinderService;
-pub use display_manager::DisplayManager;
+pub use desktop_appearance::DesktopAppearanceMonitor;
+pub use display_layout::DisplayLayoutMonitor;
+pub use display_manager::{is_wayland_session, DisplayManager};
+pub use global_shortcuts::GlobalShortcutsService;
+pub use modifier_tap::ModifierTapDetector;
+pub use notifications::{show_notification, CURSOR_ICON, ERROR_ICON, WARNING_ICON};
+pub use preferences_watcher::PreferencesWatcher;
 pub use shake_detector::ShakeDetector;
-pub use synergy_monitor::SynergyMonitor;
+pub use synergy_monitor::{SynergyEvent, SynergyMonitor};
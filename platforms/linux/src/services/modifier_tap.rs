@@ -0,0 +1,374 @@
+//! Modifier double-tap detection
+//!
+//! Watches raw key events through XInput2 and fires when a modifier key is
+//! tapped on its own twice in quick succession, like the Windows "show
+//! location of pointer when I press Ctrl" option. Raw events are only
+//! observed, never grabbed, so normal use of the modifier is unaffected.
+
+use crate::models::TapModifier;
+use anyhow::{Context, Result};
+use gtk4::glib;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use x11rb::connection::Connection;
+use x11rb::protocol::xinput::{
+    ConnectionExt as XInputConnectionExt, Device, EventMask, KeyEventFlags, XIEventMask,
+};
+use x11rb::protocol::xproto::{ConnectionExt as _, Keycode};
+use x11rb::protocol::Event;
+
+/// Recognises double taps of a single modifier from press/release events
+struct TapState {
+    interval: Duration,
+    held: HashSet<u32>,
+    tap_started: Option<Instant>,
+    last_tap: Option<Instant>,
+}
+
+impl TapState {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            held: HashSet::new(),
+            tap_started: None,
+            last_tap: None,
+        }
+    }
+
+    fn key_pressed(&mut self, keycode: u32, is_modifier: bool, now: Instant) {
+        let others_held = !self.held.is_empty();
+        self.held.insert(keycode);
+
+        if is_modifier && !others_held {
+            self.tap_started = Some(now);
+        } else {
+            // Any chord (Ctrl+C, Shift+Ctrl, ...) breaks the sequence
+            self.interrupt();
+        }
+    }
+
+    /// Returns true when this release completes a double tap
+    fn key_released(&mut self, keycode: u32, is_modifier: bool, now: Instant) -> bool {
+        self.held.remove(&keycode);
+
+        if !is_modifier {
+            return false;
+        }
+
+        let Some(started) = self.tap_started.take() else {
+            return false;
+        };
+
+        // Holding the modifier is not a tap
+        if now.duration_since(started) > self.interval {
+            self.last_tap = None;
+            return false;
+        }
+
+        match self.last_tap.take() {
+            Some(previous) if now.duration_since(previous) <= self.interval => true,
+            _ => {
+                self.last_tap = Some(now);
+                false
+            }
+        }
+    }
+
+    /// Forget any partial sequence (e.g. after a mouse click)
+    fn interrupt(&mut self) {
+        self.tap_started = None;
+        self.last_tap = None;
+    }
+}
+
+/// Detects double taps of a modifier key
+pub struct ModifierTapDetector {
+    is_running: Arc<AtomicBool>,
+}
+
+impl ModifierTapDetector {
+    /// Create a new modifier tap detector
+    pub fn new() -> Self {
+        Self {
+            is_running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Start watching for double taps of `modifier`
+    ///
+    /// # Arguments
+    /// * `modifier` - Modifier key to watch
+    /// * `interval` - Maximum time in seconds between taps, and for each tap
+    /// * `on_double_tap` - Called on the main thread for each double tap
+    pub fn start_with_callback<F>(
+        &mut self,
+        modifier: TapModifier,
+        interval: f64,
+        on_double_tap: F,
+    ) -> Result<()>
+    where
+        F: Fn() + 'static,
+    {
+        self.stop();
+
+        let (conn, screen_num) = x11rb::connect(None).context("Failed to connect to X11")?;
+        let root = conn.setup().roots[screen_num].root;
+
+        conn.xinput_xi_query_version(2, 0)?
+            .reply()
+            .context("XInput2 is not available")?;
+
+        // Raw events are only delivered to the root window
+        conn.xinput_xi_select_events(
+            root,
+            &[EventMask {
+                deviceid: Device::ALL_MASTER.into(),
+                mask: vec![
+                    XIEventMask::RAW_KEY_PRESS
+                        | XIEventMask::RAW_KEY_RELEASE
+                        | XIEventMask::RAW_BUTTON_PRESS,
+                ],
+            }],
+        )?;
+        conn.flush()?;
+
+        let modifier_keycodes = Self::modifier_keycodes(&conn, modifier)?;
+        if modifier_keycodes.is_empty() {
+            anyhow::bail!("No keys are mapped to {}", modifier.display_name());
+        }
+
+        // A fresh flag per run so a previous thread still waiting for its
+        // next event cannot be revived by this start
+        let is_running = Arc::new(AtomicBool::new(true));
+        self.is_running = is_running.clone();
+
+        // Forward detections to the main thread, dropping any that arrive
+        // after a stop
+        let (sender, receiver) = glib::MainContext::channel(glib::Priority::DEFAULT);
+        let is_running_main = is_running.clone();
+        receiver.attach(None, move |()| {
+            if is_running_main.load(Ordering::SeqCst) {
+                on_double_tap();
+            }
+            glib::ControlFlow::Continue
+        });
+        let mut state = TapState::new(Duration::from_secs_f64(interval.max(0.05)));
+
+        thread::spawn(move || {
+            while is_running.load(Ordering::SeqCst) {
+                let event = match conn.wait_for_event() {
+                    Ok(event) => event,
+                    Err(e) => {
+                        tracing::warn!("Lost X11 connection for modifier taps: {}", e);
+                        break;
+                    }
+                };
+
+                let now = Instant::now();
+                match event {
+                    Event::XinputRawKeyPress(event) => {
+                        // Auto-repeat while the key is held down
+                        if event.flags.contains(KeyEventFlags::KEY_REPEAT) {
+                            continue;
+                        }
+                        let is_modifier = Self::is_modifier(&modifier_keycodes, event.detail);
+                        state.key_pressed(event.detail, is_modifier, now);
+                    }
+                    Event::XinputRawKeyRelease(event) => {
+                        let is_modifier = Self::is_modifier(&modifier_keycodes, event.detail);
+                        if state.key_released(event.detail, is_modifier, now) {
+                            // Stopped while waiting for this event
+                            if !is_running.load(Ordering::SeqCst) {
+                                break;
+                            }
+                            tracing::debug!("{} double tap detected", modifier.display_name());
+                            if sender.send(()).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Event::XinputRawButtonPress(_) => state.interrupt(),
+                    _ => {}
+                }
+            }
+        });
+
+        tracing::info!(
+            "Watching for {} double taps ({}s interval)",
+            modifier.display_name(),
+            interval
+        );
+        Ok(())
+    }
+
+    /// Stop watching
+    ///
+    /// No callback runs after this returns. The watcher thread exits when it
+    /// receives its next event.
+    pub fn stop(&mut self) {
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+
+    /// Keycodes currently mapped to the given modifier
+    fn modifier_keycodes(conn: &impl Connection, modifier: TapModifier) -> Result<Vec<Keycode>> {
+        let mapping = conn.get_modifier_mapping()?.reply()?;
+        Ok(Self::mapped_keycodes(&mapping.keycodes, modifier))
+    }
+
+    /// Keycodes of `modifier` in a core modifier map, which has a row of
+    /// equal length for each of the eight modifiers
+    fn mapped_keycodes(keycodes: &[Keycode], modifier: TapModifier) -> Vec<Keycode> {
+        // Row order of the core modifier map: Shift, Lock, Control, Mod1..Mod5
+        let row = match modifier {
+            TapModifier::Shift => 0,
+            TapModifier::Control => 2,
+            TapModifier::Alt => 3,
+            TapModifier::Super => 6,
+        };
+
+        let per_modifier = keycodes.len() / 8;
+        keycodes
+            .iter()
+            .skip(row * per_modifier)
+            .take(per_modifier)
+            .copied()
+            .filter(|keycode| *keycode != 0)
+            .collect()
+    }
+
+    fn is_modifier(keycodes: &[Keycode], keycode: u32) -> bool {
+        keycodes.iter().any(|k| u32::from(*k) == keycode)
+    }
+}
+
+impl Default for ModifierTapDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ModifierTapDetector {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(400);
+
+    const LEFT_CTRL: u32 = 37;
+    const KEY_C: u32 = 54;
+
+    /// A typical core modifier map, two keys per modifier
+    const MODIFIER_MAP: [Keycode; 16] = [
+        50, 62, // Shift
+        66, 0, // Lock
+        37, 105, // Control
+        64, 108, // Mod1 (Alt)
+        77, 0, // Mod2 (Num Lock)
+        0, 0, // Mod3
+        133, 134, // Mod4 (Super)
+        92, 0, // Mod5
+    ];
+
+    /// Press and release `keycode` as a modifier, starting `at` and held for
+    /// `held`; true if this completes a double tap
+    fn tap(state: &mut TapState, keycode: u32, at: Instant, held: Duration) -> bool {
+        state.key_pressed(keycode, true, at);
+        state.key_released(keycode, true, at + held)
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn double_tap_inside_the_interval_fires() {
+        let mut state = TapState::new(INTERVAL);
+        let start = Instant::now();
+        assert!(!tap(&mut state, LEFT_CTRL, start, ms(50)));
+        assert!(tap(&mut state, LEFT_CTRL, start + ms(200), ms(50)));
+
+        // A third tap starts a new sequence
+        assert!(!tap(&mut state, LEFT_CTRL, start + ms(400), ms(50)));
+    }
+
+    #[test]
+    fn taps_further_apart_than_the_interval_do_not_fire() {
+        let mut state = TapState::new(INTERVAL);
+        let start = Instant::now();
+        assert!(!tap(&mut state, LEFT_CTRL, start, ms(50)));
+        assert!(!tap(&mut state, LEFT_CTRL, start + ms(600), ms(50)));
+
+        // The late tap counts as the first of a new pair
+        assert!(tap(&mut state, LEFT_CTRL, start + ms(800), ms(50)));
+    }
+
+    #[test]
+    fn chords_do_not_count_as_taps() {
+        let mut state = TapState::new(INTERVAL);
+        let start = Instant::now();
+        assert!(!tap(&mut state, LEFT_CTRL, start, ms(50)));
+
+        // Ctrl+C
+        state.key_pressed(LEFT_CTRL, true, start + ms(100));
+        state.key_pressed(KEY_C, false, start + ms(120));
+        assert!(!state.key_released(KEY_C, false, start + ms(140)));
+        assert!(!state.key_released(LEFT_CTRL, true, start + ms(160)));
+
+        // C held while Ctrl is tapped
+        state.key_pressed(KEY_C, false, start + ms(200));
+        assert!(!tap(&mut state, LEFT_CTRL, start + ms(220), ms(20)));
+        assert!(!tap(&mut state, LEFT_CTRL, start + ms(260), ms(20)));
+    }
+
+    #[test]
+    fn holding_the_modifier_is_not_a_tap() {
+        let mut state = TapState::new(INTERVAL);
+        let start = Instant::now();
+        assert!(!tap(&mut state, LEFT_CTRL, start, ms(50)));
+        assert!(!tap(&mut state, LEFT_CTRL, start + ms(100), ms(500)));
+        assert!(!tap(&mut state, LEFT_CTRL, start + ms(700), ms(50)));
+    }
+
+    #[test]
+    fn mouse_buttons_between_taps_reset_the_sequence() {
+        let mut state = TapState::new(INTERVAL);
+        let start = Instant::now();
+        assert!(!tap(&mut state, LEFT_CTRL, start, ms(50)));
+        state.interrupt();
+        assert!(!tap(&mut state, LEFT_CTRL, start + ms(150), ms(50)));
+        assert!(tap(&mut state, LEFT_CTRL, start + ms(300), ms(50)));
+    }
+
+    #[test]
+    fn each_modifier_is_found_in_the_modifier_map() {
+        let expected: [(TapModifier, &[Keycode]); 4] = [
+            (TapModifier::Control, &[37, 105]),
+            (TapModifier::Shift, &[50, 62]),
+            (TapModifier::Alt, &[64, 108]),
+            (TapModifier::Super, &[133, 134]),
+        ];
+        assert_eq!(expected.len(), TapModifier::all().len());
+
+        for (modifier, keycodes) in expected {
+            let mapped = ModifierTapDetector::mapped_keycodes(&MODIFIER_MAP, modifier);
+            assert_eq!(mapped, keycodes, "{:?}", modifier);
+
+            // Left and right keys each count, and may be mixed
+            let mut state = TapState::new(INTERVAL);
+            let start = Instant::now();
+            let [left, right] = [keycodes[0], keycodes[1]].map(u32::from);
+            assert!(ModifierTapDetector::is_modifier(&mapped, right));
+            assert!(!ModifierTapDetector::is_modifier(&mapped, KEY_C));
+            assert!(!tap(&mut state, left, start, ms(50)));
+            assert!(tap(&mut state, right, start + ms(150), ms(50)));
+        }
+    }
+}
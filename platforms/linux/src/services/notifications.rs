@@ -0,0 +1,114 @@
+//! Desktop notifications
+
+use std::thread;
+
+/// Icon for problems the user may need to act on
+pub const WARNING_ICON: &str = "dialog-warning";
+
+/// Icon for failures
+pub const ERROR_ICON: &str = "dialog-error";
+
+/// Icon for cursor transitions
+pub const CURSOR_ICON: &str = "input-mouse";
+
+/// Show a desktop notification
+///
+/// Sending is a blocking D-Bus call, so it happens on its own thread and
+/// failures are only logged.
+pub fn show_notification(summary: &str, body: String, icon: &'static str) {
+    let summary = summary.to_string();
+
+    thread::spawn(move || {
+        let result = notify_rust::Notification::new()
+            .appname("CursorHome")
+            .summary(&summary)
+            .body(&body)
+            .icon(icon)
+            .show();
+        if let Err(e) = result {
+            tracing::warn!("Failed to show notification: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus;
+    use std::collections::HashMap;
+    use std::sync::mpsc::{channel, Receiver};
+    use zbus::blocking::MessageIterator;
+    use zbus::zvariant::OwnedValue;
+    use zbus::MessageType;
+
+    /// Arguments of `Notify`: app name, id replaced, icon, summary, body,
+    /// actions, hints and timeout
+    type Notify = (
+        String,
+        u32,
+        String,
+        String,
+        String,
+        Vec<String>,
+        HashMap<String, OwnedValue>,
+        i32,
+    );
+
+    /// Answer calls to `org.freedesktop.Notifications` on the private bus as
+    /// a notification daemon would, passing on each notification shown
+    fn mock_daemon() -> Option<Receiver<Notify>> {
+        let connection = test_bus::connect()?;
+        let messages = MessageIterator::from(&connection);
+        connection
+            .request_name("org.freedesktop.Notifications")
+            .unwrap();
+
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            for message in messages {
+                let message = message.unwrap();
+                let header = message.header().unwrap();
+                if header.message_type().unwrap() != MessageType::MethodCall {
+                    continue;
+                }
+                match header.member().unwrap().map(|member| member.as_str()) {
+                    Some("Notify") => {
+                        let notify: Notify = message.body().unwrap();
+                        connection.reply(&message, &1u32).unwrap();
+                        let _ = sender.send(notify);
+                    }
+                    Some("GetCapabilities") => {
+                        connection.reply(&message, &vec!["body"]).unwrap();
+                    }
+                    Some("GetServerInformation") => {
+                        let information = ("mock", "CursorHome", "1.0", "1.2");
+                        connection.reply(&message, &information).unwrap();
+                    }
+                    _ => {}
+                }
+            }
+        });
+        Some(receiver)
+    }
+
+    #[test]
+    fn notification_reaches_the_daemon() {
+        let Some(notifications) = mock_daemon() else {
+            return;
+        };
+
+        show_notification(
+            "Settings file has errors",
+            "line 3".to_string(),
+            WARNING_ICON,
+        );
+
+        let (app_name, _, icon, summary, body, _, _, _) = notifications
+            .recv_timeout(test_bus::TIMEOUT)
+            .expect("notification never arrived");
+        assert_eq!(app_name, "CursorHome");
+        assert_eq!(icon, WARNING_ICON);
+        assert_eq!(summary, "Settings file has errors");
+        assert_eq!(body, "line 3");
+    }
+}
@@ -0,0 +1,112 @@
+//! Preferences file watcher
+//!
+//! Reloads `preferences.json` when it is edited outside the app, e.g. by
+//! hand or by a dotfile manager.
+
+use crate::models::{LoadReport, Preferences};
+use anyhow::{Context, Result};
+use gtk4::glib;
+use notify::{recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Quiet period after the last file event before the file is read
+///
+/// Editors and dotfile managers often write a file in several steps
+/// (truncate, write, rename), so wait for them to finish.
+const SETTLE_DELAY: Duration = Duration::from_millis(200);
+
+/// Watches the preferences file for external changes
+pub struct PreferencesWatcher {
+    watcher: Option<RecommendedWatcher>,
+}
+
+impl PreferencesWatcher {
+    /// Create a new preferences watcher
+    pub fn new() -> Self {
+        Self { watcher: None }
+    }
+
+    /// Start watching, calling `on_change` on the main thread with the
+    /// re-parsed preferences, or the error if the file could not be parsed
+    pub fn start_with_callback<F>(&mut self, on_change: F) -> Result<()>
+    where
+        F: Fn(Result<(Preferences, LoadReport)>) + 'static,
+    {
+        let path = Preferences::config_file().context("Could not determine config directory")?;
+        let dir = path
+            .parent()
+            .context("Preferences file has no parent directory")?
+            .to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let (sender, receiver) = glib::MainContext::channel(glib::Priority::DEFAULT);
+        receiver.attach(None, move |result| {
+            on_change(result);
+            glib::ControlFlow::Continue
+        });
+
+        let (tx, rx) = channel();
+        let mut watcher = recommended_watcher(move |res: Result<Event, notify::Error>| {
+            if let Ok(event) = res {
+                let _ = tx.send(event);
+            }
+        })?;
+
+        // Watch the directory rather than the file: files replaced by a
+        // rename (as most editors and chezmoi do) would drop a file watch
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        self.watcher = Some(watcher);
+
+        thread::spawn(move || {
+            while let Ok(event) = rx.recv() {
+                if !Self::affects(&event, &path) {
+                    continue;
+                }
+
+                // Wait until the file has been quiet for a moment
+                loop {
+                    match rx.recv_timeout(SETTLE_DELAY) {
+                        Ok(_) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+
+                // Deleting the file keeps the current settings
+                if !path.exists() {
+                    continue;
+                }
+
+                tracing::debug!("Preferences file changed, reloading");
+                if sender.send(Preferences::read(&path)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        tracing::info!("Watching {:?} for changes", path);
+        Ok(())
+    }
+
+    /// Stop watching
+    pub fn stop(&mut self) {
+        self.watcher = None;
+    }
+
+    fn affects(event: &Event, path: &Path) -> bool {
+        matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) && event.paths.iter().any(|p| p == path)
+    }
+}
+
+impl Default for PreferencesWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -4,6 +4,7 @@
 
 use anyhow::Result;
 use gtk4::glib;
+use notify::event::ModifyKind;
 use notify::{recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
@@ -16,6 +17,9 @@ use std::thread;
 pub enum SynergyEvent {
     CursorLeft(String),
     CursorReturned(String),
+    /// The log file was removed or renamed; transitions are seen again once
+    /// a log reappears at the same path
+    LogLost(PathBuf),
 }
 
 /// Monitors Synergy 3 for cursor transitions
@@ -33,10 +37,10 @@ impl SynergyMonitor {
         }
     }
 
-    /// Start monitoring Synergy with a callback for cursor returned events
-    pub fn start_with_callback<F>(&mut self, on_cursor_returned: F) -> Result<()>
+    /// Start monitoring Synergy with a callback for cursor transition events
+    pub fn start_with_callback<F>(&mut self, on_event: F) -> Result<()>
     where
-        F: Fn(String) + 'static,
+        F: Fn(SynergyEvent) + 'static,
     {
         // Find Synergy log file
         let log_path = self.find_synergy_log()?;
@@ -55,14 +59,7 @@ impl SynergyMonitor {
 
         // Handle events on the main thread
         receiver.attach(None, move |event| {
-            match event {
-                SynergyEvent::CursorReturned(screen_name) => {
-                    on_cursor_returned(screen_name);
-                }
-                SynergyEvent::CursorLeft(_) => {
-                    // Could handle this too if needed
-                }
-            }
+            on_event(event);
             glib::ControlFlow::Continue
         });
 
@@ -74,15 +71,47 @@ impl SynergyMonitor {
             }
         })?;
 
-        watcher.watch(&log_path, RecursiveMode::NonRecursive)?;
+        // Watch the directory rather than the file, so the log is picked up
+        // again when it is rotated or Synergy recreates it
+        let log_dir = log_path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Synergy log {:?} has no directory", log_path))?;
+        watcher.watch(log_dir, RecursiveMode::NonRecursive)?;
         self.watcher = Some(watcher);
 
         // Spawn thread to process file changes
         thread::spawn(move || {
             let mut last_position = initial_position;
+            let mut lost = false;
 
             for event in rx {
-                if matches!(event.kind, EventKind::Modify(_)) {
+                if !event.paths.contains(&log_path) {
+                    continue;
+                }
+
+                if !log_path.exists() {
+                    if !lost {
+                        tracing::warn!("Synergy log {:?} is gone", log_path);
+                        let _ = sender.send(SynergyEvent::LogLost(log_path.clone()));
+                        lost = true;
+                    }
+                    continue;
+                }
+
+                // A new log took the place of the old one: read it from the start
+                let replaced = matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+                );
+                if lost || replaced {
+                    if lost {
+                        tracing::info!("Synergy log {:?} is back", log_path);
+                    }
+                    lost = false;
+                    last_position = 0;
+                }
+
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
                     if let Some(events) = Self::process_new_entries(&log_path, &mut last_position) {
                         for synergy_event in events {
                             tracing::info!("Synergy transition: {:?}", synergy_event);
@@ -98,6 +127,16 @@ impl SynergyMonitor {
         Ok(())
     }
 
+    /// Stop monitoring
+    ///
+    /// Dropping the watcher closes the channel, which ends the worker thread.
+    pub fn stop(&mut self) {
+        if self.watcher.take().is_some() {
+            tracing::info!("Synergy monitoring stopped");
+        }
+        self.event_sender = None;
+    }
+
     /// Find Synergy log file
     fn find_synergy_log(&self) -> Result<PathBuf> {
         let candidates = [
@@ -131,6 +170,10 @@ impl SynergyMonitor {
         let mut file = File::open(log_path).ok()?;
         let current_size = file.metadata().ok()?.len();
 
+        // Truncated in place by log rotation
+        if current_size < *last_position {
+            *last_position = 0;
+        }
         if current_size <= *last_position {
             return None;
         }
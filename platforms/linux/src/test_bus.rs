@@ -0,0 +1,143 @@
+//! Private D-Bus session bus for tests
+//!
+//! ashpd keeps one session connection for the life of the process, so every
+//! test shares the same `dbus-daemon`. It is started on first use and exits
+//! with the test process. Tests that need it are skipped when `dbus-daemon`
+//! is not installed.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use zbus::blocking::{Connection, ConnectionBuilder};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+use zbus::Interface;
+
+/// Bus name and object path of the desktop portal
+pub const PORTAL_NAME: &str = "org.freedesktop.portal.Desktop";
+pub const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// How long tests wait for something to arrive over the bus
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct PrivateBus {
+    address: String,
+    /// The wrapper shell kills the daemon once this pipe closes
+    _lifeline: Mutex<ChildStdin>,
+}
+
+static BUS: OnceLock<Option<PrivateBus>> = OnceLock::new();
+static PORTAL: OnceLock<Option<Connection>> = OnceLock::new();
+
+/// Address of the private bus, which is also made the session bus of the
+/// process; `None` without `dbus-daemon`
+pub fn address() -> Option<&'static str> {
+    let bus = BUS.get_or_init(|| {
+        let bus = start();
+        match &bus {
+            Some(bus) => std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &bus.address),
+            None => eprintln!("dbus-daemon unavailable, skipping D-Bus tests"),
+        }
+        bus
+    });
+    bus.as_ref().map(|bus| bus.address.as_str())
+}
+
+fn start() -> Option<PrivateBus> {
+    let mut child = Command::new("sh")
+        .args([
+            "-c",
+            "dbus-daemon --session --nofork --print-address & pid=$!; read _; kill $pid",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let mut address = String::new();
+    BufReader::new(child.stdout.take()?)
+        .read_line(&mut address)
+        .ok()?;
+    let address = address.trim().to_string();
+    if address.is_empty() {
+        return None;
+    }
+
+    Some(PrivateBus {
+        address,
+        _lifeline: Mutex::new(child.stdin.take()?),
+    })
+}
+
+/// A connection to the private bus
+pub fn connect() -> Option<Connection> {
+    Some(
+        ConnectionBuilder::address(address()?)
+            .unwrap()
+            .build()
+            .unwrap(),
+    )
+}
+
+/// A connection owning `name` that serves `iface` at `path`
+pub fn serve<I: Interface>(name: &'static str, path: &'static str, iface: I) -> Option<Connection> {
+    Some(
+        ConnectionBuilder::address(address()?)
+            .unwrap()
+            .name(name)
+            .unwrap()
+            .serve_at(path, iface)
+            .unwrap()
+            .build()
+            .unwrap(),
+    )
+}
+
+/// A connection owning the desktop portal name; mock portal interfaces are
+/// served from its object server at [`PORTAL_PATH`]
+pub fn portal() -> Option<&'static Connection> {
+    PORTAL
+        .get_or_init(|| {
+            let address = address()?;
+            Some(
+                ConnectionBuilder::address(address)
+                    .unwrap()
+                    .name(PORTAL_NAME)
+                    .unwrap()
+                    .build()
+                    .unwrap(),
+            )
+        })
+        .as_ref()
+}
+
+/// Object path of the portal request or session `kind` made by `sender`
+/// with `token`, as the portal frontend derives it
+pub fn handle_path(kind: &str, sender: &str, token: &str) -> OwnedObjectPath {
+    let sender = sender.trim_start_matches(':').replace('.', "_");
+    OwnedObjectPath::try_from(format!("{}/{}/{}/{}", PORTAL_PATH, kind, sender, token)).unwrap()
+}
+
+/// The string option `key` of a portal call
+pub fn option<'a>(options: &'a HashMap<String, OwnedValue>, key: &str) -> &'a str {
+    options
+        .get(key)
+        .and_then(|value| <&str>::try_from(value).ok())
+        .unwrap_or_else(|| panic!("portal call without {}", key))
+}
+
+/// Call `poll` until it returns something or [`TIMEOUT`] passes
+pub fn wait_for<T>(mut poll: impl FnMut() -> Option<T>) -> Option<T> {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        if let Some(value) = poll() {
+            return Some(value);
+        }
+        if Instant::now() > deadline {
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
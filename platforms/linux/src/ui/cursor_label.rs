@@ -0,0 +1,100 @@
+//! Text labels drawn next to the cursor
+//!
+//! The label is rendered once with Pango into an image, which both
+//! overlays paint with cairo.
+
+use crate::models::{LabelPosition, TransitionLabel};
+use anyhow::Result;
+use gtk4::cairo::{Context, Format, ImageSurface};
+use gtk4::pango;
+use gtk4::prelude::*;
+use std::f64::consts::PI;
+
+/// Space between the text and the edge of its background, in pixels
+const PADDING_X: i32 = 12;
+const PADDING_Y: i32 = 6;
+
+/// Space between the highlight and the label, in pixels
+const MARGIN: f64 = 12.0;
+
+/// A label rendered and ready to draw
+pub struct CursorLabel {
+    surface: ImageSurface,
+    position: LabelPosition,
+}
+
+impl CursorLabel {
+    /// Render `text` on a rounded background in the label style
+    pub fn new(text: &str, style: &TransitionLabel) -> Result<Self> {
+        let context = pangocairo::FontMap::default().create_context();
+        let layout = pango::Layout::new(&context);
+        layout.set_font_description(Some(&pango::FontDescription::from_string(&style.font)));
+        layout.set_text(text);
+
+        let (_, extents) = layout.pixel_extents();
+        let width = extents.width() + PADDING_X * 2;
+        let height = extents.height() + PADDING_Y * 2;
+
+        let surface = ImageSurface::create(Format::ARgb32, width, height)?;
+        {
+            let cr = Context::new(&surface)?;
+
+            // Pill-shaped background
+            let radius = height as f64 / 2.0;
+            cr.new_sub_path();
+            cr.arc(radius, radius, radius, PI / 2.0, 1.5 * PI);
+            cr.arc(width as f64 - radius, radius, radius, -PI / 2.0, PI / 2.0);
+            cr.close_path();
+            let (r, g, b, a) = style.background.to_cairo_rgba();
+            cr.set_source_rgba(r, g, b, a);
+            cr.fill()?;
+
+            let (r, g, b, a) = style.text_color.to_cairo_rgba();
+            cr.set_source_rgba(r, g, b, a);
+            cr.move_to(
+                (PADDING_X - extents.x()) as f64,
+                (PADDING_Y - extents.y()) as f64,
+            );
+            pangocairo::functions::show_layout(&cr, &layout);
+        }
+        surface.flush();
+
+        Ok(Self {
+            surface,
+            position: style.position,
+        })
+    }
+
+    pub fn width(&self) -> i32 {
+        self.surface.width()
+    }
+
+    pub fn height(&self) -> i32 {
+        self.surface.height()
+    }
+
+    /// Top-left corner of the label beside a highlight of `radius` around
+    /// the cursor
+    pub fn origin(&self, cursor_x: f64, cursor_y: f64, radius: f64) -> (f64, f64) {
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        let distance = radius + MARGIN;
+        match self.position {
+            LabelPosition::Below => (cursor_x - width / 2.0, cursor_y + distance),
+            LabelPosition::Above => (cursor_x - width / 2.0, cursor_y - distance - height),
+            LabelPosition::Left => (cursor_x - distance - width, cursor_y - height / 2.0),
+            LabelPosition::Right => (cursor_x + distance, cursor_y - height / 2.0),
+        }
+    }
+
+    /// Paint the label beside a highlight of `radius` around the cursor
+    pub fn draw(&self, cr: &Context, cursor_x: f64, cursor_y: f64, radius: f64) {
+        self.draw_at(cr, self.origin(cursor_x, cursor_y, radius));
+    }
+
+    /// Paint the label with its top-left corner at `(x, y)`
+    pub fn draw_at(&self, cr: &Context, (x, y): (f64, f64)) {
+        cr.set_source_surface(&self.surface, x.round(), y.round())
+            .ok();
+        cr.paint().ok();
+    }
+}
@@ -0,0 +1,466 @@
+//! Frame pacing and performance counters for the X11 overlay
+//!
+//! Frames are timed to the refresh rate of the monitor under the cursor, read
+//! from the RandR mode in use. Where the Present extension is available, the
+//! interval between its vblank notifications corrects that estimate. The
+//! same RandR CRTCs give the overlay its monitor rectangles.
+
+use anyhow::Result;
+use std::fmt;
+use std::time::{Duration, Instant};
+use x11rb::connection::Connection;
+use x11rb::protocol::present::{self, ConnectionExt as PresentConnectionExt};
+use x11rb::protocol::randr::{self, ConnectionExt as RandrConnectionExt, ModeFlag};
+use x11rb::protocol::xproto::Window;
+use x11rb::rust_connection::RustConnection;
+
+/// Frame interval when the refresh rate cannot be read (60 Hz)
+const DEFAULT_INTERVAL: Duration = Duration::from_micros(16_667);
+
+/// Refresh rates outside this range are treated as bogus, in Hz
+const MIN_REFRESH_RATE: f64 = 20.0;
+const MAX_REFRESH_RATE: f64 = 500.0;
+
+/// How often frame statistics are reported
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Schedules overlay frames at the monitor's refresh rate
+pub struct FramePacer {
+    interval: Duration,
+    next_frame: Instant,
+    /// Serial of the last vblank notification requested, with Present
+    present_serial: Option<u32>,
+    /// Counter and timestamp (microseconds) of the last vblank seen
+    last_vblank: Option<(u64, u64)>,
+    stats: FrameStats,
+}
+
+impl FramePacer {
+    /// Pace frames for `window`, at the refresh rate of the monitor
+    /// containing `pointer`
+    pub fn new(conn: &RustConnection, root: Window, window: Window, pointer: (i16, i16)) -> Self {
+        let interval = match refresh_rate(conn, root, pointer) {
+            Some(rate) => {
+                tracing::info!("Pacing overlay frames at {:.2} Hz", rate);
+                Duration::from_secs_f64(1.0 / rate)
+            }
+            None => {
+                tracing::info!("Refresh rate unknown, pacing overlay frames at 60 Hz");
+                DEFAULT_INTERVAL
+            }
+        };
+
+        let mut pacer = Self::with_interval(interval, Instant::now());
+        match select_present_events(conn, window) {
+            Ok(()) => pacer.present_serial = Some(0),
+            Err(e) => tracing::debug!("Present extension unavailable: {}", e),
+        }
+        pacer
+    }
+
+    /// Pace frames `interval` apart, the first due at `now`, without Present
+    fn with_interval(interval: Duration, now: Instant) -> Self {
+        Self {
+            interval,
+            next_frame: now,
+            present_serial: None,
+            last_vblank: None,
+            stats: FrameStats::new(now),
+        }
+    }
+
+    /// Sleep until the next frame is due, counting any frames missed
+    pub fn wait(&mut self) {
+        if let Some(delay) = self.advance(Instant::now()) {
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Schedule the frame after the one due at `now`, returning how long to
+    /// wait for the current one if it isn't due yet
+    fn advance(&mut self, now: Instant) -> Option<Duration> {
+        if now < self.next_frame {
+            let delay = self.next_frame - now;
+            self.next_frame += self.interval;
+            return Some(delay);
+        }
+
+        // Running late: count the frames that went by and start afresh
+        let late = now - self.next_frame;
+        let missed = (late.as_secs_f64() / self.interval.as_secs_f64()) as u32;
+        self.stats.missed += missed;
+        self.next_frame = now + self.interval;
+        None
+    }
+
+    /// Ask the Present extension to report the next vblank
+    pub fn request_vblank(&mut self, conn: &RustConnection, window: Window) -> Result<()> {
+        if let Some(serial) = &mut self.present_serial {
+            *serial = serial.wrapping_add(1);
+            conn.present_notify_msc(window, *serial, 0, 1, 0)?;
+        }
+        Ok(())
+    }
+
+    /// Measure the refresh interval from a vblank notification
+    pub fn vblank(&mut self, event: &present::CompleteNotifyEvent) {
+        self.vblank_at(event.msc, event.ust);
+    }
+
+    /// Measure the refresh interval from vblank counter `msc` reached at
+    /// `ust` microseconds
+    fn vblank_at(&mut self, msc: u64, ust: u64) {
+        if let Some((last_msc, last_ust)) = self.last_vblank {
+            if msc > last_msc && ust > last_ust {
+                let period = (ust - last_ust) as f64 / (msc - last_msc) as f64 / 1_000_000.0;
+                if (1.0 / MAX_REFRESH_RATE..=1.0 / MIN_REFRESH_RATE).contains(&period) {
+                    self.interval = Duration::from_secs_f64(period);
+                }
+            }
+        }
+        self.last_vblank = Some((msc, ust));
+    }
+
+    pub fn stats(&mut self) -> &mut FrameStats {
+        &mut self.stats
+    }
+}
+
+/// Counts frames drawn, skipped and missed
+pub struct FrameStats {
+    since: Instant,
+    drawn: u32,
+    skipped: u32,
+    missed: u32,
+    draw_time: Duration,
+}
+
+impl FrameStats {
+    /// Start counting at `since`
+    fn new(since: Instant) -> Self {
+        Self {
+            since,
+            drawn: 0,
+            skipped: 0,
+            missed: 0,
+            draw_time: Duration::ZERO,
+        }
+    }
+
+    /// A frame was drawn in `duration`
+    pub fn drawn(&mut self, duration: Duration) {
+        self.drawn += 1;
+        self.draw_time += duration;
+    }
+
+    /// A frame was skipped because nothing changed
+    pub fn skipped(&mut self) {
+        self.skipped += 1;
+    }
+
+    /// Totals since the last report, once a report is due
+    pub fn report(&mut self) -> Option<FrameReport> {
+        self.report_at(Instant::now())
+    }
+
+    /// Totals since the last report, if one is due at `now`
+    fn report_at(&mut self, now: Instant) -> Option<FrameReport> {
+        let elapsed = now.saturating_duration_since(self.since);
+        if elapsed < REPORT_INTERVAL {
+            return None;
+        }
+
+        let report = FrameReport {
+            fps: self.drawn as f64 / elapsed.as_secs_f64(),
+            frame_ms: if self.drawn > 0 {
+                self.draw_time.as_secs_f64() * 1000.0 / self.drawn as f64
+            } else {
+                0.0
+            },
+            skipped: self.skipped,
+            missed: self.missed,
+        };
+        *self = Self::new(now);
+        Some(report)
+    }
+}
+
+/// Frame statistics over one report interval
+pub struct FrameReport {
+    /// Frames drawn per second
+    pub fps: f64,
+    /// Average time to draw a frame, in milliseconds
+    pub frame_ms: f64,
+    /// Frames not redrawn because nothing changed
+    pub skipped: u32,
+    /// Frames that went by while a frame was late
+    pub missed: u32,
+}
+
+impl fmt::Display for FrameReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.1} fps, {:.2} ms/frame, {} skipped, {} missed",
+            self.fps, self.frame_ms, self.skipped, self.missed
+        )
+    }
+}
+
+/// Refresh rate in Hz of the monitor containing `pointer`, or of the
+/// fastest monitor if none does
+fn refresh_rate(conn: &RustConnection, root: Window, (px, py): (i16, i16)) -> Option<f64> {
+    let (resources, crtcs) = active_crtcs(conn, root)?;
+
+    let mut best: Option<(bool, f64)> = None;
+    for info in crtcs {
+        let Some(rate) = resources
+            .modes
+            .iter()
+            .find(|mode| mode.id == info.mode)
+            .and_then(mode_rate)
+        else {
+            continue;
+        };
+
+        let (x, y) = (info.x as i32, info.y as i32);
+        let (px, py) = (px as i32, py as i32);
+        let under_pointer =
+            px >= x && px < x + info.width as i32 && py >= y && py < y + info.height as i32;
+        if best.is_none_or(|best| (under_pointer, rate) > best) {
+            best = Some((under_pointer, rate));
+        }
+    }
+    best.map(|(_, rate)| rate)
+}
+
+/// Rectangles of the monitors as x, y, width and height in root window
+/// pixels, or none without RandR
+///
+/// Unlike GDK monitor geometry these are not divided by the scale factor,
+/// so they match pointer positions from `QueryPointer`.
+pub fn monitor_rects(conn: &RustConnection, root: Window) -> Vec<(f64, f64, f64, f64)> {
+    let Some((_, crtcs)) = active_crtcs(conn, root) else {
+        return Vec::new();
+    };
+    crtcs
+        .iter()
+        .map(|info| {
+            (
+                info.x as f64,
+                info.y as f64,
+                info.width as f64,
+                info.height as f64,
+            )
+        })
+        .collect()
+}
+
+/// The screen resources and the CRTCs that drive a monitor
+fn active_crtcs(
+    conn: &RustConnection,
+    root: Window,
+) -> Option<(
+    randr::GetScreenResourcesCurrentReply,
+    Vec<randr::GetCrtcInfoReply>,
+)> {
+    // Fails without RandR, like every request below
+    let resources = conn
+        .randr_get_screen_resources_current(root)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    let crtcs = resources
+        .crtcs
+        .iter()
+        .filter_map(|&crtc| {
+            conn.randr_get_crtc_info(crtc, resources.config_timestamp)
+                .ok()?
+                .reply()
+                .ok()
+        })
+        // Disabled CRTCs have no mode
+        .filter(|info| info.mode != 0 && info.width > 0 && info.height > 0)
+        .collect();
+    Some((resources, crtcs))
+}
+
+/// Vertical refresh rate of a mode, in Hz
+fn mode_rate(mode: &randr::ModeInfo) -> Option<f64> {
+    let flags = u32::from(mode.mode_flags);
+    let mut lines = mode.vtotal as f64;
+    if flags & u32::from(ModeFlag::DOUBLE_SCAN) != 0 {
+        lines *= 2.0;
+    }
+    if flags & u32::from(ModeFlag::INTERLACE) != 0 {
+        lines /= 2.0;
+    }
+
+    let rate = mode.dot_clock as f64 / (mode.htotal as f64 * lines);
+    (MIN_REFRESH_RATE..=MAX_REFRESH_RATE)
+        .contains(&rate)
+        .then_some(rate)
+}
+
+/// Ask for vblank notifications on `window`; fails without Present
+fn select_present_events(conn: &RustConnection, window: Window) -> Result<()> {
+    conn.present_query_version(1, 0)?.reply()?;
+
+    let event_id = conn.generate_id()?;
+    conn.present_select_input(event_id, window, present::EventMask::COMPLETE_NOTIFY)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(10);
+
+    /// A mode with the given timings
+    fn mode(dot_clock: u32, htotal: u16, vtotal: u16, flags: ModeFlag) -> randr::ModeInfo {
+        randr::ModeInfo {
+            dot_clock,
+            htotal,
+            vtotal,
+            mode_flags: flags,
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn mode_rate_follows_the_mode_timings() {
+        // CEA 1080p60 and 1080i60
+        let progressive = mode(148_500_000, 2200, 1125, ModeFlag::default());
+        assert_close(mode_rate(&progressive).unwrap(), 60.0);
+        let interlaced = mode(74_250_000, 2200, 1125, ModeFlag::INTERLACE);
+        assert_close(mode_rate(&interlaced).unwrap(), 60.0);
+        let double_scan = mode(25_175_000, 800, 262, ModeFlag::DOUBLE_SCAN);
+        assert_close(mode_rate(&double_scan).unwrap(), 60.05);
+        // 2560x1440 at 144 Hz
+        let fast = mode(586_586_000, 2720, 1497, ModeFlag::default());
+        assert_close(mode_rate(&fast).unwrap(), 144.06);
+    }
+
+    #[test]
+    fn mode_rate_rejects_bogus_rates() {
+        assert_eq!(mode_rate(&mode(0, 2200, 1125, ModeFlag::default())), None);
+        assert_eq!(
+            mode_rate(&mode(148_500_000, 0, 0, ModeFlag::default())),
+            None
+        );
+        assert_eq!(
+            mode_rate(&mode(1_000_000, 2200, 1125, ModeFlag::default())),
+            None
+        );
+    }
+
+    #[test]
+    fn frames_on_time_wait_for_their_slot() {
+        let start = Instant::now();
+        let mut pacer = FramePacer::with_interval(INTERVAL, start);
+
+        assert_eq!(pacer.advance(start), None);
+        assert_eq!(pacer.next_frame, start + INTERVAL);
+        assert_eq!(
+            pacer.advance(start + Duration::from_millis(4)),
+            Some(Duration::from_millis(6))
+        );
+        assert_eq!(pacer.next_frame, start + INTERVAL * 2);
+        assert_eq!(pacer.stats.missed, 0);
+    }
+
+    #[test]
+    fn late_frames_count_the_frames_missed() {
+        let start = Instant::now();
+        let mut pacer = FramePacer::with_interval(INTERVAL, start);
+
+        // Two and a half intervals late
+        let late = start + Duration::from_millis(25);
+        assert_eq!(pacer.advance(late), None);
+        assert_eq!(pacer.stats.missed, 2);
+        assert_eq!(pacer.next_frame, late + INTERVAL);
+
+        // Back on time afterwards
+        assert_eq!(
+            pacer.advance(late + Duration::from_millis(1)),
+            Some(Duration::from_millis(9))
+        );
+        assert_eq!(pacer.stats.missed, 2);
+    }
+
+    #[test]
+    fn vblanks_correct_the_interval() {
+        let mut pacer = FramePacer::with_interval(INTERVAL, Instant::now());
+
+        // The first notification only gives a starting point
+        pacer.vblank_at(100, 1_000_000);
+        assert_eq!(pacer.interval, INTERVAL);
+
+        // Two vblanks in 33,334 µs is 60 Hz
+        pacer.vblank_at(102, 1_033_334);
+        assert_eq!(pacer.interval, Duration::from_micros(16_667));
+
+        // A skipped counter still divides by the vblanks that went by
+        pacer.vblank_at(105, 1_054_167);
+        assert_eq!(pacer.interval, Duration::from_nanos(6_944_333));
+    }
+
+    #[test]
+    fn vblanks_out_of_order_or_range_are_ignored() {
+        let mut pacer = FramePacer::with_interval(INTERVAL, Instant::now());
+        pacer.vblank_at(100, 1_000_000);
+
+        // Counter reset, as after a mode change
+        pacer.vblank_at(5, 1_016_667);
+        assert_eq!(pacer.interval, INTERVAL);
+        // Same counter again
+        pacer.vblank_at(5, 1_033_334);
+        assert_eq!(pacer.interval, INTERVAL);
+        // 10 Hz is slower than any real monitor
+        pacer.vblank_at(6, 1_133_334);
+        assert_eq!(pacer.interval, INTERVAL);
+        // 1000 Hz is faster than any real monitor
+        pacer.vblank_at(7, 1_134_334);
+        assert_eq!(pacer.interval, INTERVAL);
+    }
+
+    #[test]
+    fn stats_report_once_a_second_and_start_over() {
+        let start = Instant::now();
+        let mut stats = FrameStats::new(start);
+        for _ in 0..50 {
+            stats.drawn(Duration::from_millis(2));
+        }
+        stats.skipped();
+        stats.missed += 3;
+
+        assert!(stats
+            .report_at(start + Duration::from_millis(999))
+            .is_none());
+        let report = stats.report_at(start + Duration::from_secs(2)).unwrap();
+        assert_close(report.fps, 25.0);
+        assert_close(report.frame_ms, 2.0);
+        assert_eq!((report.skipped, report.missed), (1, 3));
+        assert_eq!(
+            report.to_string(),
+            "25.0 fps, 2.00 ms/frame, 1 skipped, 3 missed"
+        );
+
+        let next = start + Duration::from_secs(3);
+        let empty = stats.report_at(next).unwrap();
+        assert_eq!(empty.frame_ms, 0.0);
+        assert_eq!((empty.skipped, empty.missed), (0, 0));
+        assert!(stats.report_at(next).is_none());
+    }
+}
@@ -3,10 +3,17 @@
 //! Creates a transparent fullscreen overlay that tracks pointer position
 //! and draws the cursor highlight.
 
-use crate::models::{AnimationStyle, AnimationType, CursorStyle, Shape};
+use super::cursor_label::CursorLabel;
+use super::shape_geometry;
+use super::shape_image::ShapeImages;
+use crate::models::{
+    AnimationStyle, AnimationType, ColorStop, CursorStyle, GuideLines, Layer, Paint, Pattern,
+    Shape, TransitionLabel,
+};
 use anyhow::Result;
 use gtk4::cairo::{self, Context};
 use gtk4::gdk;
+use gtk4::gdk_pixbuf::Pixbuf;
 use gtk4::glib::{self, ControlFlow};
 use gtk4::prelude::*;
 use gtk4::DrawingArea;
@@ -22,6 +29,9 @@ pub struct HighlightOverlay {
     cursor_position: Rc<Cell<(f64, f64)>>,
     style: Rc<RefCell<CursorStyle>>,
     animation: Rc<RefCell<AnimationState>>,
+    images: Rc<RefCell<ShapeImages>>,
+    /// Label drawn beside the highlight until it hides
+    label: Rc<RefCell<Option<CursorLabel>>>,
     is_visible: Rc<Cell<bool>>,
 }
 
@@ -43,6 +53,62 @@ impl Default for AnimationState {
     }
 }
 
+/// Animation values for one frame
+pub struct Frame {
+    /// Opacity multiplier
+    fade: f64,
+    /// Size multiplier
+    scale: f64,
+    /// Seconds since the highlight started
+    elapsed: f64,
+    /// Monitor rectangles, which pointer arrows and guide lines use
+    monitors: Vec<shape_geometry::Rect>,
+}
+
+impl Frame {
+    /// The frame `progress` of the way through an animation cycle and
+    /// `elapsed` seconds into the highlight
+    pub fn new(
+        animation_style: &AnimationStyle,
+        progress: f64,
+        elapsed: f64,
+        monitors: Vec<shape_geometry::Rect>,
+    ) -> Self {
+        let (fade, scale) = match animation_style.animation_type {
+            AnimationType::None => (1.0, 1.0),
+            AnimationType::Pulse => (0.3 + 0.7 * (1.0 - progress), 1.0),
+            AnimationType::Fade => (1.0 - progress * 0.7, 1.0),
+            AnimationType::Scale => (1.0, 0.8 + 0.4 * (1.0 - progress)),
+            AnimationType::Ripple => (1.0 - progress, 1.0 + progress * 0.5),
+        };
+        Self {
+            fade,
+            scale,
+            elapsed,
+            monitors,
+        }
+    }
+
+    /// Size multiplier for the layers
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// The area guide lines through `cursor` span, or `None` when they are off
+    pub fn guide_bounds(
+        &self,
+        guide_lines: GuideLines,
+        cursor: shape_geometry::Point,
+    ) -> Option<shape_geometry::Rect> {
+        let desktop = shape_geometry::bounding_box(&self.monitors);
+        match guide_lines {
+            GuideLines::Off => None,
+            GuideLines::Desktop => desktop,
+            GuideLines::Monitor => shape_geometry::rect_at(&self.monitors, cursor).or(desktop),
+        }
+    }
+}
+
 /// Query the current cursor position using X11
 fn get_x11_cursor_position() -> Option<(f64, f64)> {
     use x11rb::connection::Connection;
@@ -111,6 +177,8 @@ impl HighlightOverlay {
         let cursor_position = Rc::new(Cell::new((screen_width as f64 / 2.0, screen_height as f64 / 2.0)));
         let style = Rc::new(RefCell::new(CursorStyle::default()));
         let animation = Rc::new(RefCell::new(AnimationState::default()));
+        let images = Rc::new(RefCell::new(ShapeImages::default()));
+        let label: Rc<RefCell<Option<CursorLabel>>> = Rc::new(RefCell::new(None));
         let is_visible = Rc::new(Cell::new(false));
 
         // Set up drawing
@@ -118,8 +186,10 @@ impl HighlightOverlay {
         let animation_clone = animation.clone();
         let cursor_pos_draw = cursor_position.clone();
         let is_visible_draw = is_visible.clone();
+        let images_draw = images.clone();
+        let label_draw = label.clone();
 
-        drawing_area.set_draw_func(move |_area, cr, _width, _height| {
+        drawing_area.set_draw_func(move |area, cr, _width, _height| {
             // Clear to transparent
             cr.set_operator(cairo::Operator::Clear);
             cr.paint().ok();
@@ -136,7 +206,14 @@ impl HighlightOverlay {
                 cursor_y,
                 &style_clone.borrow(),
                 &animation_clone.borrow(),
+                &mut images_draw.borrow_mut(),
+                area,
             );
+
+            if let Some(label) = label_draw.borrow().as_ref() {
+                let radius = style_clone.borrow().outer_size() / 2.0;
+                label.draw(cr, cursor_x, cursor_y, radius);
+            }
         });
 
         Ok(Self {
@@ -145,6 +222,8 @@ impl HighlightOverlay {
             cursor_position,
             style,
             animation,
+            images,
+            label,
             is_visible,
         })
     }
@@ -160,6 +239,7 @@ impl HighlightOverlay {
     ) {
         self.cursor_position.set((x, y));
         *self.style.borrow_mut() = style.clone();
+        *self.images.borrow_mut() = ShapeImages::default();
 
         // Reset animation state
         {
@@ -181,9 +261,21 @@ impl HighlightOverlay {
         self.start_animation_loop(duration);
     }
 
+    /// Show `text` beside the next highlight, until it hides
+    pub fn set_label(&mut self, text: &str, style: &TransitionLabel) {
+        *self.label.borrow_mut() = match CursorLabel::new(text, style) {
+            Ok(label) => Some(label),
+            Err(e) => {
+                tracing::warn!("Failed to render cursor label: {}", e);
+                None
+            }
+        };
+    }
+
     /// Hide the highlight
     pub fn hide(&mut self) {
         self.is_visible.set(false);
+        *self.label.borrow_mut() = None;
         self.window.set_opacity(0.0);
         self.window.set_visible(false);
     }
@@ -203,6 +295,7 @@ impl HighlightOverlay {
         let is_visible = self.is_visible.clone();
         let window = self.window.clone();
         let cursor_position = self.cursor_position.clone();
+        let label = self.label.clone();
 
         let duration_ms = (duration * 1000.0) as u64;
         let start = Instant::now();
@@ -217,6 +310,7 @@ impl HighlightOverlay {
                 window.set_visible(false);
                 window.set_opacity(0.0);
                 is_visible.set(false);
+                *label.borrow_mut() = None;
                 return ControlFlow::Break;
             }
 
@@ -269,64 +363,159 @@ impl HighlightOverlay {
         cursor_y: f64,
         style: &CursorStyle,
         animation: &AnimationState,
+        images: &mut ShapeImages,
+        area: &DrawingArea,
     ) {
-        // Apply animation
-        let (alpha, scale) = match animation.style.animation_type {
-            AnimationType::None => (style.color.a as f64, 1.0),
-            AnimationType::Pulse => {
-                let alpha = 0.3 + 0.7 * (1.0 - animation.progress);
-                (alpha * style.color.a as f64, 1.0)
-            }
-            AnimationType::Fade => {
-                let alpha = 1.0 - animation.progress * 0.7;
-                (alpha * style.color.a as f64, 1.0)
-            }
-            AnimationType::Scale => {
-                let scale = 0.8 + 0.4 * (1.0 - animation.progress);
-                (style.color.a as f64, scale)
-            }
-            AnimationType::Ripple => {
-                let alpha = 1.0 - animation.progress;
-                let scale = 1.0 + animation.progress * 0.5;
-                (alpha * style.color.a as f64, scale)
-            }
-        };
-
-        let size = style.size * scale;
-        let (r, g, b) = style.color.to_cairo_rgb();
+        let elapsed = animation
+            .start_time
+            .map_or(0.0, |start| start.elapsed().as_secs_f64());
+        let monitors = Self::monitors(area);
+        let frame = Frame::new(&animation.style, animation.progress, elapsed, monitors);
+        let cursor = (cursor_x, cursor_y);
+        Self::draw_frame(cr, cursor, style, &frame, images, area.scale_factor() as f64);
+    }
 
-        // Draw glow if enabled
+    /// Draw one frame of the highlight around `cursor`
+    ///
+    /// Needs no window, so previews are drawn the same way. Images are
+    /// rasterized at `device_scale` so they stay sharp on HiDPI monitors.
+    pub fn draw_frame(
+        cr: &Context,
+        (cursor_x, cursor_y): (f64, f64),
+        style: &CursorStyle,
+        frame: &Frame,
+        images: &mut ShapeImages,
+        device_scale: f64,
+    ) {
+        // Draw glow if enabled, around the largest layer
         if style.glow_enabled && style.glow_intensity > 0.0 {
-            let glow_alpha = alpha * style.glow_intensity * 0.5;
-            let glow_size = size + style.glow_radius * 2.0;
+            let (r, g, b, a) = style.color.sample(0.0, 0.0, frame.elapsed).to_cairo_rgba();
+            let glow_alpha = frame.fade * a * style.glow_intensity * 0.5;
+            let glow_size = style.outer_size() * frame.scale + style.glow_radius * 2.0;
 
             cr.set_source_rgba(r, g, b, glow_alpha);
             cr.arc(cursor_x, cursor_y, glow_size / 2.0, 0.0, 2.0 * PI);
             cr.fill().ok();
         }
 
+        let layers = style.effective_layers();
+
+        // Guide lines go beneath the highlight
+        Self::draw_guide_lines(cr, cursor_x, cursor_y, &layers, style.guide_lines, frame);
+
+        // Draw layers bottom first
+        for mut layer in layers {
+            let mut image = None;
+            if let Shape::Image(shape) = &layer.shape {
+                image = images.get(shape, layer.size * device_scale).cloned();
+                if image.is_none() {
+                    // Still show where the cursor is if the file is missing
+                    layer.shape = Shape::Ring;
+                }
+            }
+            Self::draw_layer(cr, cursor_x, cursor_y, &layer, frame, image.as_ref());
+        }
+    }
+
+    /// Draw one layer of the highlight, with `image` loaded for image shapes
+    fn draw_layer(
+        cr: &Context,
+        cursor_x: f64,
+        cursor_y: f64,
+        layer: &Layer,
+        frame: &Frame,
+        image: Option<&Pixbuf>,
+    ) {
+        let size = layer.size * frame.scale;
+        Self::set_paint_source(
+            cr,
+            &layer.color,
+            cursor_x,
+            cursor_y,
+            size / 2.0,
+            frame.fade,
+            frame.elapsed,
+        );
+
+        let dashes: Vec<f64> = layer
+            .border_style
+            .dash_pattern()
+            .iter()
+            .map(|length| length * layer.border_weight)
+            .collect();
+        cr.set_dash(&dashes, 0.0);
+
         // Draw main shape
-        match style.shape {
+        match &layer.shape {
             Shape::Circle => {
-                cr.set_source_rgba(r, g, b, alpha);
                 cr.arc(cursor_x, cursor_y, size / 2.0, 0.0, 2.0 * PI);
                 cr.fill().ok();
             }
-            Shape::Ring => {
-                cr.set_source_rgba(r, g, b, alpha);
-                cr.set_line_width(style.border_weight);
-                cr.arc(
-                    cursor_x,
-                    cursor_y,
-                    size / 2.0 - style.border_weight / 2.0,
-                    0.0,
-                    2.0 * PI,
-                );
+            Shape::Ring | Shape::Reticle => {
+                cr.set_line_width(layer.border_weight);
+                let radius = size / 2.0 - layer.border_weight / 2.0;
+                cr.arc(cursor_x, cursor_y, radius, 0.0, 2.0 * PI);
+                cr.stroke().ok();
+
+                if matches!(layer.shape, Shape::Reticle) {
+                    for (start, end) in shape_geometry::reticle_ticks(cursor_x, cursor_y, radius) {
+                        cr.move_to(start.0, start.1);
+                        cr.line_to(end.0, end.1);
+                    }
+                    cr.stroke().ok();
+                }
+            }
+            Shape::RoundedSquare => {
+                cr.set_line_width(layer.border_weight);
+                let (centers, corner) =
+                    shape_geometry::rounded_square(cursor_x, cursor_y, size, layer.border_weight);
+
+                // Each corner turns a quarter further, starting from the top right
+                cr.new_sub_path();
+                for (i, (x, y)) in centers.into_iter().enumerate() {
+                    let start = (i as f64 - 1.0) * PI / 2.0;
+                    cr.arc(x, y, corner, start, start + PI / 2.0);
+                }
+                cr.close_path();
                 cr.stroke().ok();
             }
+            Shape::Starburst => {
+                cr.set_line_width(layer.border_weight);
+                for ((start, end), _) in shape_geometry::star_rays(cursor_x, cursor_y, size / 2.0) {
+                    cr.move_to(start.0, start.1);
+                    cr.line_to(end.0, end.1);
+                }
+                cr.stroke().ok();
+            }
+            Shape::PointerArrows => {
+                cr.set_line_width(layer.border_weight);
+                for &monitor in &frame.monitors {
+                    let Some(arrow) = shape_geometry::pointer_arrow(
+                        shape_geometry::center(monitor),
+                        (cursor_x, cursor_y),
+                        size,
+                        layer.border_weight,
+                    ) else {
+                        continue;
+                    };
+
+                    let ((x1, y1), (x2, y2)) = arrow.shaft;
+                    cr.move_to(x1, y1);
+                    cr.line_to(x2, y2);
+                    cr.stroke().ok();
+
+                    cr.set_dash(&[], 0.0);
+                    let [tip, left, right] = arrow.head;
+                    cr.move_to(tip.0, tip.1);
+                    cr.line_to(left.0, left.1);
+                    cr.line_to(right.0, right.1);
+                    cr.close_path();
+                    cr.fill().ok();
+                    cr.set_dash(&dashes, 0.0);
+                }
+            }
             Shape::Crosshair => {
-                cr.set_source_rgba(r, g, b, alpha);
-                cr.set_line_width(style.border_weight);
+                cr.set_line_width(layer.border_weight);
 
                 // Horizontal line
                 cr.move_to(cursor_x - size / 2.0, cursor_y);
@@ -339,7 +528,8 @@ impl HighlightOverlay {
                 cr.stroke().ok();
             }
             Shape::Spotlight => {
-                let pattern = cairo::RadialGradient::new(
+                // Fade the paint out towards the edge
+                let falloff = cairo::RadialGradient::new(
                     cursor_x,
                     cursor_y,
                     0.0,
@@ -347,14 +537,202 @@ impl HighlightOverlay {
                     cursor_y,
                     size / 2.0,
                 );
-                pattern.add_color_stop_rgba(0.0, r, g, b, alpha * 0.8);
-                pattern.add_color_stop_rgba(0.5, r, g, b, alpha * 0.3);
-                pattern.add_color_stop_rgba(1.0, r, g, b, 0.0);
+                falloff.add_color_stop_rgba(0.0, 0.0, 0.0, 0.0, 0.8);
+                falloff.add_color_stop_rgba(0.5, 0.0, 0.0, 0.0, 0.3);
+                falloff.add_color_stop_rgba(1.0, 0.0, 0.0, 0.0, 0.0);
 
-                cr.set_source(&pattern).ok();
-                cr.arc(cursor_x, cursor_y, size / 2.0, 0.0, 2.0 * PI);
-                cr.fill().ok();
+                cr.mask(&falloff).ok();
+            }
+            Shape::Image(shape) => {
+                if let Some(pixbuf) = image {
+                    Self::draw_image(cr, cursor_x, cursor_y, pixbuf, size, shape.tint, frame.fade);
+                }
+            }
+        }
+    }
+
+    /// Rectangles of the monitors of the display `area` is shown on
+    fn monitors(area: &DrawingArea) -> Vec<shape_geometry::Rect> {
+        let monitors = area.display().monitors();
+        (0..monitors.n_items())
+            .filter_map(|i| monitors.item(i).and_downcast::<gdk::Monitor>())
+            .map(|monitor| {
+                let geometry = monitor.geometry();
+                (
+                    geometry.x() as f64,
+                    geometry.y() as f64,
+                    geometry.width() as f64,
+                    geometry.height() as f64,
+                )
+            })
+            .collect()
+    }
+
+    /// Draw lines through the cursor like the top layer, leaving the
+    /// highlight clear
+    fn draw_guide_lines(
+        cr: &Context,
+        cursor_x: f64,
+        cursor_y: f64,
+        layers: &[Layer],
+        guide_lines: GuideLines,
+        frame: &Frame,
+    ) {
+        let cursor = (cursor_x, cursor_y);
+        let bounds = frame.guide_bounds(guide_lines, cursor);
+        let (Some(top), Some(bounds)) = (layers.last(), bounds) else {
+            return;
+        };
+
+        let largest = layers.iter().map(|layer| layer.size).fold(0.0, f64::max);
+        let gap = largest * frame.scale / 2.0;
+        Self::set_paint_source(
+            cr,
+            &top.color,
+            cursor_x,
+            cursor_y,
+            gap,
+            frame.fade,
+            frame.elapsed,
+        );
+        let dashes: Vec<f64> = top
+            .border_style
+            .dash_pattern()
+            .iter()
+            .map(|length| length * top.border_weight)
+            .collect();
+        cr.set_dash(&dashes, 0.0);
+        cr.set_line_width(top.border_weight);
+
+        for (start, end) in shape_geometry::guide_lines(cursor, bounds, gap) {
+            cr.move_to(start.0, start.1);
+            cr.line_to(end.0, end.1);
+        }
+        cr.stroke().ok();
+    }
+
+    /// Draw a picture centered on the cursor, fitted into a `size` square
+    ///
+    /// Tinted pictures are used as a mask for the current source, so they
+    /// keep their alpha but take the layer's paint.
+    fn draw_image(
+        cr: &Context,
+        cursor_x: f64,
+        cursor_y: f64,
+        pixbuf: &Pixbuf,
+        size: f64,
+        tint: bool,
+        fade: f64,
+    ) {
+        let fit = size / pixbuf.width().max(pixbuf.height()) as f64;
+        let width = pixbuf.width() as f64 * fit;
+        let height = pixbuf.height() as f64 * fit;
+
+        cr.save().ok();
+        cr.translate(cursor_x - width / 2.0, cursor_y - height / 2.0);
+        cr.scale(fit, fit);
+
+        cr.push_group();
+        cr.set_source_pixbuf(pixbuf, 0.0, 0.0);
+        cr.paint().ok();
+        let Ok(picture) = cr.pop_group() else {
+            cr.restore().ok();
+            return;
+        };
+
+        if tint {
+            // The source was set before scaling, so the paint still spans
+            // the layer rather than the picture's pixels
+            cr.mask(&picture).ok();
+        } else {
+            cr.set_source(&picture).ok();
+            cr.paint_with_alpha(fade).ok();
+        }
+        cr.restore().ok();
+    }
+
+    /// Use `paint` as the source for a shape of `radius` around the cursor
+    fn set_paint_source(
+        cr: &Context,
+        paint: &Paint,
+        cursor_x: f64,
+        cursor_y: f64,
+        radius: f64,
+        fade: f64,
+        elapsed: f64,
+    ) {
+        let add_stops = |gradient: &cairo::Gradient, stops: &[ColorStop]| {
+            for stop in stops {
+                let (r, g, b, a) = stop.color.to_cairo_rgba();
+                gradient.add_color_stop_rgba(stop.offset, r, g, b, a * fade);
+            }
+        };
+
+        match (paint, paint.stops()) {
+            (Paint::Pattern(Pattern::LinearGradient { angle, .. }), Some(stops)) => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let (dx, dy) = (cos * radius, sin * radius);
+                let gradient = cairo::LinearGradient::new(
+                    cursor_x - dx,
+                    cursor_y - dy,
+                    cursor_x + dx,
+                    cursor_y + dy,
+                );
+                add_stops(&gradient, &stops);
+                cr.set_source(&gradient).ok();
+            }
+            (Paint::Pattern(Pattern::RadialGradient { .. }), Some(stops)) => {
+                let gradient =
+                    cairo::RadialGradient::new(cursor_x, cursor_y, 0.0, cursor_x, cursor_y, radius);
+                add_stops(&gradient, &stops);
+                cr.set_source(&gradient).ok();
+            }
+            _ => {
+                let (r, g, b, a) = paint.sample(0.0, 0.0, elapsed).to_cairo_rgba();
+                cr.set_source_rgba(r, g, b, a * fade);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4K monitor at 200% beside a 1080p one, in root window pixels
+    const MONITORS: [shape_geometry::Rect; 2] =
+        [(0.0, 0.0, 3840.0, 2160.0), (3840.0, 0.0, 1920.0, 1080.0)];
+
+    fn frame() -> Frame {
+        Frame::new(&AnimationStyle::default(), 0.0, 0.0, MONITORS.to_vec())
+    }
+
+    #[test]
+    fn guide_lines_span_the_monitor_under_the_cursor() {
+        let frame = frame();
+        assert_eq!(
+            frame.guide_bounds(GuideLines::Monitor, (4000.0, 500.0)),
+            Some(MONITORS[1])
+        );
+        assert_eq!(
+            frame.guide_bounds(GuideLines::Monitor, (3000.0, 2000.0)),
+            Some(MONITORS[0])
+        );
+    }
+
+    #[test]
+    fn guide_lines_span_the_desktop() {
+        let frame = frame();
+        let desktop = Some((0.0, 0.0, 5760.0, 2160.0));
+        assert_eq!(
+            frame.guide_bounds(GuideLines::Desktop, (100.0, 100.0)),
+            desktop
+        );
+        // Below the smaller monitor, where no monitor is
+        assert_eq!(
+            frame.guide_bounds(GuideLines::Monitor, (4000.0, 1500.0)),
+            desktop
+        );
+        assert_eq!(frame.guide_bounds(GuideLines::Off, (100.0, 100.0)), None);
+    }
+}
@@ -1,11 +1,18 @@
 //! UI components for CursorHome
 
+mod cursor_label;
+mod frame_pacing;
 mod highlight_overlay;
+mod preview;
 mod settings_window;
+mod shape_geometry;
+mod shape_image;
+mod style_preview;
 mod tray_icon;
 mod x11_overlay;
 
 pub use highlight_overlay::HighlightOverlay;
+pub use preview::{render_preview, PreviewFormat, PreviewOptions};
 pub use settings_window::SettingsWindow;
-pub use tray_icon::TrayIcon;
+pub use tray_icon::{TrayCommand, TrayIcon, TrayState};
 pub use x11_overlay::X11Overlay;
@@ -0,0 +1,321 @@
+//! Highlight previews rendered without a display
+//!
+//! A style is drawn with the overlay's cairo renderer at evenly spaced points
+//! of its animation, and written as numbered PNG frames or one animated PNG.
+//! Nothing here needs a display, so previews can be rendered for docs and
+//! bug reports on any machine. The X11 overlay draws with the same renderer,
+//! so the golden images in `tests/golden/` cover what X11 users see too.
+
+use super::highlight_overlay::{Frame, HighlightOverlay};
+use super::shape_image::{ShapeImages, MAX_ANIMATION_SCALE};
+use crate::models::{AnimationStyle, AnimationType, CursorStyle, DesktopAppearance};
+use anyhow::{Context as _, Result};
+use gtk4::cairo::{Context, Format, ImageSurface};
+use serde::de::DeserializeOwned;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// Space around the largest extent of the highlight, in pixels
+const MARGIN: f64 = 8.0;
+
+/// Shortest delay between animated frames, in seconds
+const MIN_FRAME_DELAY: f64 = 0.01;
+
+/// How preview frames are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewFormat {
+    /// One PNG file per frame
+    Frames,
+    /// A single looping animated PNG
+    Animated,
+}
+
+/// What to render and where to write it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewOptions {
+    /// JSON file with a cursor style
+    pub style: PathBuf,
+    /// JSON file with an animation style; the default animation if not set
+    pub animation: Option<PathBuf>,
+    pub out_dir: PathBuf,
+    /// Frames per animation period
+    pub frames: u32,
+    pub format: PreviewFormat,
+}
+
+/// Render a preview as described by `options`, returning the files written
+pub fn render_preview(options: &PreviewOptions) -> Result<Vec<PathBuf>> {
+    let mut style: CursorStyle = read_json(&options.style)?;
+    let animation = match &options.animation {
+        Some(path) => read_json(path)?,
+        None => AnimationStyle::default(),
+    };
+
+    // Without a desktop to ask, accent paints use their own color
+    DesktopAppearance::default().apply_to(&mut style);
+
+    let preview = Preview::render(&style, &animation, options.frames)?;
+    fs::create_dir_all(&options.out_dir)
+        .with_context(|| format!("Failed to create {}", options.out_dir.display()))?;
+
+    match options.format {
+        PreviewFormat::Frames => preview
+            .frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                let path = options.out_dir.join(format!("frame-{:03}.png", i));
+                preview.write_png(&path, std::slice::from_ref(frame))?;
+                Ok(path)
+            })
+            .collect(),
+        PreviewFormat::Animated => {
+            let path = options.out_dir.join("preview.png");
+            preview.write_png(&path, &preview.frames)?;
+            Ok(vec![path])
+        }
+    }
+}
+
+/// Rendered frames of a highlight
+struct Preview {
+    /// Width and height of every frame
+    side: u32,
+    /// Seconds each frame is shown for
+    delay: f64,
+    /// Pixels of each frame as straight (not premultiplied) RGBA
+    frames: Vec<Vec<u8>>,
+}
+
+impl Preview {
+    /// Render `count` frames spread over one period of the animation
+    ///
+    /// A still highlight renders a single frame.
+    fn render(style: &CursorStyle, animation: &AnimationStyle, count: u32) -> Result<Self> {
+        let glow = if style.glow_enabled {
+            style.glow_radius * 2.0
+        } else {
+            0.0
+        };
+        let extent = style.outer_size() * MAX_ANIMATION_SCALE + glow + MARGIN * 2.0;
+        let side = extent.ceil() as i32;
+        let center = side as f64 / 2.0;
+
+        let cycling = style.color.is_cycling()
+            || style
+                .effective_layers()
+                .iter()
+                .any(|layer| layer.color.is_cycling());
+        let still = animation.animation_type == AnimationType::None && !cycling;
+        let count = if still { 1 } else { count.max(1) };
+        let period = animation.period();
+
+        // The preview stands in for a single monitor, which guide lines span
+        let monitors = vec![(0.0, 0.0, side as f64, side as f64)];
+        let mut images = ShapeImages::default();
+        let mut frames = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let elapsed = period * i as f64 / count as f64;
+            let progress = animation.progress_at(elapsed);
+            let frame = Frame::new(animation, progress, elapsed, monitors.clone());
+
+            let surface = ImageSurface::create(Format::ARgb32, side, side)?;
+            {
+                let cr = Context::new(&surface)?;
+                HighlightOverlay::draw_frame(
+                    &cr,
+                    (center, center),
+                    style,
+                    &frame,
+                    &mut images,
+                    1.0,
+                );
+            }
+            surface.flush();
+            frames.push(straight_rgba(&surface)?);
+        }
+
+        Ok(Self {
+            side: side as u32,
+            delay: (period / count as f64).max(MIN_FRAME_DELAY),
+            frames,
+        })
+    }
+
+    /// Write `frames` to `path`, as an animated PNG if there is more than one
+    fn write_png(&self, path: &Path, frames: &[Vec<u8>]) -> Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.side, self.side);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        if frames.len() > 1 {
+            // Loop forever, with the delay in milliseconds
+            encoder.set_animated(frames.len() as u32, 0)?;
+            encoder.set_frame_delay((self.delay * 1000.0).round() as u16, 1000)?;
+        }
+
+        let mut writer = encoder.write_header()?;
+        for frame in frames {
+            writer.write_image_data(frame)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Read a JSON file, filling in defaults for missing fields
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Pixels of a cairo surface as straight RGBA, row by row
+fn straight_rgba(surface: &ImageSurface) -> Result<Vec<u8>> {
+    let (width, height) = (surface.width() as usize, surface.height() as usize);
+    let stride = surface.stride() as usize;
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    surface.with_data(|data| {
+        for row in data.chunks(stride).take(height) {
+            for pixel in row[..width * 4].chunks_exact(4) {
+                // Cairo stores each pixel as a native-endian premultiplied ARGB value
+                let argb = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                let [a, r, g, b] = argb.to_be_bytes();
+                let unpremultiply = |c: u8| match a {
+                    0 => 0,
+                    a => ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8,
+                };
+                pixels.extend_from_slice(&[
+                    unpremultiply(r),
+                    unpremultiply(g),
+                    unpremultiply(b),
+                    a,
+                ]);
+            }
+        }
+    })?;
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frames rendered per golden case
+    const FRAMES: u32 = 4;
+
+    /// Largest difference allowed in any channel of a pixel, which leaves
+    /// room for antialiasing changes between cairo versions
+    const CHANNEL_TOLERANCE: u8 = 8;
+
+    /// Set to write the rendered frames as the new golden images
+    const BLESS_VAR: &str = "CURSORHOME_BLESS";
+
+    /// Decode a PNG into its size and RGBA pixels
+    fn read_png(path: &Path) -> (u32, u32, Vec<u8>) {
+        let file = File::open(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let mut reader = png::Decoder::new(file).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(info.color_type, png::ColorType::Rgba, "{}", path.display());
+        pixels.truncate(info.buffer_size());
+        (info.width, info.height, pixels)
+    }
+
+    /// Render `tests/golden/<case>` and compare each frame with its image
+    fn check_golden(case: &str) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(case);
+        let out_dir =
+            std::env::temp_dir().join(format!("cursorhome-golden-{}-{}", case, std::process::id()));
+        let options = PreviewOptions {
+            style: dir.join("style.json"),
+            animation: Some(dir.join("animation.json")),
+            out_dir: out_dir.clone(),
+            frames: FRAMES,
+            format: PreviewFormat::Frames,
+        };
+        let rendered = render_preview(&options).unwrap();
+        let bless = std::env::var_os(BLESS_VAR).is_some();
+
+        for path in &rendered {
+            let golden = dir.join(path.file_name().unwrap());
+            if bless {
+                fs::copy(path, &golden).unwrap();
+                continue;
+            }
+            assert!(
+                golden.exists(),
+                "{} is missing; run the tests with {}=1 to create it",
+                golden.display(),
+                BLESS_VAR
+            );
+
+            let (width, height, expected) = read_png(&golden);
+            let (actual_width, actual_height, actual) = read_png(path);
+            assert_eq!(
+                (actual_width, actual_height),
+                (width, height),
+                "{} changed size",
+                golden.display()
+            );
+            let worst = expected
+                .iter()
+                .zip(&actual)
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap_or(0);
+            assert!(
+                worst <= CHANNEL_TOLERANCE,
+                "{} differs by up to {} per channel; see {}",
+                golden.display(),
+                worst,
+                path.display()
+            );
+        }
+        fs::remove_dir_all(&out_dir).ok();
+    }
+
+    #[test]
+    fn golden_ring_pulse() {
+        check_golden("ring_pulse");
+    }
+
+    #[test]
+    fn golden_gradient_glow() {
+        check_golden("gradient_glow");
+    }
+
+    #[test]
+    fn golden_layered_spotlight() {
+        check_golden("layered_spotlight");
+    }
+
+    #[test]
+    fn golden_dashed_guides() {
+        check_golden("dashed_guides");
+    }
+
+    #[test]
+    fn golden_hue_starburst() {
+        check_golden("hue_starburst");
+    }
+
+    #[test]
+    fn still_highlight_renders_one_frame() {
+        let style = CursorStyle::default();
+        let animation = AnimationStyle {
+            animation_type: AnimationType::None,
+            ..AnimationStyle::default()
+        };
+        let preview = Preview::render(&style, &animation, FRAMES).unwrap();
+        assert_eq!(preview.frames.len(), 1);
+        assert_eq!(
+            preview.frames[0].len(),
+            (preview.side * preview.side * 4) as usize
+        );
+    }
+}
@@ -1,109 +1,562 @@
 //! Settings window UI
+//!
+//! A libadwaita preferences window with one page per area, searchable by
+//! row title. Every widget writes straight into the shared
+//! [`PreferencesStore`], so changes apply immediately and are saved without
+//! an explicit "Apply".
 
-use crate::models::{AnimationType, Preferences, Shape};
+use super::style_preview::StylePreview;
+use crate::models::{
+    AnimationType, BorderStyle, Color, DisplayProfile, Easing, GuideLines, ImageShape,
+    LabelPosition, Paint, Pattern, Preferences, PreferencesStore, Shape, SharedSettings,
+    ShortcutAction, StyleOverride, StylePreset, TapModifier, Trigger,
+};
+use crate::services::DisplayLayoutMonitor;
+use anyhow::Result;
 use gtk4::prelude::*;
 use gtk4::{
-    Box as GtkBox, CheckButton, ColorButton, ComboBoxText, Label,
-    Orientation, Scale, SpinButton,
+    gdk, gio, glib, Button, CheckButton, ColorButton, ComboBoxText, Entry, EventControllerKey,
+    FileChooserAction, FileChooserNative, FileFilter, FontButton, Label, ListBox, Orientation,
+    ResponseType, Scale, SpinButton,
 };
 use libadwaita as adw;
 use libadwaita::prelude::*;
-use std::sync::Arc;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Updates a widget to show the current preferences
+type Refresh = Box<dyn Fn(&Preferences)>;
 
 /// Settings window for CursorHome
 pub struct SettingsWindow;
 
 impl SettingsWindow {
     /// Show the settings window
-    pub fn show(preferences: Arc<Preferences>) {
-        let window = adw::Window::builder()
+    pub fn show(store: PreferencesStore) {
+        let window = adw::PreferencesWindow::builder()
             .title("CursorHome Settings")
-            .default_width(450)
-            .default_height(550)
+            .default_width(640)
+            .default_height(700)
+            .search_enabled(true)
             .build();
 
-        let content = GtkBox::new(Orientation::Vertical, 0);
+        // Widgets re-read the preferences when they change elsewhere
+        // (tray, shortcuts, or an edit to preferences.json)
+        let mut refresh: Vec<Refresh> = Vec::new();
 
-        // Header bar
-        let header = adw::HeaderBar::new();
-        content.append(&header);
+        // General page
+        let general = Self::create_page("General", "preferences-system-symbolic");
+        general.add(&Self::create_general_section(&store, &mut refresh));
+        general.add(&Self::create_shake_section(&store, &mut refresh));
+        general.add(&Self::create_tap_section(&store, &mut refresh));
+        general.add(&Self::create_notifications_section(&store, &mut refresh));
+        general.add(&Self::create_settings_file_section(&store));
+        window.add(&general);
 
-        // Main content with scrolling
-        let scroll = gtk4::ScrolledWindow::builder()
-            .hscrollbar_policy(gtk4::PolicyType::Never)
-            .vscrollbar_policy(gtk4::PolicyType::Automatic)
-            .build();
+        // Appearance page
+        let appearance = Self::create_page("Appearance", "applications-graphics-symbolic");
+        appearance.add(&Self::create_preview(&store));
+        appearance.add(&Self::create_appearance_section(&store, &mut refresh));
+        appearance.add(&Self::create_presets_section(&store, &mut refresh));
+        appearance.add(&Self::create_display_profiles_section(&store, &mut refresh));
+        appearance.add(&Self::create_trigger_styles_section(&store, &mut refresh));
+        window.add(&appearance);
 
-        let main_box = GtkBox::new(Orientation::Vertical, 24);
-        main_box.set_margin_start(24);
-        main_box.set_margin_end(24);
-        main_box.set_margin_top(24);
-        main_box.set_margin_bottom(24);
+        // Animation page
+        let animation = Self::create_page("Animation", "media-playback-start-symbolic");
+        animation.add(&Self::create_preview(&store));
+        animation.add(&Self::create_animation_section(&store, &mut refresh));
+        window.add(&animation);
 
-        // General section
-        main_box.append(&Self::create_general_section(&preferences));
+        // Magnifier page
+        let magnifier = Self::create_page("Magnifier", "zoom-in-symbolic");
+        magnifier.add(&Self::create_magnifier_section(&store, &mut refresh));
+        window.add(&magnifier);
 
-        // Appearance section
-        main_box.append(&Self::create_appearance_section(&preferences));
+        // Shortcuts page
+        let shortcuts = Self::create_page(
+            "Shortcuts",
+            "preferences-desktop-keyboard-shortcuts-symbolic",
+        );
+        shortcuts.add(&Self::create_shortcuts_section(&store, &mut refresh));
+        window.add(&shortcuts);
 
-        // Animation section
-        main_box.append(&Self::create_animation_section(&preferences));
+        // Synergy page
+        let synergy = Self::create_page("Synergy", "video-display-symbolic");
+        synergy.add(&Self::create_synergy_section(&store, &mut refresh));
+        synergy.add(&Self::create_transition_label_section(&store, &mut refresh));
+        window.add(&synergy);
 
-        // Shake detection section
-        main_box.append(&Self::create_shake_section(&preferences));
+        // Peers page
+        let peers = Self::create_page("Peers", "network-workgroup-symbolic");
+        peers.add(&Self::create_peers_section(&store, &mut refresh));
+        window.add(&peers);
 
-        scroll.set_child(Some(&main_box));
-        content.append(&scroll);
+        // Setting a widget to the value it already shows is a no-op for the
+        // store, so refreshing never feeds back into another change
+        let subscription = store.subscribe(move |_, new| {
+            for refresh in &refresh {
+                refresh(new);
+            }
+        });
+        window.connect_close_request(move |_| {
+            store.unsubscribe(subscription);
+            glib::Propagation::Proceed
+        });
 
-        window.set_content(Some(&content));
         window.present();
     }
 
-    fn create_section(title: &str) -> (GtkBox, GtkBox) {
-        let section = GtkBox::new(Orientation::Vertical, 12);
-
-        let title_label = Label::new(Some(title));
-        title_label.add_css_class("title-4");
-        title_label.set_halign(gtk4::Align::Start);
-        section.append(&title_label);
-
-        let content = GtkBox::new(Orientation::Vertical, 8);
-        content.add_css_class("card");
-        content.set_margin_start(0);
-        section.append(&content);
+    fn create_page(title: &str, icon_name: &str) -> adw::PreferencesPage {
+        adw::PreferencesPage::builder()
+            .title(title)
+            .icon_name(icon_name)
+            .build()
+    }
 
-        (section, content)
+    fn create_section(title: &str) -> adw::PreferencesGroup {
+        adw::PreferencesGroup::builder().title(title).build()
     }
 
-    fn create_general_section(preferences: &Arc<Preferences>) -> GtkBox {
-        let (section, content) = Self::create_section("General");
+    fn create_general_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("General");
+        let preferences = store.get();
 
         // Enabled toggle
         let enabled_row = Self::create_row("Enable CursorHome");
         let enabled_check = CheckButton::new();
         enabled_check.set_active(preferences.enabled);
-        enabled_row.append(&enabled_check);
-        content.append(&enabled_row);
+        let store_clone = store.clone();
+        enabled_check.connect_toggled(move |check| {
+            let enabled = check.is_active();
+            store_clone.update(|p| p.enabled = enabled);
+        });
+        let check = enabled_check.clone();
+        refresh.push(Box::new(move |p| check.set_active(p.enabled)));
+        Self::add_suffix(&enabled_row, &enabled_check);
+        section.add(&enabled_row);
 
         // Launch at login
         let launch_row = Self::create_row("Launch at login");
         let launch_check = CheckButton::new();
         launch_check.set_active(preferences.launch_at_login);
-        launch_row.append(&launch_check);
-        content.append(&launch_row);
+        let store_clone = store.clone();
+        launch_check.connect_toggled(move |check| {
+            let launch = check.is_active();
+            store_clone.update(|p| p.launch_at_login = launch);
+        });
+        let check = launch_check.clone();
+        refresh.push(Box::new(move |p| check.set_active(p.launch_at_login)));
+        Self::add_suffix(&launch_row, &launch_check);
+        section.add(&launch_row);
 
         // Highlight duration
         let duration_row = Self::create_row("Highlight duration (seconds)");
         let duration_spin = SpinButton::with_range(1.0, 30.0, 0.5);
         duration_spin.set_value(preferences.highlight_duration);
-        duration_row.append(&duration_spin);
-        content.append(&duration_row);
+        let store_clone = store.clone();
+        duration_spin.connect_value_changed(move |spin| {
+            let duration = spin.value();
+            store_clone.update(|p| p.highlight_duration = duration);
+        });
+        let spin = duration_spin.clone();
+        refresh.push(Box::new(move |p| spin.set_value(p.highlight_duration)));
+        Self::add_suffix(&duration_row, &duration_spin);
+        section.add(&duration_row);
+
+        // Frame statistics on the overlay
+        let stats_row = Self::create_row("Show frame statistics (X11)");
+        let stats_check = CheckButton::new();
+        stats_check.set_active(preferences.show_frame_stats);
+        stats_check.set_tooltip_text(Some(
+            "Draw the frame rate and frame times next to the highlight, to diagnose stutter",
+        ));
+        let store_clone = store.clone();
+        stats_check.connect_toggled(move |check| {
+            let show = check.is_active();
+            store_clone.update(|p| p.show_frame_stats = show);
+        });
+        let check = stats_check.clone();
+        refresh.push(Box::new(move |p| check.set_active(p.show_frame_stats)));
+        Self::add_suffix(&stats_row, &stats_check);
+        section.add(&stats_row);
+
+        section
+    }
+
+    fn create_presets_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Style Presets");
+
+        // Preset selector
+        let preset_row = Self::create_row("Preset");
+        let preset_combo = ComboBoxText::new();
+        store.with(|p| Self::fill_presets(&preset_combo, p));
+        let apply_button = Button::with_label("Apply");
+        Self::add_suffix(&preset_row, &preset_combo);
+        Self::add_suffix(&preset_row, &apply_button);
+        section.add(&preset_row);
+
+        // Name of the selected preset, also used for new presets
+        let name_row = Self::create_row("Name");
+        let name_entry = Entry::new();
+        let rename_button = Button::with_label("Rename");
+        Self::add_suffix(&name_row, &name_entry);
+        Self::add_suffix(&name_row, &rename_button);
+        section.add(&name_row);
+
+        let actions_row = Self::create_row("Manage presets");
+        let new_button = Button::with_label("New from Current Style");
+        let duplicate_button = Button::with_label("Duplicate");
+        let delete_button = Button::with_label("Delete");
+        delete_button.add_css_class("destructive-action");
+        Self::add_suffix(&actions_row, &new_button);
+        Self::add_suffix(&actions_row, &duplicate_button);
+        Self::add_suffix(&actions_row, &delete_button);
+        section.add(&actions_row);
+
+        let error_label = Label::new(None);
+        error_label.add_css_class("error");
+        error_label.set_halign(gtk4::Align::Start);
+        error_label.set_margin_start(12);
+        error_label.set_margin_bottom(8);
+        error_label.set_visible(false);
+        section.add(&error_label);
+
+        let selected = {
+            let combo = preset_combo.clone();
+            move || combo.active().map(|i| i as usize)
+        };
+
+        // Show the selected preset's name for renaming
+        let store_clone = store.clone();
+        let entry = name_entry.clone();
+        preset_combo.connect_changed(move |combo| {
+            let index = combo.active().map(|i| i as usize);
+            let name = store_clone.with(|p| {
+                index
+                    .and_then(|i| p.presets.get(i))
+                    .map(|preset| preset.name.clone())
+            });
+            entry.set_text(name.as_deref().unwrap_or(""));
+        });
+        preset_combo.set_active(store.with(|p| p.active_preset()).map(|i| i as u32));
+
+        let store_clone = store.clone();
+        let selected_clone = selected.clone();
+        apply_button.connect_clicked(move |_| {
+            if let Some(index) = selected_clone() {
+                store_clone.update(|p| {
+                    p.apply_preset(index);
+                });
+            }
+        });
+
+        let store_clone = store.clone();
+        let selected_clone = selected.clone();
+        let entry = name_entry.clone();
+        let error = error_label.clone();
+        rename_button.connect_clicked(move |_| {
+            let Some(index) = selected_clone() else {
+                return;
+            };
+            let name = entry.text().trim().to_string();
+            let problem = store_clone.with(|p| {
+                if name.is_empty() {
+                    Some("Preset names cannot be empty".to_string())
+                } else if p.preset_index(&name).is_some_and(|other| other != index) {
+                    Some(format!("A preset named \"{}\" already exists", name))
+                } else {
+                    None
+                }
+            });
+
+            error.set_visible(problem.is_some());
+            match problem {
+                Some(problem) => error.set_text(&problem),
+                None => store_clone.update(|p| p.rename_preset(index, name)),
+            }
+        });
+
+        let store_clone = store.clone();
+        let entry = name_entry.clone();
+        let combo = preset_combo.clone();
+        new_button.connect_clicked(move |_| {
+            let base = entry.text().trim().to_string();
+            let base = if base.is_empty() { "Custom" } else { &base };
+            store_clone.update(|p| {
+                let name = p.unique_preset_name(base);
+                let preset = StylePreset::from_preferences(&name, p);
+                p.presets.push(preset);
+            });
+            let count = store_clone.with(|p| p.presets.len());
+            combo.set_active(Some(count as u32 - 1));
+        });
+
+        let store_clone = store.clone();
+        let selected_clone = selected.clone();
+        let combo = preset_combo.clone();
+        duplicate_button.connect_clicked(move |_| {
+            let Some(index) = selected_clone() else {
+                return;
+            };
+            store_clone.update(|p| {
+                let mut copy = p.presets[index].clone();
+                copy.name = p.unique_preset_name(&format!("{} Copy", copy.name));
+                p.presets.insert(index + 1, copy);
+            });
+            combo.set_active(Some(index as u32 + 1));
+        });
+
+        let store_clone = store.clone();
+        delete_button.connect_clicked(move |_| {
+            if let Some(index) = selected() {
+                store_clone.update(|p| p.delete_preset(index));
+            }
+        });
+
+        // Rebuild the list when presets change, keeping the selection
+        let combo = preset_combo.clone();
+        let shown = RefCell::new(store.with(|p| p.presets.clone()));
+        refresh.push(Box::new(move |p| {
+            if *shown.borrow() == p.presets {
+                return;
+            }
+            *shown.borrow_mut() = p.presets.clone();
+
+            let previous = combo.active();
+            Self::fill_presets(&combo, p);
+            let count = p.presets.len() as u32;
+            combo.set_active(previous.filter(|_| count > 0).map(|i| i.min(count - 1)));
+        }));
+
+        section.set_description(Some(
+            "Presets can also be switched from the tray menu, a shortcut, or \
+             `cursorhome preset <name>`.",
+        ));
 
         section
     }
 
-    fn create_appearance_section(preferences: &Arc<Preferences>) -> GtkBox {
-        let (section, content) = Self::create_section("Appearance");
+    fn create_display_profiles_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Monitor Profiles");
+        let layout = DisplayLayoutMonitor::current_fingerprint();
+
+        let layout_row = Self::create_row("Current monitors");
+        layout_row.set_subtitle(&DisplayProfile::describe_layout(&layout));
+        section.add(&layout_row);
+
+        // Preset for the current layout; the first entry turns switching off
+        let preset_row = Self::create_row("Preset for these monitors");
+        let preset_combo = ComboBoxText::new();
+        store.with(|p| Self::fill_layout_presets(&preset_combo, p, &layout));
+        let store_clone = store.clone();
+        let layout_clone = layout.clone();
+        preset_combo.connect_changed(move |combo| {
+            let Some(active) = combo.active() else {
+                return;
+            };
+            let preset = match active {
+                0 => None,
+                _ => combo.active_text().map(|name| name.to_string()),
+            };
+            store_clone.update(|p| p.set_preset_for_layout(&layout_clone, preset));
+        });
+        Self::add_suffix(&preset_row, &preset_combo);
+        section.add(&preset_row);
+
+        // Profiles saved for other layouts, each removable
+        let others = ListBox::new();
+        others.add_css_class("boxed-list");
+        others.set_selection_mode(gtk4::SelectionMode::None);
+        others.set_margin_top(12);
+        section.add(&others);
+        store.with(|p| Self::fill_other_profiles(&others, store, p, &layout));
+
+        let store_clone = store.clone();
+        let shown = RefCell::new(store.with(|p| (p.presets.clone(), p.display_profiles.clone())));
+        refresh.push(Box::new(move |p| {
+            let current = (p.presets.clone(), p.display_profiles.clone());
+            if *shown.borrow() == current {
+                return;
+            }
+            *shown.borrow_mut() = current;
+
+            Self::fill_layout_presets(&preset_combo, p, &layout);
+            Self::fill_other_profiles(&others, &store_clone, p, &layout);
+        }));
+
+        section.set_description(Some(
+            "The preset is applied whenever exactly these monitors, at these \
+             resolutions, are connected.",
+        ));
+
+        section
+    }
+
+    fn fill_layout_presets(combo: &ComboBoxText, preferences: &Preferences, layout: &str) {
+        combo.remove_all();
+        combo.append_text("Don't switch");
+        Self::fill_presets_into(combo, preferences);
+
+        let selected = preferences
+            .preset_for_layout(layout)
+            .and_then(|name| preferences.presets.iter().position(|p| p.name == name))
+            .map_or(0, |index| index as u32 + 1);
+        combo.set_active(Some(selected));
+    }
+
+    fn fill_other_profiles(
+        container: &ListBox,
+        store: &PreferencesStore,
+        preferences: &Preferences,
+        layout: &str,
+    ) {
+        while let Some(child) = container.first_child() {
+            container.remove(&child);
+        }
+
+        for profile in &preferences.display_profiles {
+            if profile.layout == layout {
+                continue;
+            }
+
+            let row = Self::create_row(&format!(
+                "{} → {}",
+                DisplayProfile::describe_layout(&profile.layout),
+                profile.preset
+            ));
+            let remove_button = Button::with_label("Remove");
+            let store = store.clone();
+            let profile_layout = profile.layout.clone();
+            remove_button.connect_clicked(move |_| {
+                store.update(|p| p.set_preset_for_layout(&profile_layout, None));
+            });
+            Self::add_suffix(&row, &remove_button);
+            container.append(&row);
+        }
+        container.set_visible(container.first_child().is_some());
+    }
+
+    fn create_trigger_styles_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Trigger Styles");
+
+        // Peer requests are set on the Peers page
+        for &trigger in Trigger::all() {
+            if trigger != Trigger::Peer {
+                section.add(&Self::create_trigger_row(store, trigger, refresh));
+            }
+        }
+
+        section.set_description(Some(
+            "Highlights from these triggers use the chosen preset instead of \
+             the style above. The preset is copied, so later edits to it are \
+             not picked up.",
+        ));
+
+        section
+    }
+
+    /// Row choosing the preset for highlights from `trigger`; the first
+    /// entry uses the default style
+    fn create_trigger_row(
+        store: &PreferencesStore,
+        trigger: Trigger,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::ActionRow {
+        let row = Self::create_row(trigger.display_name());
+        let combo = ComboBoxText::new();
+        store.with(|p| Self::fill_trigger_presets(&combo, p, trigger));
+        let store_clone = store.clone();
+        combo.connect_changed(move |combo| {
+            let Some(active) = combo.active() else {
+                return;
+            };
+            store_clone.update(|p| {
+                let overrides = match active {
+                    0 => StyleOverride::default(),
+                    _ => match p.presets.get(active as usize - 1) {
+                        Some(preset) => StyleOverride::from_preset(preset),
+                        // "Custom" keeps the override from preferences.json
+                        None => return,
+                    },
+                };
+                *p.trigger_styles.get_mut(trigger) = overrides;
+            });
+        });
+
+        let combo_clone = combo.clone();
+        let shown = RefCell::new(
+            store.with(|p| (p.presets.clone(), p.trigger_styles.get(trigger).clone())),
+        );
+        refresh.push(Box::new(move |p| {
+            let current = (p.presets.clone(), p.trigger_styles.get(trigger).clone());
+            if *shown.borrow() == current {
+                return;
+            }
+            *shown.borrow_mut() = current;
+
+            Self::fill_trigger_presets(&combo_clone, p, trigger);
+        }));
+
+        Self::add_suffix(&row, &combo);
+        row
+    }
+
+    fn fill_trigger_presets(combo: &ComboBoxText, preferences: &Preferences, trigger: Trigger) {
+        combo.remove_all();
+        combo.append_text("Default style");
+        Self::fill_presets_into(combo, preferences);
+
+        let overrides = preferences.trigger_styles.get(trigger);
+        let selected = if overrides.is_empty() {
+            0
+        } else {
+            match preferences
+                .presets
+                .iter()
+                .position(|preset| overrides.matches_preset(preset))
+            {
+                Some(index) => index as u32 + 1,
+                None => {
+                    // Overrides edited by hand that match no preset
+                    combo.append_text("Custom");
+                    preferences.presets.len() as u32 + 1
+                }
+            }
+        };
+        combo.set_active(Some(selected));
+    }
+
+    fn fill_presets(combo: &ComboBoxText, preferences: &Preferences) {
+        combo.remove_all();
+        Self::fill_presets_into(combo, preferences);
+    }
+
+    fn fill_presets_into(combo: &ComboBoxText, preferences: &Preferences) {
+        for preset in &preferences.presets {
+            combo.append_text(&preset.name);
+        }
+    }
+
+    fn create_appearance_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Appearance");
+        let preferences = store.get();
 
         // Shape selector
         let shape_row = Self::create_row("Shape");
@@ -111,49 +564,429 @@ impl SettingsWindow {
         for shape in Shape::all() {
             shape_combo.append_text(shape.display_name());
         }
-        shape_combo.set_active(Some(match preferences.cursor_style.shape {
-            Shape::Circle => 0,
-            Shape::Ring => 1,
-            Shape::Crosshair => 2,
-            Shape::Spotlight => 3,
+        shape_combo.append_text("Image…");
+        shape_combo.set_active(Self::shape_index(&preferences.cursor_style.shape));
+        let store_clone = store.clone();
+        shape_combo.connect_changed(move |combo| {
+            let Some(index) = combo.active() else {
+                return;
+            };
+            match Shape::all().get(index as usize) {
+                Some(shape) => {
+                    let shape = shape.clone();
+                    store_clone.update(|p| p.cursor_style.shape = shape);
+                }
+                None if matches!(store_clone.get().cursor_style.shape, Shape::Image(_)) => {}
+                None => {
+                    // Go back to the current shape if no file is picked
+                    let combo_clone = combo.clone();
+                    let store = store_clone.clone();
+                    Self::choose_shape_image(combo, &store_clone, move || {
+                        combo_clone.set_active(Self::shape_index(&store.get().cursor_style.shape))
+                    });
+                }
+            }
+        });
+        let combo = shape_combo.clone();
+        refresh.push(Box::new(move |p| {
+            combo.set_active(Self::shape_index(&p.cursor_style.shape))
         }));
-        shape_row.append(&shape_combo);
-        content.append(&shape_row);
+        Self::add_suffix(&shape_row, &shape_combo);
+        section.add(&shape_row);
+
+        // Image file, for image shapes
+        let image_row = Self::create_row("Image file");
+        let image_button = Button::with_label("Choose…");
+        let store_clone = store.clone();
+        image_button.connect_clicked(move |button| {
+            Self::choose_shape_image(button, &store_clone, || {});
+        });
+        let tint_check = CheckButton::with_label("Tint with color");
+        tint_check.set_tooltip_text(Some(
+            "Fill the image with the highlight color, keeping its transparency",
+        ));
+        let store_clone = store.clone();
+        tint_check.connect_toggled(move |check| {
+            let tint = check.is_active();
+            store_clone.update(|p| {
+                if let Shape::Image(image) = &mut p.cursor_style.shape {
+                    image.tint = tint;
+                }
+            });
+        });
+        let show_image = {
+            let (row, button, check) =
+                (image_row.clone(), image_button.clone(), tint_check.clone());
+            move |p: &Preferences| {
+                let image = match &p.cursor_style.shape {
+                    Shape::Image(image) => Some(image),
+                    _ => None,
+                };
+                row.set_visible(image.is_some());
+                if let Some(image) = image {
+                    let name = image.path.file_name().unwrap_or(image.path.as_os_str());
+                    button.set_label(&name.to_string_lossy());
+                    button.set_tooltip_text(Some(&image.path.to_string_lossy()));
+                    check.set_active(image.tint);
+                }
+            }
+        };
+        show_image(&preferences);
+        refresh.push(Box::new(show_image));
+        Self::add_suffix(&image_row, &image_button);
+        Self::add_suffix(&image_row, &tint_check);
+        section.add(&image_row);
 
         // Size slider
         let size_row = Self::create_row("Size");
         let size_scale = Scale::with_range(Orientation::Horizontal, 20.0, 200.0, 5.0);
         size_scale.set_value(preferences.cursor_style.size);
         size_scale.set_hexpand(true);
-        size_row.append(&size_scale);
-        content.append(&size_row);
+        let store_clone = store.clone();
+        size_scale.connect_value_changed(move |scale| {
+            let size = scale.value();
+            store_clone.update(|p| p.cursor_style.size = size);
+        });
+        let scale = size_scale.clone();
+        refresh.push(Box::new(move |p| scale.set_value(p.cursor_style.size)));
+        Self::add_suffix(&size_row, &size_scale);
+        section.add(&size_row);
 
         // Color picker
         let color_row = Self::create_row("Color");
         let color_button = ColorButton::new();
-        color_button.set_rgba(&preferences.cursor_style.color.to_gdk_rgba());
-        color_row.append(&color_button);
-        content.append(&color_row);
+        color_button.set_rgba(&preferences.cursor_style.color.base_color().to_gdk_rgba());
+        let store_clone = store.clone();
+        color_button.connect_color_set(move |button| {
+            let color = Color::from_gdk_rgba(&button.rgba());
+            store_clone.update(|p| {
+                let source = Self::color_source_index(&p.cursor_style.color).unwrap_or(0);
+                p.cursor_style.color = Self::color_from_source(source, color);
+            });
+        });
+        let button = color_button.clone();
+        refresh.push(Box::new(move |p| {
+            button.set_rgba(&p.cursor_style.color.base_color().to_gdk_rgba())
+        }));
+        Self::add_suffix(&color_row, &color_button);
+        section.add(&color_row);
+
+        // Where the color comes from
+        let source_row = Self::create_row("Color source");
+        let source_combo = ComboBoxText::new();
+        source_combo.append_text("Chosen color");
+        source_combo.append_text("Desktop accent color");
+        source_combo.append_text("Contrast with background");
+        source_combo.set_active(Self::color_source_index(&preferences.cursor_style.color));
+        source_combo.set_tooltip_text(Some(
+            "The desktop accent color needs the XDG desktop portal; the chosen color \
+             is used without one. Contrasting with the background works on X11 only.",
+        ));
+        let store_clone = store.clone();
+        source_combo.connect_changed(move |combo| {
+            let Some(source) = combo.active() else {
+                return;
+            };
+            store_clone.update(|p| {
+                if Self::color_source_index(&p.cursor_style.color) == Some(source) {
+                    return;
+                }
+                let color = p.cursor_style.color.base_color();
+                p.cursor_style.color = Self::color_from_source(source, color);
+            });
+        });
+        let combo = source_combo.clone();
+        refresh.push(Box::new(move |p| {
+            combo.set_active(Self::color_source_index(&p.cursor_style.color))
+        }));
+        Self::add_suffix(&source_row, &source_combo);
+        section.add(&source_row);
 
         // Border weight
         let border_row = Self::create_row("Border weight");
         let border_spin = SpinButton::with_range(1.0, 10.0, 1.0);
         border_spin.set_value(preferences.cursor_style.border_weight);
-        border_row.append(&border_spin);
-        content.append(&border_row);
+        let store_clone = store.clone();
+        border_spin.connect_value_changed(move |spin| {
+            let weight = spin.value();
+            store_clone.update(|p| p.cursor_style.border_weight = weight);
+        });
+        let spin = border_spin.clone();
+        refresh.push(Box::new(move |p| {
+            spin.set_value(p.cursor_style.border_weight)
+        }));
+        Self::add_suffix(&border_row, &border_spin);
+        section.add(&border_row);
+
+        // Border style
+        let border_style_row = Self::create_row("Border style");
+        let border_style_combo = ComboBoxText::new();
+        for border_style in BorderStyle::all() {
+            border_style_combo.append_text(border_style.display_name());
+        }
+        border_style_combo.set_active(Self::border_style_index(
+            preferences.cursor_style.border_style,
+        ));
+        let store_clone = store.clone();
+        border_style_combo.connect_changed(move |combo| {
+            let Some(border_style) = combo
+                .active()
+                .and_then(|i| BorderStyle::all().get(i as usize).copied())
+            else {
+                return;
+            };
+            store_clone.update(|p| p.cursor_style.border_style = border_style);
+        });
+        let combo = border_style_combo.clone();
+        refresh.push(Box::new(move |p| {
+            combo.set_active(Self::border_style_index(p.cursor_style.border_style))
+        }));
+        Self::add_suffix(&border_style_row, &border_style_combo);
+        section.add(&border_style_row);
 
         // Glow effect
         let glow_row = Self::create_row("Glow effect");
         let glow_check = CheckButton::new();
         glow_check.set_active(preferences.cursor_style.glow_enabled);
-        glow_row.append(&glow_check);
-        content.append(&glow_row);
+        let store_clone = store.clone();
+        glow_check.connect_toggled(move |check| {
+            let glow = check.is_active();
+            store_clone.update(|p| p.cursor_style.glow_enabled = glow);
+        });
+        Self::add_suffix(&glow_row, &glow_check);
+        section.add(&glow_row);
+
+        // Glow intensity
+        let intensity_row = Self::create_row("Glow intensity");
+        let intensity_scale = Scale::with_range(Orientation::Horizontal, 0.0, 1.0, 0.05);
+        intensity_scale.set_value(preferences.cursor_style.glow_intensity);
+        intensity_scale.set_hexpand(true);
+        let store_clone = store.clone();
+        intensity_scale.connect_value_changed(move |scale| {
+            let intensity = scale.value();
+            store_clone.update(|p| p.cursor_style.glow_intensity = intensity);
+        });
+        Self::add_suffix(&intensity_row, &intensity_scale);
+        section.add(&intensity_row);
+
+        // Glow radius
+        let radius_row = Self::create_row("Glow radius");
+        let radius_scale = Scale::with_range(Orientation::Horizontal, 0.0, 50.0, 1.0);
+        radius_scale.set_value(preferences.cursor_style.glow_radius);
+        radius_scale.set_hexpand(true);
+        let store_clone = store.clone();
+        radius_scale.connect_value_changed(move |scale| {
+            let radius = scale.value();
+            store_clone.update(|p| p.cursor_style.glow_radius = radius);
+        });
+        Self::add_suffix(&radius_row, &radius_scale);
+        section.add(&radius_row);
+
+        // The intensity and radius only matter with the glow on
+        let show_glow = {
+            let (check, intensity, radius) = (
+                glow_check.clone(),
+                intensity_scale.clone(),
+                radius_scale.clone(),
+            );
+            let (intensity_row, radius_row) = (intensity_row.clone(), radius_row.clone());
+            move |p: &Preferences| {
+                let style = &p.cursor_style;
+                check.set_active(style.glow_enabled);
+                intensity.set_value(style.glow_intensity);
+                radius.set_value(style.glow_radius);
+                intensity_row.set_sensitive(style.glow_enabled);
+                radius_row.set_sensitive(style.glow_enabled);
+            }
+        };
+        show_glow(&preferences);
+        refresh.push(Box::new(show_glow));
+
+        // Guide lines
+        let guide_row = Self::create_row("Guide lines");
+        let guide_combo = ComboBoxText::new();
+        for guide_lines in GuideLines::all() {
+            guide_combo.append_text(guide_lines.display_name());
+        }
+        guide_combo.set_active(Self::guide_lines_index(
+            preferences.cursor_style.guide_lines,
+        ));
+        guide_combo.set_tooltip_text(Some(
+            "Draw lines through the cursor to the edges of the screen, \
+             in the highlight's color and border weight",
+        ));
+        let store_clone = store.clone();
+        guide_combo.connect_changed(move |combo| {
+            let Some(guide_lines) = combo
+                .active()
+                .and_then(|i| GuideLines::all().get(i as usize).copied())
+            else {
+                return;
+            };
+            store_clone.update(|p| p.cursor_style.guide_lines = guide_lines);
+        });
+        let combo = guide_combo.clone();
+        refresh.push(Box::new(move |p| {
+            combo.set_active(Self::guide_lines_index(p.cursor_style.guide_lines))
+        }));
+        Self::add_suffix(&guide_row, &guide_combo);
+        section.add(&guide_row);
+
+        // Layers and paints can only be edited in preferences.json for now
+        Self::show_style_hint(&section, &preferences);
+        let group = section.clone();
+        refresh.push(Box::new(move |p| Self::show_style_hint(&group, p)));
 
         section
     }
 
-    fn create_animation_section(preferences: &Arc<Preferences>) -> GtkBox {
-        let (section, content) = Self::create_section("Animation");
+    fn show_style_hint(group: &adw::PreferencesGroup, preferences: &Preferences) {
+        let style = &preferences.cursor_style;
+        let mut notes = Vec::new();
+        if !style.layers.is_empty() {
+            notes.push(format!(
+                "This style is drawn as {} layers from preferences.json. The \
+                 shape, size and border weight above are not used, and the \
+                 color only tints the glow.",
+                style.layers.len()
+            ));
+        }
+        if let Paint::Pattern(
+            Pattern::LinearGradient { .. }
+            | Pattern::RadialGradient { .. }
+            | Pattern::HueCycle { .. },
+        ) = style.color
+        {
+            notes.push(
+                "The color is a gradient or color cycle from preferences.json; \
+                 picking a color replaces it."
+                    .to_string(),
+            );
+        }
+        let description = notes.join(" ");
+        group.set_description((!notes.is_empty()).then_some(description.as_str()));
+    }
+
+    /// Position of a shape in the shape list, where "Image…" follows the
+    /// built-in shapes
+    fn shape_index(shape: &Shape) -> Option<u32> {
+        match shape {
+            Shape::Image(_) => Some(Shape::all().len() as u32),
+            shape => Shape::all()
+                .iter()
+                .position(|s| s == shape)
+                .map(|i| i as u32),
+        }
+    }
+
+    /// Ask for a PNG or SVG file and use it as the highlight shape, calling
+    /// `on_done` once the dialog closes
+    fn choose_shape_image(
+        widget: &impl IsA<gtk4::Widget>,
+        store: &PreferencesStore,
+        on_done: impl Fn() + 'static,
+    ) {
+        let parent = widget.root().and_downcast::<gtk4::Window>();
+        let chooser = FileChooserNative::new(
+            Some("Choose Highlight Image"),
+            parent.as_ref(),
+            FileChooserAction::Open,
+            Some("_Open"),
+            Some("_Cancel"),
+        );
+        let filter = FileFilter::new();
+        filter.set_name(Some("PNG and SVG images"));
+        filter.add_mime_type("image/png");
+        filter.add_mime_type("image/svg+xml");
+        chooser.add_filter(&filter);
+
+        // Native dialogs are not kept alive by GTK, so hold a reference
+        // until the dialog answers
+        let keep_alive = RefCell::new(Some(chooser.clone()));
+        let store = store.clone();
+        chooser.connect_response(move |chooser, response| {
+            if response == ResponseType::Accept {
+                if let Some(path) = chooser.file().and_then(|file| file.path()) {
+                    store.update(|p| {
+                        let tint =
+                            matches!(&p.cursor_style.shape, Shape::Image(image) if image.tint);
+                        p.cursor_style.shape = Shape::Image(ImageShape { path, tint });
+                    });
+                }
+            }
+            on_done();
+            keep_alive.borrow_mut().take();
+        });
+        chooser.show();
+    }
+
+    fn border_style_index(border_style: BorderStyle) -> Option<u32> {
+        BorderStyle::all()
+            .iter()
+            .position(|b| *b == border_style)
+            .map(|i| i as u32)
+    }
+
+    fn guide_lines_index(guide_lines: GuideLines) -> Option<u32> {
+        GuideLines::all()
+            .iter()
+            .position(|g| *g == guide_lines)
+            .map(|i| i as u32)
+    }
+
+    fn label_position_index(position: LabelPosition) -> Option<u32> {
+        LabelPosition::all()
+            .iter()
+            .position(|p| *p == position)
+            .map(|i| i as u32)
+    }
+
+    /// Position of a paint in the color source list; gradients and color
+    /// cycles are not listed
+    fn color_source_index(paint: &Paint) -> Option<u32> {
+        match paint {
+            Paint::Solid(_) => Some(0),
+            Paint::Pattern(Pattern::Accent { .. }) => Some(1),
+            Paint::Pattern(Pattern::Adaptive { .. }) => Some(2),
+            Paint::Pattern(_) => None,
+        }
+    }
+
+    fn color_from_source(source: u32, color: Color) -> Paint {
+        match source {
+            1 => Paint::Pattern(Pattern::Accent { color }),
+            2 => Paint::Pattern(Pattern::Adaptive { color }),
+            _ => Paint::Solid(color),
+        }
+    }
+
+    fn easing_index(easing: Easing) -> Option<u32> {
+        Easing::all()
+            .iter()
+            .position(|e| *e == easing)
+            .map(|i| i as u32)
+    }
+
+    fn animation_index(animation_type: AnimationType) -> Option<u32> {
+        AnimationType::all()
+            .iter()
+            .position(|a| *a == animation_type)
+            .map(|i| i as u32)
+    }
+
+    fn modifier_index(modifier: TapModifier) -> Option<u32> {
+        TapModifier::all()
+            .iter()
+            .position(|m| *m == modifier)
+            .map(|i| i as u32)
+    }
+
+    fn create_animation_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Animation");
+        let preferences = store.get();
 
         // Animation type
         let type_row = Self::create_row("Animation type");
@@ -161,43 +994,165 @@ impl SettingsWindow {
         for anim in AnimationType::all() {
             type_combo.append_text(anim.display_name());
         }
-        type_combo.set_active(Some(match preferences.animation_style.animation_type {
-            AnimationType::None => 0,
-            AnimationType::Pulse => 1,
-            AnimationType::Ripple => 2,
-            AnimationType::Fade => 3,
-            AnimationType::Scale => 4,
+        type_combo.set_active(Self::animation_index(
+            preferences.animation_style.animation_type,
+        ));
+        let store_clone = store.clone();
+        type_combo.connect_changed(move |combo| {
+            let Some(animation_type) = combo
+                .active()
+                .and_then(|i| AnimationType::all().get(i as usize).copied())
+            else {
+                return;
+            };
+            store_clone.update(|p| p.animation_style.animation_type = animation_type);
+        });
+        let combo = type_combo.clone();
+        refresh.push(Box::new(move |p| {
+            combo.set_active(Self::animation_index(p.animation_style.animation_type))
         }));
-        type_row.append(&type_combo);
-        content.append(&type_row);
+        Self::add_suffix(&type_row, &type_combo);
+        section.add(&type_row);
 
         // Duration
         let duration_row = Self::create_row("Animation duration");
-        let duration_scale = Scale::with_range(Orientation::Horizontal, 0.1, 2.0, 0.1);
+        let duration_scale = Scale::with_range(Orientation::Horizontal, 0.1, 5.0, 0.1);
         duration_scale.set_value(preferences.animation_style.duration);
         duration_scale.set_hexpand(true);
-        duration_row.append(&duration_scale);
-        content.append(&duration_row);
+        let store_clone = store.clone();
+        duration_scale.connect_value_changed(move |scale| {
+            let duration = scale.value();
+            store_clone.update(|p| p.animation_style.duration = duration);
+        });
+        let scale = duration_scale.clone();
+        refresh.push(Box::new(move |p| {
+            scale.set_value(p.animation_style.duration)
+        }));
+        Self::add_suffix(&duration_row, &duration_scale);
+        section.add(&duration_row);
+
+        // Easing
+        let easing_row = Self::create_row("Easing");
+        let easing_combo = ComboBoxText::new();
+        for easing in Easing::all() {
+            easing_combo.append_text(easing.display_name());
+        }
+        easing_combo.set_active(Self::easing_index(preferences.animation_style.easing));
+        let store_clone = store.clone();
+        easing_combo.connect_changed(move |combo| {
+            let Some(easing) = combo
+                .active()
+                .and_then(|i| Easing::all().get(i as usize).copied())
+            else {
+                return;
+            };
+            store_clone.update(|p| p.animation_style.easing = easing);
+        });
+        let combo = easing_combo.clone();
+        refresh.push(Box::new(move |p| {
+            combo.set_active(Self::easing_index(p.animation_style.easing))
+        }));
+        Self::add_suffix(&easing_row, &easing_combo);
+        section.add(&easing_row);
 
         // Repeat count
         let repeat_row = Self::create_row("Repeat count (0 = infinite)");
         let repeat_spin = SpinButton::with_range(0.0, 10.0, 1.0);
         repeat_spin.set_value(preferences.animation_style.repeat_count as f64);
-        repeat_row.append(&repeat_spin);
-        content.append(&repeat_row);
+        let store_clone = store.clone();
+        repeat_spin.connect_value_changed(move |spin| {
+            let repeat_count = spin.value_as_int() as u32;
+            store_clone.update(|p| p.animation_style.repeat_count = repeat_count);
+        });
+        let spin = repeat_spin.clone();
+        refresh.push(Box::new(move |p| {
+            spin.set_value(p.animation_style.repeat_count as f64)
+        }));
+        Self::add_suffix(&repeat_row, &repeat_spin);
+        section.add(&repeat_row);
+
+        // Auto-reverse
+        let reverse_row = Self::create_row("Play each cycle back in reverse");
+        let reverse_check = CheckButton::new();
+        reverse_check.set_active(preferences.animation_style.auto_reverse);
+        let store_clone = store.clone();
+        reverse_check.connect_toggled(move |check| {
+            let auto_reverse = check.is_active();
+            store_clone.update(|p| p.animation_style.auto_reverse = auto_reverse);
+        });
+        let check = reverse_check.clone();
+        refresh.push(Box::new(move |p| {
+            check.set_active(p.animation_style.auto_reverse)
+        }));
+        Self::add_suffix(&reverse_row, &reverse_check);
+        section.add(&reverse_row);
+
+        // Desktop accessibility settings
+        let accessibility_row = Self::create_row("Follow reduced motion and high contrast");
+        let accessibility_check = CheckButton::new();
+        accessibility_check.set_active(preferences.follow_desktop_accessibility);
+        accessibility_check.set_tooltip_text(Some(
+            "When the desktop asks for reduced motion the highlight is shown without \
+             animation, and with a high-contrast theme it is drawn in yellow on black.",
+        ));
+        let store_clone = store.clone();
+        accessibility_check.connect_toggled(move |check| {
+            let follow = check.is_active();
+            store_clone.update(|p| p.follow_desktop_accessibility = follow);
+        });
+        let check = accessibility_check.clone();
+        refresh.push(Box::new(move |p| {
+            check.set_active(p.follow_desktop_accessibility)
+        }));
+        Self::add_suffix(&accessibility_row, &accessibility_check);
+        section.add(&accessibility_row);
+
+        section
+    }
+
+    /// A live preview of the highlight, with a button showing it for real
+    fn create_preview(store: &PreferencesStore) -> adw::PreferencesGroup {
+        let section = Self::create_section("Preview");
+
+        let preview = StylePreview::new(store);
+        preview.add_css_class("card");
+        section.add(&preview);
+
+        let test_button = Button::with_label("Test on Screen");
+        test_button.add_css_class("flat");
+        test_button.set_tooltip_text(Some(
+            "Highlight the cursor with the current settings, even while CursorHome is disabled",
+        ));
+        test_button.connect_clicked(|_| {
+            if let Some(app) = gio::Application::default() {
+                app.activate_action("test-highlight", None);
+            }
+        });
+        section.set_header_suffix(Some(&test_button));
 
         section
     }
 
-    fn create_shake_section(preferences: &Arc<Preferences>) -> GtkBox {
-        let (section, content) = Self::create_section("Shake Detection");
+    fn create_shake_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Shake Detection");
+        let preferences = store.get();
 
         // Enable shake
         let enabled_row = Self::create_row("Shake to find cursor");
         let enabled_check = CheckButton::new();
         enabled_check.set_active(preferences.shake_enabled);
-        enabled_row.append(&enabled_check);
-        content.append(&enabled_row);
+        let store_clone = store.clone();
+        enabled_check.connect_toggled(move |check| {
+            let enabled = check.is_active();
+            store_clone.update(|p| p.shake_enabled = enabled);
+        });
+        let check = enabled_check.clone();
+        refresh.push(Box::new(move |p| check.set_active(p.shake_enabled)));
+        Self::add_suffix(&enabled_row, &enabled_check);
+        section.add(&enabled_row);
 
         // Sensitivity
         let sensitivity_row = Self::create_row("Sensitivity");
@@ -210,24 +1165,579 @@ impl SettingsWindow {
         sensitivity_scale.add_mark(0.5, gtk4::PositionType::Bottom, Some("Medium"));
         sensitivity_scale.add_mark(1.0, gtk4::PositionType::Bottom, Some("High"));
 
-        sensitivity_row.append(&sensitivity_scale);
-        content.append(&sensitivity_row);
+        let store_clone = store.clone();
+        sensitivity_scale.connect_value_changed(move |scale| {
+            let sensitivity = scale.value();
+            store_clone.update(|p| p.shake_sensitivity = sensitivity);
+        });
+        let scale = sensitivity_scale.clone();
+        refresh.push(Box::new(move |p| scale.set_value(p.shake_sensitivity)));
+
+        Self::add_suffix(&sensitivity_row, &sensitivity_scale);
+        section.add(&sensitivity_row);
 
         section
     }
 
-    fn create_row(label: &str) -> GtkBox {
-        let row = GtkBox::new(Orientation::Horizontal, 12);
-        row.set_margin_start(12);
-        row.set_margin_end(12);
-        row.set_margin_top(8);
-        row.set_margin_bottom(8);
+    fn create_tap_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Double-Tap Modifier");
+        let tap_trigger = store.with(|p| p.tap_trigger.clone());
 
-        let label_widget = Label::new(Some(label));
-        label_widget.set_hexpand(true);
-        label_widget.set_halign(gtk4::Align::Start);
-        row.append(&label_widget);
+        // Enable trigger
+        let enabled_row = Self::create_row("Double-tap a modifier to find cursor (X11)");
+        let enabled_check = CheckButton::new();
+        enabled_check.set_active(tap_trigger.enabled);
+        let store_clone = store.clone();
+        enabled_check.connect_toggled(move |check| {
+            let enabled = check.is_active();
+            store_clone.update(|p| p.tap_trigger.enabled = enabled);
+        });
+        let check = enabled_check.clone();
+        refresh.push(Box::new(move |p| check.set_active(p.tap_trigger.enabled)));
+        Self::add_suffix(&enabled_row, &enabled_check);
+        section.add(&enabled_row);
 
-        row
+        // Modifier selector
+        let modifier_row = Self::create_row("Modifier key");
+        let modifier_combo = ComboBoxText::new();
+        for modifier in TapModifier::all() {
+            modifier_combo.append_text(modifier.display_name());
+        }
+        modifier_combo.set_active(Self::modifier_index(tap_trigger.modifier));
+        let store_clone = store.clone();
+        modifier_combo.connect_changed(move |combo| {
+            let Some(modifier) = combo
+                .active()
+                .and_then(|i| TapModifier::all().get(i as usize).copied())
+            else {
+                return;
+            };
+            store_clone.update(|p| p.tap_trigger.modifier = modifier);
+        });
+        let combo = modifier_combo.clone();
+        refresh.push(Box::new(move |p| {
+            combo.set_active(Self::modifier_index(p.tap_trigger.modifier))
+        }));
+        Self::add_suffix(&modifier_row, &modifier_combo);
+        section.add(&modifier_row);
+
+        // Interval
+        let interval_row = Self::create_row("Double-tap interval (seconds)");
+        let interval_spin = SpinButton::with_range(0.1, 1.0, 0.05);
+        interval_spin.set_digits(2);
+        interval_spin.set_value(tap_trigger.interval);
+        let store_clone = store.clone();
+        interval_spin.connect_value_changed(move |spin| {
+            let interval = spin.value();
+            store_clone.update(|p| p.tap_trigger.interval = interval);
+        });
+        let spin = interval_spin.clone();
+        refresh.push(Box::new(move |p| spin.set_value(p.tap_trigger.interval)));
+        Self::add_suffix(&interval_row, &interval_spin);
+        section.add(&interval_row);
+
+        section
+    }
+
+    fn create_synergy_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Synergy");
+
+        // Highlight on return
+        let enabled_row = Self::create_row("Highlight when the cursor returns");
+        let enabled_check = CheckButton::new();
+        enabled_check.set_active(store.with(|p| p.synergy_enabled));
+        let store_clone = store.clone();
+        enabled_check.connect_toggled(move |check| {
+            let enabled = check.is_active();
+            store_clone.update(|p| p.synergy_enabled = enabled);
+        });
+        let check = enabled_check.clone();
+        refresh.push(Box::new(move |p| check.set_active(p.synergy_enabled)));
+        Self::add_suffix(&enabled_row, &enabled_check);
+        section.add(&enabled_row);
+
+        section
+    }
+
+    fn create_transition_label_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Return Label");
+
+        // Label beside the cursor on return
+        let label_row = Self::create_row("Show a label when the cursor returns");
+        let label_check = CheckButton::new();
+        label_check.set_active(store.with(|p| p.transition_label.enabled));
+        label_check.set_tooltip_text(Some(
+            "Names this machine and the one the cursor came back from",
+        ));
+        let store_clone = store.clone();
+        label_check.connect_toggled(move |check| {
+            let enabled = check.is_active();
+            store_clone.update(|p| p.transition_label.enabled = enabled);
+        });
+        let check = label_check.clone();
+        refresh.push(Box::new(move |p| {
+            check.set_active(p.transition_label.enabled)
+        }));
+        Self::add_suffix(&label_row, &label_check);
+        section.add(&label_row);
+
+        // Label font
+        let font_row = Self::create_row("Label font");
+        let font_button = FontButton::new();
+        font_button.set_font(&store.with(|p| p.transition_label.font.clone()));
+        let store_clone = store.clone();
+        font_button.connect_font_set(move |button| {
+            if let Some(font) = button.font() {
+                store_clone.update(|p| p.transition_label.font = font.to_string());
+            }
+        });
+        let button = font_button.clone();
+        refresh.push(Box::new(move |p| button.set_font(&p.transition_label.font)));
+        Self::add_suffix(&font_row, &font_button);
+        section.add(&font_row);
+
+        // Label position
+        let position_row = Self::create_row("Label position");
+        let position_combo = ComboBoxText::new();
+        for position in LabelPosition::all() {
+            position_combo.append_text(position.display_name());
+        }
+        position_combo.set_active(Self::label_position_index(
+            store.with(|p| p.transition_label.position),
+        ));
+        let store_clone = store.clone();
+        position_combo.connect_changed(move |combo| {
+            if let Some(position) = combo
+                .active()
+                .and_then(|i| LabelPosition::all().get(i as usize))
+            {
+                store_clone.update(|p| p.transition_label.position = *position);
+            }
+        });
+        let combo = position_combo.clone();
+        refresh.push(Box::new(move |p| {
+            combo.set_active(Self::label_position_index(p.transition_label.position))
+        }));
+        Self::add_suffix(&position_row, &position_combo);
+        section.add(&position_row);
+
+        // Label text and background colors
+        let colors_row = Self::create_row("Label colors");
+        let text_button = ColorButton::new();
+        text_button.set_use_alpha(true);
+        text_button.set_tooltip_text(Some("Text"));
+        text_button.set_rgba(&store.with(|p| p.transition_label.text_color.to_gdk_rgba()));
+        let store_clone = store.clone();
+        text_button.connect_color_set(move |button| {
+            let color = Color::from_gdk_rgba(&button.rgba());
+            store_clone.update(|p| p.transition_label.text_color = color);
+        });
+        let background_button = ColorButton::new();
+        background_button.set_use_alpha(true);
+        background_button.set_tooltip_text(Some("Background"));
+        background_button.set_rgba(&store.with(|p| p.transition_label.background.to_gdk_rgba()));
+        let store_clone = store.clone();
+        background_button.connect_color_set(move |button| {
+            let color = Color::from_gdk_rgba(&button.rgba());
+            store_clone.update(|p| p.transition_label.background = color);
+        });
+        let (text, background) = (text_button.clone(), background_button.clone());
+        refresh.push(Box::new(move |p| {
+            text.set_rgba(&p.transition_label.text_color.to_gdk_rgba());
+            background.set_rgba(&p.transition_label.background.to_gdk_rgba());
+        }));
+        Self::add_suffix(&colors_row, &text_button);
+        Self::add_suffix(&colors_row, &background_button);
+        section.add(&colors_row);
+
+        section
+    }
+
+    fn create_magnifier_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Magnifier");
+        let preferences = store.get();
+
+        // Zoom
+        let zoom_row = Self::create_row("Zoom factor");
+        let zoom_scale = Scale::with_range(Orientation::Horizontal, 1.0, 10.0, 0.5);
+        zoom_scale.set_value(preferences.magnifier_zoom);
+        zoom_scale.set_hexpand(true);
+        let store_clone = store.clone();
+        zoom_scale.connect_value_changed(move |scale| {
+            let zoom = scale.value();
+            store_clone.update(|p| p.magnifier_zoom = zoom);
+        });
+        let scale = zoom_scale.clone();
+        refresh.push(Box::new(move |p| scale.set_value(p.magnifier_zoom)));
+        Self::add_suffix(&zoom_row, &zoom_scale);
+        section.add(&zoom_row);
+
+        // Size
+        let size_row = Self::create_row("Size");
+        let size_scale = Scale::with_range(Orientation::Horizontal, 50.0, 500.0, 25.0);
+        size_scale.set_value(preferences.magnifier_size);
+        size_scale.set_hexpand(true);
+        let store_clone = store.clone();
+        size_scale.connect_value_changed(move |scale| {
+            let size = scale.value();
+            store_clone.update(|p| p.magnifier_size = size);
+        });
+        let scale = size_scale.clone();
+        refresh.push(Box::new(move |p| scale.set_value(p.magnifier_size)));
+        Self::add_suffix(&size_row, &size_scale);
+        section.add(&size_row);
+
+        section.set_description(Some(
+            "How far the magnifier zooms in and how large its window is.",
+        ));
+
+        section
+    }
+
+    fn create_peers_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Peer Requests");
+
+        section.add(&Self::create_trigger_row(store, Trigger::Peer, refresh));
+
+        let test_row = Self::create_row("Highlight as a peer would");
+        let test_button = Button::with_label("Test");
+        test_button.connect_clicked(|_| {
+            if let Some(app) = gio::Application::default() {
+                app.activate_action("find-cursor-from", Some(&Trigger::Peer.id().to_variant()));
+            }
+        });
+        Self::add_suffix(&test_row, &test_button);
+        section.add(&test_row);
+
+        section.set_description(Some(
+            "Other CursorHome machines ask for a highlight through the \
+             find-cursor-from action with the \"peer\" trigger. Discovering \
+             peers on the network is not supported on Linux yet.",
+        ));
+
+        section
+    }
+
+    fn create_settings_file_section(store: &PreferencesStore) -> adw::PreferencesGroup {
+        let section = Self::create_section("Settings File");
+
+        // Outcome of the last export or import
+        let status_label = Label::new(None);
+        status_label.set_halign(gtk4::Align::Start);
+        status_label.set_margin_top(8);
+        status_label.set_wrap(true);
+        status_label.set_visible(false);
+        let show_status = {
+            let label = status_label.clone();
+            move |result: Result<String>| {
+                match result {
+                    Ok(message) => {
+                        label.remove_css_class("error");
+                        label.set_text(&message);
+                    }
+                    Err(e) => {
+                        label.add_css_class("error");
+                        label.set_text(&format!("{:#}", e));
+                    }
+                }
+                label.set_visible(true);
+            }
+        };
+
+        let export_row = Self::create_row("Export settings");
+        let export_button = Button::with_label("Export…");
+        let store_clone = store.clone();
+        let show = show_status.clone();
+        export_button.connect_clicked(move |button| {
+            let store = store_clone.clone();
+            let show = show.clone();
+            Self::choose_settings_file(button, FileChooserAction::Save, move |path| {
+                let settings = store.with(SharedSettings::from_preferences);
+                show(
+                    settings
+                        .write(&path)
+                        .map(|()| format!("Exported settings to {}", path.display())),
+                );
+            });
+        });
+        Self::add_suffix(&export_row, &export_button);
+        section.add(&export_row);
+
+        let import_row = Self::create_row("Import settings");
+        let import_button = Button::with_label("Import…");
+        let store_clone = store.clone();
+        import_button.connect_clicked(move |button| {
+            let store = store_clone.clone();
+            let show = show_status.clone();
+            Self::choose_settings_file(button, FileChooserAction::Open, move |path| {
+                show(SharedSettings::read(&path).map(|settings| {
+                    let mut corrections = Vec::new();
+                    store.update(|p| corrections = settings.apply_to(p));
+                    let mut message = format!("Imported settings from {}", path.display());
+                    if !corrections.is_empty() {
+                        let corrected: Vec<String> =
+                            corrections.iter().map(|c| c.to_string()).collect();
+                        message += &format!("; out of range, corrected {}", corrected.join(", "));
+                    }
+                    message
+                }));
+            });
+        });
+        Self::add_suffix(&import_row, &import_button);
+        section.add(&import_row);
+
+        section.add(&status_label);
+
+        section.set_description(Some(
+            "The file holds the style, animation and behavior settings, in the \
+             format of the shared/models schemas.",
+        ));
+
+        section
+    }
+
+    /// Ask for a JSON file to export to or import from, calling `on_chosen`
+    /// with its path
+    fn choose_settings_file(
+        widget: &impl IsA<gtk4::Widget>,
+        action: FileChooserAction,
+        on_chosen: impl Fn(PathBuf) + 'static,
+    ) {
+        let parent = widget.root().and_downcast::<gtk4::Window>();
+        let (title, accept) = match action {
+            FileChooserAction::Save => ("Export Settings", "_Export"),
+            _ => ("Import Settings", "_Import"),
+        };
+        let chooser = FileChooserNative::new(
+            Some(title),
+            parent.as_ref(),
+            action,
+            Some(accept),
+            Some("_Cancel"),
+        );
+        let filter = FileFilter::new();
+        filter.set_name(Some("JSON files"));
+        filter.add_mime_type("application/json");
+        chooser.add_filter(&filter);
+        if action == FileChooserAction::Save {
+            chooser.set_current_name("cursorhome-settings.json");
+        }
+
+        // Native dialogs are not kept alive by GTK, so hold a reference
+        // until the dialog answers
+        let keep_alive = RefCell::new(Some(chooser.clone()));
+        chooser.connect_response(move |chooser, response| {
+            if response == ResponseType::Accept {
+                if let Some(path) = chooser.file().and_then(|file| file.path()) {
+                    on_chosen(path);
+                }
+            }
+            keep_alive.borrow_mut().take();
+        });
+        chooser.show();
+    }
+
+    fn create_notifications_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Notifications");
+
+        // Synergy transitions, with the other machine's name
+        let transitions_row = Self::create_row("When the cursor moves between machines");
+        let transitions_check = CheckButton::new();
+        transitions_check.set_active(store.with(|p| p.notifications.transitions));
+        let store_clone = store.clone();
+        transitions_check.connect_toggled(move |check| {
+            let enabled = check.is_active();
+            store_clone.update(|p| p.notifications.transitions = enabled);
+        });
+        let check = transitions_check.clone();
+        refresh.push(Box::new(move |p| {
+            check.set_active(p.notifications.transitions)
+        }));
+        Self::add_suffix(&transitions_row, &transitions_check);
+        section.add(&transitions_row);
+
+        // Synergy log not found or removed
+        let log_row = Self::create_row("When the Synergy log is missing");
+        let log_check = CheckButton::new();
+        log_check.set_active(store.with(|p| p.notifications.synergy_log));
+        let store_clone = store.clone();
+        log_check.connect_toggled(move |check| {
+            let enabled = check.is_active();
+            store_clone.update(|p| p.notifications.synergy_log = enabled);
+        });
+        let check = log_check.clone();
+        refresh.push(Box::new(move |p| {
+            check.set_active(p.notifications.synergy_log)
+        }));
+        Self::add_suffix(&log_row, &log_check);
+        section.add(&log_row);
+
+        // Overlay failures
+        let overlay_row = Self::create_row("When the highlight cannot be shown");
+        let overlay_check = CheckButton::new();
+        overlay_check.set_active(store.with(|p| p.notifications.overlay_errors));
+        let store_clone = store.clone();
+        overlay_check.connect_toggled(move |check| {
+            let enabled = check.is_active();
+            store_clone.update(|p| p.notifications.overlay_errors = enabled);
+        });
+        let check = overlay_check.clone();
+        refresh.push(Box::new(move |p| {
+            check.set_active(p.notifications.overlay_errors)
+        }));
+        Self::add_suffix(&overlay_row, &overlay_check);
+        section.add(&overlay_row);
+
+        section
+    }
+
+    fn create_shortcuts_section(
+        store: &PreferencesStore,
+        refresh: &mut Vec<Refresh>,
+    ) -> adw::PreferencesGroup {
+        let section = Self::create_section("Shortcuts");
+
+        // Explains why a recorded shortcut was rejected
+        let error_label = Label::new(None);
+        error_label.add_css_class("error");
+        error_label.set_halign(gtk4::Align::Start);
+        error_label.set_margin_start(12);
+        error_label.set_margin_bottom(8);
+        error_label.set_visible(false);
+
+        for action in ShortcutAction::all() {
+            let row = Self::create_row(action.display_name());
+            let recorder =
+                Self::create_shortcut_recorder(*action, store.clone(), error_label.clone());
+            let button = recorder.clone();
+            let action = *action;
+            refresh.push(Box::new(move |p| {
+                button.set_label(&Self::shortcut_label(p.shortcuts.get(action)))
+            }));
+            Self::add_suffix(&row, &recorder);
+            section.add(&row);
+        }
+        section.add(&error_label);
+
+        section.set_description(Some(
+            "Click a shortcut, then press the new key combination. \
+             Backspace disables the shortcut, Escape cancels.",
+        ));
+
+        section
+    }
+
+    /// Button that shows a binding and records a new one when clicked
+    fn create_shortcut_recorder(
+        action: ShortcutAction,
+        store: PreferencesStore,
+        error_label: Label,
+    ) -> Button {
+        let current_label = move |store: &PreferencesStore| {
+            store.with(|p| Self::shortcut_label(p.shortcuts.get(action)))
+        };
+        let button = Button::with_label(&current_label(&store));
+        let recording = Rc::new(Cell::new(false));
+
+        let recording_clone = recording.clone();
+        button.connect_clicked(move |button| {
+            recording_clone.set(true);
+            button.set_label("Press keys…");
+            button.grab_focus();
+        });
+
+        let controller = EventControllerKey::new();
+        let button_weak = button.downgrade();
+        controller.connect_key_pressed(move |_, keyval, _, state| {
+            let Some(button) = button_weak.upgrade() else {
+                return glib::Propagation::Proceed;
+            };
+            if !recording.get() {
+                return glib::Propagation::Proceed;
+            }
+
+            let modifiers = state & gtk4::accelerator_get_default_mod_mask();
+
+            let accelerator = match keyval {
+                gdk::Key::Escape if modifiers.is_empty() => {
+                    recording.set(false);
+                    button.set_label(&current_label(&store));
+                    return glib::Propagation::Stop;
+                }
+                gdk::Key::BackSpace if modifiers.is_empty() => None,
+                _ => {
+                    // Keep recording until a non-modifier key is pressed
+                    if !gtk4::accelerator_valid(keyval, modifiers) {
+                        return glib::Propagation::Stop;
+                    }
+                    Some(gtk4::accelerator_name(keyval, modifiers).to_string())
+                }
+            };
+
+            recording.set(false);
+
+            if let Some(accelerator) = &accelerator {
+                let problem = if modifiers.is_empty() {
+                    Some("Shortcuts need at least one modifier key".to_string())
+                } else {
+                    store
+                        .with(|p| p.shortcuts.conflict(action, accelerator))
+                        .map(|other| format!("Already used by \"{}\"", other))
+                };
+
+                if let Some(problem) = problem {
+                    let label = gtk4::accelerator_get_label(keyval, modifiers);
+                    error_label.set_text(&format!("{}: {}", label, problem));
+                    error_label.set_visible(true);
+                    button.set_label(&current_label(&store));
+                    return glib::Propagation::Stop;
+                }
+            }
+
+            error_label.set_visible(false);
+            store.update(|p| p.shortcuts.set(action, accelerator));
+            button.set_label(&current_label(&store));
+
+            glib::Propagation::Stop
+        });
+        button.add_controller(controller);
+
+        button
+    }
+
+    /// Human-readable label for an accelerator, e.g. "Shift+Ctrl+F"
+    fn shortcut_label(accelerator: Option<&str>) -> String {
+        accelerator
+            .and_then(gtk4::accelerator_parse)
+            .map(|(key, modifiers)| gtk4::accelerator_get_label(key, modifiers).to_string())
+            .unwrap_or_else(|| "Disabled".to_string())
+    }
+
+    fn create_row(label: &str) -> adw::ActionRow {
+        adw::ActionRow::builder().title(label).build()
+    }
+
+    /// Put `widget` at the end of `row`, centered vertically
+    fn add_suffix(row: &adw::ActionRow, widget: &impl IsA<gtk4::Widget>) {
+        widget.set_valign(gtk4::Align::Center);
+        row.add_suffix(widget);
     }
 }
@@ -0,0 +1,281 @@
+//! Geometry of the shapes built from lines and polygons
+//!
+//! Shared by the X11 and GTK renderers so both draw the same shapes.
+//! Coordinates are in screen pixels.
+
+/// A point on screen
+pub type Point = (f64, f64);
+
+/// Fraction of the radius left empty at the center of a reticle
+const RETICLE_GAP: f64 = 0.35;
+
+/// Number of rays in a star burst
+const STAR_RAYS: u32 = 12;
+
+/// Where star burst rays start, as a fraction of the radius
+const STAR_INNER: f64 = 0.3;
+
+/// Length of every other (short) star burst ray, as a fraction of the radius
+const STAR_SHORT: f64 = 0.65;
+
+/// Corner radius of a rounded square, as a fraction of its side
+const ROUNDED_CORNER: f64 = 0.2;
+
+/// The four tick marks of a reticle, from the center gap out to `radius`
+pub fn reticle_ticks(cx: f64, cy: f64, radius: f64) -> [(Point, Point); 4] {
+    let gap = radius * RETICLE_GAP;
+    [
+        ((cx - radius, cy), (cx - gap, cy)),
+        ((cx + gap, cy), (cx + radius, cy)),
+        ((cx, cy - radius), (cx, cy - gap)),
+        ((cx, cy + gap), (cx, cy + radius)),
+    ]
+}
+
+/// The corner arcs of a rounded square of `size` stroked `line_width` wide
+///
+/// Returns the centers of the arcs clockwise from the top right, and their
+/// radius. Thick lines on small squares shrink the corners so the sides
+/// never cross.
+pub fn rounded_square(cx: f64, cy: f64, size: f64, line_width: f64) -> ([Point; 4], f64) {
+    let half = (size / 2.0 - line_width / 2.0).max(0.0);
+    let corner = (size * ROUNDED_CORNER).min(half);
+    let (left, top) = (cx - half + corner, cy - half + corner);
+    let (right, bottom) = (cx + half - corner, cy + half - corner);
+    (
+        [(right, top), (right, bottom), (left, bottom), (left, top)],
+        corner,
+    )
+}
+
+/// The rays of a star burst, alternating long and short, with the
+/// direction of each as a unit vector for sampling paints
+pub fn star_rays(cx: f64, cy: f64, radius: f64) -> Vec<((Point, Point), Point)> {
+    (0..STAR_RAYS)
+        .map(|i| {
+            let angle = i as f64 / STAR_RAYS as f64 * std::f64::consts::TAU;
+            let (sin, cos) = angle.sin_cos();
+            let length = if i % 2 == 0 { 1.0 } else { STAR_SHORT };
+            let start = (
+                cx + cos * radius * STAR_INNER,
+                cy + sin * radius * STAR_INNER,
+            );
+            let end = (cx + cos * radius * length, cy + sin * radius * length);
+            ((start, end), (cos, sin))
+        })
+        .collect()
+}
+
+/// An arrow from a monitor's center toward the cursor
+pub struct Arrow {
+    pub shaft: (Point, Point),
+    /// Tip first, then the two back corners
+    pub head: [Point; 3],
+    /// Direction of the arrow as a unit vector
+    pub direction: Point,
+}
+
+/// An arrow from `from` that stops at the edge of a highlight of `size`
+/// around `to`
+///
+/// Returns `None` when the cursor is too close to `from` for an arrow to
+/// fit, e.g. right at a monitor's center.
+pub fn pointer_arrow(from: Point, to: Point, size: f64, line_width: f64) -> Option<Arrow> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let distance = dx.hypot(dy);
+    let head_length = (size * 0.25).max(line_width * 3.0);
+    let length = distance - size / 2.0;
+    if length < head_length * 2.0 {
+        return None;
+    }
+
+    let (ux, uy) = (dx / distance, dy / distance);
+    let along = |d: f64| (from.0 + ux * d, from.1 + uy * d);
+    let tip = along(length);
+    let (bx, by) = along(length - head_length);
+    let half_width = head_length / 2.0;
+
+    Some(Arrow {
+        shaft: (from, (bx, by)),
+        head: [
+            tip,
+            (bx - uy * half_width, by + ux * half_width),
+            (bx + uy * half_width, by - ux * half_width),
+        ],
+        direction: (ux, uy),
+    })
+}
+
+/// A rectangle as x, y, width and height
+pub type Rect = (f64, f64, f64, f64);
+
+/// Center of a rectangle
+pub fn center((x, y, width, height): Rect) -> Point {
+    (x + width / 2.0, y + height / 2.0)
+}
+
+/// The first of `rects` containing `point`
+pub fn rect_at(rects: &[Rect], (px, py): Point) -> Option<Rect> {
+    rects
+        .iter()
+        .copied()
+        .find(|&(x, y, width, height)| px >= x && px < x + width && py >= y && py < y + height)
+}
+
+/// Horizontal and vertical lines through the cursor to the edges of
+/// `bounds`, stopping `gap` short of the cursor so the highlight stays clear
+pub fn guide_lines((cx, cy): Point, (x, y, width, height): Rect, gap: f64) -> Vec<(Point, Point)> {
+    [
+        ((x, cy), (cx - gap, cy)),
+        ((cx + gap, cy), (x + width, cy)),
+        ((cx, y), (cx, cy - gap)),
+        ((cx, cy + gap), (cx, y + height)),
+    ]
+    .into_iter()
+    .filter(|&(start, end)| end.0 > start.0 || end.1 > start.1)
+    .collect()
+}
+
+/// Smallest rectangle containing all of `rects`
+pub fn bounding_box(rects: &[Rect]) -> Option<Rect> {
+    let (first, rest) = rects.split_first()?;
+    let (mut left, mut top) = (first.0, first.1);
+    let (mut right, mut bottom) = (first.0 + first.2, first.1 + first.3);
+    for &(x, y, width, height) in rest {
+        left = left.min(x);
+        top = top.min(y);
+        right = right.max(x + width);
+        bottom = bottom.max(y + height);
+    }
+    Some((left, top, right - left, bottom - top))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether two points are within rounding error of each other
+    fn near(a: Point, b: Point) -> bool {
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    }
+
+    /// Distance between two points
+    fn distance(a: Point, b: Point) -> f64 {
+        (b.0 - a.0).hypot(b.1 - a.1)
+    }
+
+    #[test]
+    fn reticle_ticks_run_from_the_gap_to_the_radius() {
+        let ticks = reticle_ticks(100.0, 50.0, 20.0);
+        assert_eq!(
+            ticks,
+            [
+                ((80.0, 50.0), (93.0, 50.0)),
+                ((107.0, 50.0), (120.0, 50.0)),
+                ((100.0, 30.0), (100.0, 43.0)),
+                ((100.0, 57.0), (100.0, 70.0)),
+            ]
+        );
+        for (start, end) in ticks {
+            assert!((distance(start, end) - 20.0 * (1.0 - RETICLE_GAP)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn rounded_square_corners_sit_inside_the_stroke() {
+        let (centers, corner) = rounded_square(100.0, 100.0, 60.0, 4.0);
+        assert_eq!(corner, 12.0);
+        // The stroke is centered 28px out, so the arcs' centers are a corner in
+        assert_eq!(
+            centers,
+            [(116.0, 84.0), (116.0, 116.0), (84.0, 116.0), (84.0, 84.0)]
+        );
+    }
+
+    #[test]
+    fn rounded_square_sides_never_cross() {
+        // A thick outline around the smallest square
+        let (centers, corner) = rounded_square(0.0, 0.0, 26.0, 16.0);
+        assert_eq!(corner, 5.0);
+        for (x, y) in centers {
+            assert_eq!((x, y), (0.0, 0.0));
+        }
+
+        // Lines wider than the square leave a point rather than inverting
+        let (centers, corner) = rounded_square(10.0, 20.0, 20.0, 30.0);
+        assert_eq!(corner, 0.0);
+        assert!(centers.iter().all(|&c| c == (10.0, 20.0)));
+    }
+
+    #[test]
+    fn star_rays_alternate_long_and_short() {
+        let rays = star_rays(50.0, 50.0, 40.0);
+        assert_eq!(rays.len(), STAR_RAYS as usize);
+
+        for (i, ((start, end), direction)) in rays.into_iter().enumerate() {
+            let length = if i % 2 == 0 { 1.0 } else { STAR_SHORT };
+            assert!((distance((50.0, 50.0), start) - 40.0 * STAR_INNER).abs() < 1e-9);
+            assert!((distance((50.0, 50.0), end) - 40.0 * length).abs() < 1e-9);
+            assert!((direction.0.hypot(direction.1) - 1.0).abs() < 1e-9);
+
+            // Each ray points along its direction
+            let along = (end.0 - start.0, end.1 - start.1);
+            let ray_length = along.0.hypot(along.1);
+            assert!(near(
+                (along.0 / ray_length, along.1 / ray_length),
+                direction
+            ));
+        }
+    }
+
+    #[test]
+    fn star_rays_start_pointing_right() {
+        let rays = star_rays(0.0, 0.0, 10.0);
+        assert!(near(rays[0].0 .1, (10.0, 0.0)));
+        assert!(near(rays[0].1, (1.0, 0.0)));
+        // A quarter of the way round points down, as screen y grows downward
+        assert!(near(rays[3].1, (0.0, 1.0)));
+    }
+
+    #[test]
+    fn pointer_arrow_stops_at_the_highlight_edge() {
+        let arrow = pointer_arrow((0.0, 0.0), (300.0, 400.0), 60.0, 4.0).unwrap();
+        assert!(near(arrow.direction, (0.6, 0.8)));
+        assert_eq!(arrow.shaft.0, (0.0, 0.0));
+
+        // The tip is `size / 2` short of the cursor, and the head 15px long
+        let [tip, left, right] = arrow.head;
+        assert!((distance(tip, (300.0, 400.0)) - 30.0).abs() < 1e-9);
+        assert!((distance(arrow.shaft.1, tip) - 15.0).abs() < 1e-9);
+        assert!(near(
+            ((left.0 + right.0) / 2.0, (left.1 + right.1) / 2.0),
+            arrow.shaft.1
+        ));
+        assert!((distance(left, right) - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pointer_arrow_head_grows_with_thick_lines() {
+        let arrow = pointer_arrow((0.0, 0.0), (500.0, 0.0), 40.0, 8.0).unwrap();
+        // 3 line widths is longer than a quarter of the highlight
+        assert!(near(arrow.head[0], (480.0, 0.0)));
+        assert!(near(arrow.shaft.1, (456.0, 0.0)));
+        assert!(near(arrow.head[1], (456.0, 12.0)));
+        assert!(near(arrow.head[2], (456.0, -12.0)));
+    }
+
+    #[test]
+    fn no_pointer_arrow_with_the_cursor_at_the_monitor_center() {
+        let center = center((0.0, 0.0, 1920.0, 1080.0));
+        assert!(pointer_arrow(center, center, 60.0, 4.0).is_none());
+        // Nor inside the highlight, where no arrow fits
+        assert!(pointer_arrow(center, (center.0 + 30.0, center.1), 60.0, 4.0).is_none());
+    }
+
+    #[test]
+    fn no_pointer_arrow_shorter_than_two_heads() {
+        // 30px to the highlight edge, heads of 15px
+        assert!(pointer_arrow((0.0, 0.0), (0.0, 59.9), 60.0, 4.0).is_none());
+        assert!(pointer_arrow((0.0, 0.0), (0.0, 60.0), 60.0, 4.0).is_some());
+    }
+}
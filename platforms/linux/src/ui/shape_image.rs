@@ -0,0 +1,45 @@
+//! Pictures used as highlight shapes
+//!
+//! Images are loaded with gdk-pixbuf, which reads PNG and, with the librsvg
+//! loader installed, SVG files.
+
+use crate::models::ImageShape;
+use gtk4::gdk_pixbuf::Pixbuf;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Largest size multiplier an animation reaches (ripple)
+pub const MAX_ANIMATION_SCALE: f64 = 1.5;
+
+/// Images rasterized for one highlight, so files are read once rather than
+/// every frame
+#[derive(Default)]
+pub struct ShapeImages {
+    /// Rasterized images by path and pixel size; `None` if loading failed
+    images: HashMap<(PathBuf, i32), Option<Pixbuf>>,
+}
+
+impl ShapeImages {
+    /// The image fitted into a square `pixels` wide
+    ///
+    /// The picture is rasterized large enough for any animation scale, so
+    /// renderers only ever scale it down. Returns `None` if the file can't
+    /// be read, after logging why once.
+    pub fn get(&mut self, image: &ImageShape, pixels: f64) -> Option<&Pixbuf> {
+        let side = (pixels * MAX_ANIMATION_SCALE).ceil().max(1.0) as i32;
+        self.images
+            .entry((image.path.clone(), side))
+            .or_insert_with(|| load(image, side))
+            .as_ref()
+    }
+}
+
+fn load(image: &ImageShape, side: i32) -> Option<Pixbuf> {
+    match Pixbuf::from_file_at_scale(&image.path, side, side, true) {
+        Ok(pixbuf) => Some(pixbuf),
+        Err(e) => {
+            tracing::warn!("Failed to load highlight image {:?}: {}", image.path, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Live highlight preview for the settings window
+//!
+//! Draws the cursor and animation styles from the [`PreferencesStore`] on
+//! every frame with the overlay's own renderer, so edits show up as soon as
+//! a widget changes them.
+
+use super::highlight_overlay::{Frame, HighlightOverlay};
+use super::shape_image::{ShapeImages, MAX_ANIMATION_SCALE};
+use crate::models::{DesktopAppearance, PreferencesStore};
+use gtk4::prelude::*;
+use gtk4::{glib, DrawingArea};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Height of the preview, in pixels
+const PREVIEW_HEIGHT: i32 = 160;
+
+/// Space kept free around the highlight, in pixels
+const MARGIN: f64 = 8.0;
+
+/// Animated preview of the current highlight style
+pub struct StylePreview;
+
+impl StylePreview {
+    /// Create a drawing area that keeps animating the style in `store`
+    pub fn new(store: &PreferencesStore) -> DrawingArea {
+        let area = DrawingArea::new();
+        area.set_content_height(PREVIEW_HEIGHT);
+        area.set_hexpand(true);
+
+        let start = Instant::now();
+        let images = Rc::new(RefCell::new(ShapeImages::default()));
+        let store = store.clone();
+        area.set_draw_func(move |area, cr, width, height| {
+            let (mut style, animation) =
+                store.with(|p| (p.cursor_style.clone(), p.animation_style.clone()));
+
+            // The settings window has no desktop to ask, so accent paints
+            // use their own color, as in rendered previews
+            DesktopAppearance::default().apply_to(&mut style);
+
+            // Shrink large highlights to fit, never enlarge small ones
+            let glow = if style.glow_enabled {
+                style.glow_radius * 2.0
+            } else {
+                0.0
+            };
+            let extent = style.outer_size() * MAX_ANIMATION_SCALE + glow + MARGIN * 2.0;
+            let (width, height) = (width as f64, height as f64);
+            let zoom = (width.min(height) / extent).min(1.0);
+            if zoom <= 0.0 {
+                return;
+            }
+
+            let elapsed = start.elapsed().as_secs_f64();
+            let progress = animation.progress_at(elapsed);
+
+            // The preview stands in for a single monitor, which guide lines span
+            let (half_width, half_height) = (width / 2.0 / zoom, height / 2.0 / zoom);
+            let monitors = vec![(
+                -half_width,
+                -half_height,
+                half_width * 2.0,
+                half_height * 2.0,
+            )];
+            let frame = Frame::new(&animation, progress, elapsed, monitors);
+
+            cr.translate(width / 2.0, height / 2.0);
+            cr.scale(zoom, zoom);
+            HighlightOverlay::draw_frame(
+                cr,
+                (0.0, 0.0),
+                &style,
+                &frame,
+                &mut images.borrow_mut(),
+                area.scale_factor() as f64 * zoom,
+            );
+        });
+
+        area.add_tick_callback(|area, _| {
+            area.queue_draw();
+            glib::ControlFlow::Continue
+        });
+
+        area
+    }
+}
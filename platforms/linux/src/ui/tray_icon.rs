@@ -2,26 +2,424 @@
 //!
 //! Provides a system tray icon for CursorHome using KSNI (KDE StatusNotifierItem).
 
-use crate::app::AppState;
-use std::rc::Rc;
+use crate::models::Color;
+use gtk4::cairo;
+use gtk4::glib;
+use ksni::menu::{CheckmarkItem, MenuItem, RadioGroup, RadioItem, StandardItem, SubMenu};
+use std::f64::consts::PI;
+use std::thread;
+
+/// Icon sizes offered to the tray host
+const ICON_SIZES: [i32; 3] = [22, 32, 48];
+
+/// Commands sent from the tray menu to the main thread
+#[derive(Debug, Clone)]
+pub enum TrayCommand {
+    FindCursor,
+    SetEnabled(bool),
+    SetFollowMode(bool),
+    SelectPreset(String),
+    ShowSettings,
+    Quit,
+}
+
+/// State reflected by the tray icon and its menu
+#[derive(Debug, Clone)]
+pub struct TrayState {
+    pub enabled: bool,
+    pub follow_mode: bool,
+    /// Names of the style presets, in menu order
+    pub presets: Vec<String>,
+    /// Preset matching the current style, if any
+    pub active_preset: Option<usize>,
+    pub color: Color,
+    /// Machine the cursor is on while it is away (from Synergy)
+    pub away_on: Option<String>,
+    /// Why the preferences file could not be loaded, if it couldn't
+    pub config_error: Option<String>,
+}
+
+impl TrayState {
+    fn status_text(&self) -> String {
+        if self.config_error.is_some() {
+            return "Settings file has errors".to_string();
+        }
+
+        match (&self.away_on, self.enabled) {
+            (_, false) => "CursorHome Disabled".to_string(),
+            (Some(screen_name), true) => format!("Cursor is on {}", screen_name),
+            (None, true) => "CursorHome Active".to_string(),
+        }
+    }
+}
+
+/// KSNI model; lives on the tray service thread
+struct CursorHomeTray {
+    state: TrayState,
+    commands: glib::Sender<TrayCommand>,
+}
+
+impl CursorHomeTray {
+    fn send(&self, command: TrayCommand) {
+        if self.commands.send(command).is_err() {
+            tracing::warn!("Tray command dropped, main loop is not running");
+        }
+    }
+}
+
+impl ksni::Tray for CursorHomeTray {
+    fn id(&self) -> String {
+        "cursorhome".into()
+    }
+
+    fn title(&self) -> String {
+        "CursorHome".into()
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.send(TrayCommand::FindCursor);
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        ICON_SIZES
+            .iter()
+            .filter_map(|size| render_icon(&self.state, *size))
+            .collect()
+    }
+
+    fn tool_tip(&self) -> ksni::ToolTip {
+        ksni::ToolTip {
+            title: "CursorHome".into(),
+            description: match &self.state.config_error {
+                Some(error) => error.clone(),
+                None => self.state.status_text(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let enabled = self.state.enabled;
+
+        vec![
+            StandardItem {
+                label: self.state.status_text(),
+                enabled: false,
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            StandardItem {
+                label: "Find Cursor".into(),
+                enabled,
+                activate: Box::new(|tray: &mut Self| tray.send(TrayCommand::FindCursor)),
+                ..Default::default()
+            }
+            .into(),
+            CheckmarkItem {
+                label: "Enabled".into(),
+                checked: enabled,
+                activate: Box::new(|tray: &mut Self| {
+                    tray.send(TrayCommand::SetEnabled(!tray.state.enabled))
+                }),
+                ..Default::default()
+            }
+            .into(),
+            CheckmarkItem {
+                label: "Follow Mode".into(),
+                enabled,
+                checked: self.state.follow_mode,
+                activate: Box::new(|tray: &mut Self| {
+                    tray.send(TrayCommand::SetFollowMode(!tray.state.follow_mode))
+                }),
+                ..Default::default()
+            }
+            .into(),
+            SubMenu {
+                label: "Style".into(),
+                enabled: !self.state.presets.is_empty(),
+                submenu: vec![RadioGroup {
+                    // Nothing is checked while the style matches no preset
+                    selected: self.state.active_preset.unwrap_or(usize::MAX),
+                    select: Box::new(|tray: &mut Self, index| {
+                        if let Some(name) = tray.state.presets.get(index).cloned() {
+                            tray.send(TrayCommand::SelectPreset(name));
+                        }
+                    }),
+                    options: self
+                        .state
+                        .presets
+                        .iter()
+                        .map(|name| RadioItem {
+                            label: name.clone(),
+                            ..Default::default()
+                        })
+                        .collect(),
+                }
+                .into()],
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            StandardItem {
+                label: "Settings…".into(),
+                activate: Box::new(|tray: &mut Self| tray.send(TrayCommand::ShowSettings)),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: "Quit CursorHome".into(),
+                activate: Box::new(|tray: &mut Self| tray.send(TrayCommand::Quit)),
+                ..Default::default()
+            }
+            .into(),
+        ]
+    }
+
+    fn watcher_offine(&self) -> bool {
+        tracing::warn!("StatusNotifierWatcher is offline, tray icon hidden until it returns");
+        true
+    }
+}
 
 /// System tray icon for CursorHome
 pub struct TrayIcon {
-    #[allow(dead_code)]
-    state: Rc<AppState>,
+    handle: Option<ksni::Handle<CursorHomeTray>>,
 }
 
 impl TrayIcon {
     /// Create a new tray icon
-    pub fn new(state: Rc<AppState>) -> Self {
-        Self { state }
+    pub fn new() -> Self {
+        Self { handle: None }
     }
 
-    /// Initialize the tray icon (non-async stub for now)
-    pub fn init(&self) {
-        tracing::info!("System tray icon initialized (stub)");
-        // TODO: Implement actual KSNI tray icon
-        // This requires running in a Tokio runtime context
-        // For now, the app runs without a tray icon
+    /// Register the tray icon and deliver menu commands on the main thread
+    pub fn start_with_callback<F>(&mut self, state: TrayState, on_command: F)
+    where
+        F: Fn(TrayCommand) + 'static,
+    {
+        let (sender, receiver) = glib::MainContext::channel(glib::Priority::DEFAULT);
+        receiver.attach(None, move |command| {
+            on_command(command);
+            glib::ControlFlow::Continue
+        });
+
+        let service = ksni::TrayService::new(CursorHomeTray {
+            state,
+            commands: sender,
+        });
+        self.handle = Some(service.handle());
+
+        // The KSNI service loop blocks, so it gets its own thread
+        thread::spawn(move || {
+            if let Err(e) = service.run() {
+                tracing::warn!("System tray unavailable: {}", e);
+            }
+        });
+
+        tracing::info!("System tray icon initialized");
+    }
+
+    /// Change the state shown by the tray icon and menu
+    pub fn update<F>(&self, f: F)
+    where
+        F: FnOnce(&mut TrayState),
+    {
+        if let Some(handle) = &self.handle {
+            handle.update(|tray| f(&mut tray.state));
+        }
+    }
+}
+
+impl Default for TrayIcon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        if let Some(handle) = &self.handle {
+            handle.shutdown();
+        }
+    }
+}
+
+/// Draw the tray icon: a ring in the highlight color, grey while disabled,
+/// and dashed with an arrow leaving to the right while the cursor is away
+fn render_icon(state: &TrayState, size: i32) -> Option<ksni::Icon> {
+    let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, size, size).ok()?;
+
+    {
+        let cr = cairo::Context::new(&surface).ok()?;
+        let s = size as f64;
+        let line_width = (s / 8.0).max(2.0);
+        let (r, g, b) = if state.enabled {
+            state.color.to_cairo_rgb()
+        } else {
+            (0.55, 0.55, 0.55)
+        };
+
+        cr.set_source_rgb(r, g, b);
+        cr.set_line_width(line_width);
+
+        if state.away_on.is_some() {
+            cr.set_dash(&[line_width, line_width], 0.0);
+            cr.arc(s * 0.4, s / 2.0, s * 0.4 - line_width, 0.0, 2.0 * PI);
+            cr.stroke().ok();
+            cr.set_dash(&[], 0.0);
+
+            // Arrow pointing off to the other machine
+            let tip = s - line_width / 2.0;
+            cr.move_to(s * 0.4, s / 2.0);
+            cr.line_to(tip, s / 2.0);
+            cr.move_to(tip - s * 0.25, s * 0.25);
+            cr.line_to(tip, s / 2.0);
+            cr.line_to(tip - s * 0.25, s * 0.75);
+            cr.stroke().ok();
+        } else {
+            cr.arc(s / 2.0, s / 2.0, s / 2.0 - line_width, 0.0, 2.0 * PI);
+            cr.stroke().ok();
+            cr.arc(s / 2.0, s / 2.0, line_width * 0.75, 0.0, 2.0 * PI);
+            cr.fill().ok();
+        }
+    }
+
+    surface.flush();
+    let stride = surface.stride() as usize;
+    let row_bytes = size as usize * 4;
+    let data = surface.data().ok()?;
+
+    // Cairo stores native-endian ARGB32; SNI expects network byte order
+    let mut argb = Vec::with_capacity(row_bytes * size as usize);
+    for row in data.chunks(stride).take(size as usize) {
+        for pixel in row[..row_bytes].chunks_exact(4) {
+            let value = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+            argb.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    Some(ksni::Icon {
+        width: size,
+        height: size,
+        data: argb,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+    use zbus::blocking::{Proxy, ProxyBuilder};
+    use zbus::dbus_interface;
+    use zbus::CacheProperties;
+
+    /// Stand-in for the StatusNotifierWatcher of a panel
+    struct MockWatcher {
+        items: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[dbus_interface(name = "org.kde.StatusNotifierWatcher")]
+    impl MockWatcher {
+        fn register_status_notifier_item(&self, service: String) {
+            self.items.lock().unwrap().push(service);
+        }
+
+        #[dbus_interface(property)]
+        fn is_status_notifier_host_registered(&self) -> bool {
+            true
+        }
+
+        #[dbus_interface(property)]
+        fn protocol_version(&self) -> i32 {
+            0
+        }
+    }
+
+    fn state() -> TrayState {
+        TrayState {
+            enabled: true,
+            follow_mode: false,
+            presets: vec!["Classic".to_string()],
+            active_preset: Some(0),
+            color: Color::default(),
+            away_on: None,
+            config_error: None,
+        }
+    }
+
+    /// Description shown in the tooltip of the item
+    fn tool_tip(item: &Proxy) -> String {
+        let (_, _, _, description): (String, Vec<(i32, i32, Vec<u8>)>, String, String) =
+            item.get_property("ToolTip").unwrap();
+        description
+    }
+
+    #[test]
+    fn registers_with_the_watcher_and_delivers_commands() {
+        let items = Arc::new(Mutex::new(Vec::new()));
+        let watcher = MockWatcher {
+            items: items.clone(),
+        };
+        let Some(_watcher) = test_bus::serve(
+            "org.kde.StatusNotifierWatcher",
+            "/StatusNotifierWatcher",
+            watcher,
+        ) else {
+            return;
+        };
+
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let commands = Rc::new(RefCell::new(Vec::new()));
+                let mut tray = TrayIcon::new();
+                tray.start_with_callback(state(), {
+                    let commands = commands.clone();
+                    move |command| commands.borrow_mut().push(command)
+                });
+
+                let service = test_bus::wait_for(|| items.lock().unwrap().first().cloned())
+                    .expect("tray item never registered");
+                let connection = test_bus::connect().unwrap();
+                let item: Proxy = ProxyBuilder::new_bare(&connection)
+                    .destination(service)
+                    .unwrap()
+                    .path("/StatusNotifierItem")
+                    .unwrap()
+                    .interface("org.kde.StatusNotifierItem")
+                    .unwrap()
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .unwrap();
+
+                assert_eq!(item.get_property::<String>("Id").unwrap(), "cursorhome");
+                let icons: Vec<(i32, i32, Vec<u8>)> = item.get_property("IconPixmap").unwrap();
+                let sizes: Vec<i32> = icons.iter().map(|(width, _, _)| *width).collect();
+                assert_eq!(sizes, ICON_SIZES);
+                assert!(icons
+                    .iter()
+                    .all(|(width, height, data)| data.len() == (width * height * 4) as usize));
+                assert_eq!(tool_tip(&item), "CursorHome Active");
+
+                item.call_method("Activate", &(0i32, 0i32)).unwrap();
+                test_bus::wait_for(|| {
+                    context.iteration(false);
+                    (!commands.borrow().is_empty()).then_some(())
+                })
+                .expect("activation never delivered");
+                assert!(matches!(
+                    commands.borrow().as_slice(),
+                    [TrayCommand::FindCursor]
+                ));
+
+                tray.update(|state| state.enabled = false);
+                test_bus::wait_for(|| (tool_tip(&item) == "CursorHome Disabled").then_some(()))
+                    .expect("tooltip never updated");
+            })
+            .unwrap();
     }
 }
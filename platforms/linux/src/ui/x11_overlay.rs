@@ -1,9 +1,20 @@
 //! X11 ARGB overlay for transparent cursor highlighting
 //!
 //! Creates a true transparent overlay window using X11's 32-bit ARGB visual.
-
-use crate::models::{AnimationStyle, AnimationType, CursorStyle, Shape};
+//! Frames are drawn with the same cairo renderer as the GTK overlay, so
+//! translucent layers, gradients and glow look the same on both.
+
+use super::cursor_label::CursorLabel;
+use super::frame_pacing::{self, FramePacer, FrameReport};
+use super::highlight_overlay::{Frame, HighlightOverlay};
+use super::shape_geometry;
+use super::shape_image::ShapeImages;
+use crate::models::{
+    AnimationStyle, Color, CursorStyle, Layer, Paint, Pattern, Shape, TransitionLabel,
+};
 use anyhow::{Context, Result};
+use gtk4::cairo::{self, Format, ImageSurface};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 use x11rb::connection::Connection;
 use x11rb::protocol::render::{ConnectionExt as RenderConnectionExt, PictType};
@@ -21,6 +32,15 @@ pub struct X11Overlay {
     height: u16,
     visual_id: Visualid,
     depth: u8,
+    /// Monitor rectangles in root window pixels, which pointer arrows and
+    /// guide lines use
+    monitors: Vec<shape_geometry::Rect>,
+    /// Label drawn beside the next highlight
+    label: Option<CursorLabel>,
+    /// Whether to draw frame statistics in a corner of the monitor
+    show_frame_stats: bool,
+    /// The latest frame statistics, when shown
+    frame_stats: Option<CursorLabel>,
     is_visible: bool,
 }
 
@@ -102,10 +122,42 @@ impl X11Overlay {
             height,
             visual_id,
             depth,
+            monitors: Vec::new(),
+            label: None,
+            show_frame_stats: false,
+            frame_stats: None,
             is_visible: false,
         })
     }
 
+    /// Show `text` beside the next highlight
+    pub fn set_label(&mut self, text: &str, style: &TransitionLabel) {
+        self.label = match CursorLabel::new(text, style) {
+            Ok(label) => Some(label),
+            Err(e) => {
+                tracing::warn!("Failed to render cursor label: {}", e);
+                None
+            }
+        };
+    }
+
+    /// Draw fps, frame time and missed frames on the overlay
+    pub fn set_show_frame_stats(&mut self, show: bool) {
+        self.show_frame_stats = show;
+    }
+
+    /// Monitor rectangles, or the whole screen if none are known
+    ///
+    /// Everything here is in root window pixels like the pointer, so guide
+    /// lines end at the monitor's real edges on scaled desktops too.
+    fn monitor_rects(&self) -> Vec<shape_geometry::Rect> {
+        if self.monitors.is_empty() {
+            vec![(0.0, 0.0, self.width as f64, self.height as f64)]
+        } else {
+            self.monitors.clone()
+        }
+    }
+
     /// Show the overlay and start the highlight animation
     pub fn show_highlight(
         &mut self,
@@ -113,6 +165,36 @@ impl X11Overlay {
         animation_style: &AnimationStyle,
         duration_secs: f64,
     ) -> Result<()> {
+        let duration = Duration::from_secs_f64(duration_secs);
+        self.run_highlight(style, animation_style, |elapsed| elapsed < duration)
+    }
+
+    /// Show the overlay until `keep_running` is cleared (used by follow mode)
+    pub fn show_highlight_while(
+        &mut self,
+        style: &CursorStyle,
+        animation_style: &AnimationStyle,
+        keep_running: &AtomicBool,
+    ) -> Result<()> {
+        self.run_highlight(style, animation_style, |_| {
+            keep_running.load(Ordering::SeqCst)
+        })
+    }
+
+    /// Run the animation loop for as long as `should_continue` returns true
+    fn run_highlight<F>(
+        &mut self,
+        style: &CursorStyle,
+        animation_style: &AnimationStyle,
+        should_continue: F,
+    ) -> Result<()>
+    where
+        F: Fn(Duration) -> bool,
+    {
+        // Monitors may have changed since the last highlight
+        let root = self.conn.setup().roots[self.screen_num].root;
+        self.monitors = frame_pacing::monitor_rects(&self.conn, root);
+
         // Map the window
         self.conn.map_window(self.window)?;
         self.conn.flush()?;
@@ -122,38 +204,69 @@ impl X11Overlay {
 
         // Run animation loop
         let start = Instant::now();
-        let duration = Duration::from_secs_f64(duration_secs);
-        let frame_duration = Duration::from_millis(16); // ~60fps
+        let mut pacer = FramePacer::new(&self.conn, root, self.window, self.get_cursor_position()?);
+        let mut adaptation = Adaptation::default();
+        let mut images = ShapeImages::default();
+        let mut last_drawn: Option<DrawnFrame> = None;
 
-        while start.elapsed() < duration {
-            let frame_start = Instant::now();
+        while should_continue(start.elapsed()) {
+            pacer.wait();
 
             // Get current cursor position
             let (cursor_x, cursor_y) = self.get_cursor_position()?;
 
             // Calculate animation progress
             let elapsed = start.elapsed().as_secs_f64();
-            let progress = self.calculate_animation_progress(elapsed, animation_style);
-
-            // Draw the highlight
-            self.draw_highlight(cursor_x, cursor_y, style, animation_style, progress)?;
+            let progress = animation_style.progress_at(elapsed);
+            let frame = Frame::new(animation_style, progress, elapsed, self.monitor_rects());
+            let cursor = (cursor_x, cursor_y);
+            let layers = self.adapt_layers(cursor, style, &frame, &mut adaptation, &mut images);
 
-            // Handle X11 events (exposure, etc.)
+            // Handle X11 events (exposure, vblank notifications)
             while let Some(event) = self.conn.poll_for_event()? {
-                if let x11rb::protocol::Event::Expose(_) = event {
-                    // Redraw on expose
-                    self.draw_highlight(cursor_x, cursor_y, style, animation_style, progress)?;
+                match event {
+                    x11rb::protocol::Event::Expose(_) => last_drawn = None,
+                    x11rb::protocol::Event::PresentCompleteNotify(event) => pacer.vblank(&event),
+                    _ => {}
+                }
+            }
+
+            if let Some(report) = pacer.stats().report() {
+                tracing::debug!("Overlay frames: {}", report);
+                if self.show_frame_stats {
+                    self.set_frame_stats(&report);
+                    last_drawn = None;
                 }
             }
 
-            // Frame timing
-            let frame_elapsed = frame_start.elapsed();
-            if frame_elapsed < frame_duration {
-                std::thread::sleep(frame_duration - frame_elapsed);
+            // Skip frames that would look the same as the last one
+            let drawn = DrawnFrame::new((cursor_x, cursor_y), &layers, progress, elapsed);
+            if last_drawn.as_ref() == Some(&drawn) {
+                pacer.stats().skipped();
+                continue;
             }
+
+            // Adaptive paints are already resolved in the layers
+            let style = CursorStyle {
+                layers,
+                ..style.clone()
+            };
+            let draw_start = Instant::now();
+            self.draw_highlight(cursor, &style, &frame, &mut images)?;
+            pacer.stats().drawn(draw_start.elapsed());
+            pacer.request_vblank(&self.conn, self.window)?;
+            self.conn.flush()?;
+            last_drawn = Some(drawn);
+            adaptation.shown = Some(Shown {
+                cursor: (cursor_x as f64, cursor_y as f64),
+                style,
+                frame,
+            });
         }
 
-        // Hide the overlay
+        // Hide the overlay; the label only belongs to this highlight
+        self.label = None;
+        self.frame_stats = None;
         self.hide()?;
 
         Ok(())
@@ -170,6 +283,21 @@ impl X11Overlay {
         Ok(())
     }
 
+    /// Render frame statistics for drawing on the overlay
+    fn set_frame_stats(&mut self, report: &FrameReport) {
+        let style = TransitionLabel {
+            font: FRAME_STATS_FONT.to_string(),
+            ..TransitionLabel::default()
+        };
+        self.frame_stats = match CursorLabel::new(&report.to_string(), &style) {
+            Ok(label) => Some(label),
+            Err(e) => {
+                tracing::debug!("Failed to render frame statistics: {}", e);
+                None
+            }
+        };
+    }
+
     /// Get current cursor position
     fn get_cursor_position(&self) -> Result<(i16, i16)> {
         let screen = &self.conn.setup().roots[self.screen_num];
@@ -181,36 +309,156 @@ impl X11Overlay {
         Ok((reply.root_x, reply.root_y))
     }
 
-    /// Calculate animation progress based on style
-    fn calculate_animation_progress(&self, elapsed: f64, style: &AnimationStyle) -> f64 {
-        if style.duration <= 0.0 {
-            return 1.0;
+    /// The style's layers, with adaptive paints replaced by a color that
+    /// contrasts with the screen around the highlight
+    fn adapt_layers(
+        &self,
+        cursor: (i16, i16),
+        style: &CursorStyle,
+        frame: &Frame,
+        adaptation: &mut Adaptation,
+        images: &mut ShapeImages,
+    ) -> Vec<Layer> {
+        let mut layers = style.effective_layers();
+        if !layers.iter().any(|layer| layer.color.is_adaptive()) {
+            return layers;
         }
 
-        let raw_progress = (elapsed % style.duration) / style.duration;
-
-        let progress = if style.auto_reverse {
-            let cycle = (elapsed / style.duration) as u32;
-            if cycle % 2 == 1 {
-                1.0 - raw_progress
-            } else {
-                raw_progress
+        // Sampling a few times a second is enough and keeps GetImage cheap
+        if adaptation.frames % SAMPLE_EVERY_FRAMES == 0 {
+            let half_size = (style.outer_size() * frame.scale() / 2.0) as i16;
+            let shown = adaptation.shown.as_ref();
+            match self.sample_background(cursor, half_size, shown, images) {
+                Ok(background) => adaptation.background = background,
+                Err(e) => tracing::debug!("Failed to sample background: {}", e),
             }
-        } else {
-            raw_progress
+        }
+        adaptation.frames += 1;
+
+        let Some(background) = adaptation.background else {
+            return layers;
         };
+        adaptation.colors.resize(layers.len(), None);
+        for (layer, smoothed) in layers.iter_mut().zip(&mut adaptation.colors) {
+            let Paint::Pattern(Pattern::Adaptive { color }) = layer.color else {
+                continue;
+            };
+
+            // Ease towards the new color so the highlight doesn't flicker
+            let target = color.contrasting(&background);
+            let color = match *smoothed {
+                Some(previous) => previous.mix(&target, SMOOTHING),
+                None => target,
+            };
+            *smoothed = Some(color);
+            layer.color = Paint::Solid(color);
+        }
+
+        layers
+    }
+
+    /// Average color of a thin frame of screen pixels just outside the
+    /// highlight
+    ///
+    /// The screen includes the overlay, so what `shown` drew there (glow,
+    /// guide lines, arrow shafts, the label) is taken back out of each
+    /// pixel, and pixels it mostly covers are skipped. Returns `None` for
+    /// pixel formats other than 32-bit little-endian.
+    fn sample_background(
+        &self,
+        (cursor_x, cursor_y): (i16, i16),
+        half_size: i16,
+        shown: Option<&Shown>,
+        images: &mut ShapeImages,
+    ) -> Result<Option<Color>> {
+        let setup = self.conn.setup();
+        let screen = &setup.roots[self.screen_num];
+        let bits_per_pixel = setup
+            .pixmap_formats
+            .iter()
+            .find(|format| format.depth == screen.root_depth)
+            .map(|format| format.bits_per_pixel);
+        if bits_per_pixel != Some(32) || setup.image_byte_order != ImageOrder::LSB_FIRST {
+            return Ok(None);
+        }
 
-        style.easing.apply(progress)
+        let inner = half_size as i32 + 2;
+        let outer = inner + SAMPLE_MARGIN;
+        let (cx, cy) = (cursor_x as i32, cursor_y as i32);
+        let strips = [
+            // Top and bottom, full width
+            (cx - outer, cy - outer, cx + outer, cy - inner),
+            (cx - outer, cy + inner, cx + outer, cy + outer),
+            // Left and right, between them
+            (cx - outer, cy - inner, cx - inner, cy + inner),
+            (cx + inner, cy - inner, cx + outer, cy + inner),
+        ];
+
+        let (mut r, mut g, mut b, mut count) = (0u64, 0u64, 0u64, 0u64);
+        for (x0, y0, x1, y1) in strips {
+            // Clip to the screen
+            let (x0, y0) = (x0.max(0), y0.max(0));
+            let (x1, y1) = (x1.min(self.width as i32), y1.min(self.height as i32));
+            if x1 <= x0 || y1 <= y0 {
+                continue;
+            }
+
+            let region = (x0 as i16, y0 as i16, (x1 - x0) as u16, (y1 - y0) as u16);
+            let (x, y, width, height) = region;
+            let image = self
+                .conn
+                .get_image(ImageFormat::Z_PIXMAP, screen.root, x, y, width, height, !0)?
+                .reply()?;
+
+            // The overlay's own premultiplied pixels over the same area
+            let overlay = match shown {
+                Some(shown) => {
+                    self.render(region, shown.cursor, &shown.style, &shown.frame, images)?
+                }
+                None => Vec::new(),
+            };
+
+            // Pixels are stored as B, G, R, unused
+            for (i, pixel) in image.data.chunks_exact(4).enumerate() {
+                let [alpha, over_r, over_g, over_b] = overlay.get(i).copied().unwrap_or_default();
+                if alpha > MAX_SAMPLED_OVERLAY_ALPHA {
+                    continue;
+                }
+
+                // Undo blending the overlay over the background
+                let uncover = |screen: u8, over: u8| {
+                    let background = (screen as f64 - over as f64) * 255.0 / (255 - alpha) as f64;
+                    background.clamp(0.0, 255.0) as u64
+                };
+                b += uncover(pixel[0], over_b);
+                g += uncover(pixel[1], over_g);
+                r += uncover(pixel[2], over_r);
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Color::new(
+            (r / count) as u8,
+            (g / count) as u8,
+            (b / count) as u8,
+            1.0,
+        )))
     }
 
-    /// Draw the highlight at cursor position using XRender
+    /// Draw the highlight at the cursor position
+    ///
+    /// Core X11 drawing replaces pixels instead of blending them, so each
+    /// frame is drawn with cairo into images of the areas it covers, which
+    /// are uploaded with their alpha.
     fn draw_highlight(
         &self,
-        cursor_x: i16,
-        cursor_y: i16,
+        (cursor_x, cursor_y): (i16, i16),
         style: &CursorStyle,
-        animation_style: &AnimationStyle,
-        progress: f64,
+        frame: &Frame,
+        images: &mut ShapeImages,
     ) -> Result<()> {
         // Create a GC for drawing
         let gc = self.conn.generate_id()?;
@@ -232,113 +480,267 @@ impl X11Overlay {
             }],
         )?;
 
-        // Calculate alpha and scale based on animation
-        let (alpha, scale) = match animation_style.animation_type {
-            AnimationType::None => (style.color.a as f64, 1.0),
-            AnimationType::Pulse => {
-                let alpha = 0.3 + 0.7 * (1.0 - progress);
-                (alpha * style.color.a as f64, 1.0)
-            }
-            AnimationType::Fade => {
-                let alpha = 1.0 - progress * 0.7;
-                (alpha * style.color.a as f64, 1.0)
-            }
-            AnimationType::Scale => {
-                let scale = 0.8 + 0.4 * (1.0 - progress);
-                (style.color.a as f64, scale)
-            }
-            AnimationType::Ripple => {
-                let alpha = 1.0 - progress;
-                let scale = 1.0 + progress * 0.5;
-                (alpha * style.color.a as f64, scale)
-            }
+        let cursor = (cursor_x as f64, cursor_y as f64);
+        for region in self.regions(cursor, style, frame) {
+            let pixels = self.render(region, cursor, style, frame, images)?;
+            let (x, y, width, height) = region;
+            self.put_pixels(gc, (x, y), (width, height), &pixels)?;
+        }
+
+        // Free the GC
+        self.conn.free_gc(gc)?;
+        self.conn.flush()?;
+
+        Ok(())
+    }
+
+    /// Screen areas that together cover everything a frame draws
+    ///
+    /// Pointer arrow shafts are covered in short pieces, so a diagonal
+    /// arrow doesn't make a whole monitor's worth of pixels to upload.
+    fn regions(
+        &self,
+        cursor: shape_geometry::Point,
+        style: &CursorStyle,
+        frame: &Frame,
+    ) -> Vec<PixelRect> {
+        let layers = style.effective_layers();
+        // Room for the widest line and its antialiasing
+        let margin = layers
+            .iter()
+            .map(|layer| layer.border_weight)
+            .fold(0.0, f64::max)
+            + 2.0;
+        let glow = if style.glow_enabled {
+            style.glow_radius
+        } else {
+            0.0
         };
+        let radius = style.outer_size() * frame.scale() / 2.0;
+        let extent = radius + glow + margin;
+        let mut rects = vec![(
+            cursor.0 - extent,
+            cursor.1 - extent,
+            extent * 2.0,
+            extent * 2.0,
+        )];
+
+        if let Some(bounds) = frame.guide_bounds(style.guide_lines, cursor) {
+            for (start, end) in shape_geometry::guide_lines(cursor, bounds, radius) {
+                rects.push(line_bounds(start, end, margin));
+            }
+        }
+
+        for layer in layers
+            .iter()
+            .filter(|layer| matches!(layer.shape, Shape::PointerArrows))
+        {
+            for monitor in self.monitor_rects() {
+                let Some(arrow) = shape_geometry::pointer_arrow(
+                    shape_geometry::center(monitor),
+                    cursor,
+                    layer.size * frame.scale(),
+                    layer.border_weight,
+                ) else {
+                    continue;
+                };
+
+                let (start, end) = arrow.shaft;
+                let length = (end.0 - start.0).hypot(end.1 - start.1);
+                let pieces = (length / ARROW_PIECE_LENGTH).ceil().max(1.0) as u32;
+                let along = |i: u32| {
+                    let t = i as f64 / pieces as f64;
+                    (
+                        start.0 + (end.0 - start.0) * t,
+                        start.1 + (end.1 - start.1) * t,
+                    )
+                };
+                for i in 0..pieces {
+                    rects.push(line_bounds(along(i), along(i + 1), margin));
+                }
 
-        let size = (style.size * scale) as i16;
-        let half_size = size / 2;
-
-        // Convert color to X11 format (ARGB)
-        let a = (alpha * 255.0).clamp(0.0, 255.0) as u32;
-        let r = style.color.r as u32;
-        let g = style.color.g as u32;
-        let b = style.color.b as u32;
-        let color = (a << 24) | (r << 16) | (g << 8) | b;
-
-        // Update GC with the color
-        self.conn
-            .change_gc(gc, &ChangeGCAux::new().foreground(color))?;
-
-        // Draw based on shape
-        match style.shape {
-            Shape::Circle | Shape::Spotlight => {
-                // Draw filled circle using arcs
-                self.conn.poly_fill_arc(
-                    self.window,
-                    gc,
-                    &[Arc {
-                        x: cursor_x - half_size,
-                        y: cursor_y - half_size,
-                        width: size as u16,
-                        height: size as u16,
-                        angle1: 0,
-                        angle2: 360 * 64, // Full circle (in 1/64 degrees)
-                    }],
-                )?;
+                let [tip, left, right] = arrow.head;
+                let head = [(tip, left), (left, right)].map(|(a, b)| line_bounds(a, b, margin));
+                rects.extend(shape_geometry::bounding_box(&head));
             }
-            Shape::Ring => {
-                // Draw ring (arc outline)
-                let line_width = style.border_weight as u16;
-                self.conn
-                    .change_gc(gc, &ChangeGCAux::new().line_width(line_width as u32))?;
-                self.conn.poly_arc(
-                    self.window,
-                    gc,
-                    &[Arc {
-                        x: cursor_x - half_size,
-                        y: cursor_y - half_size,
-                        width: size as u16,
-                        height: size as u16,
-                        angle1: 0,
-                        angle2: 360 * 64,
-                    }],
-                )?;
+        }
+
+        if let Some(label) = &self.label {
+            let (x, y) = label.origin(cursor.0, cursor.1, style.outer_size() / 2.0);
+            rects.push((x, y, label.width() as f64, label.height() as f64));
+        }
+        if let Some(stats) = &self.frame_stats {
+            let (x, y) = self.frame_stats_origin(cursor);
+            rects.push((x, y, stats.width() as f64, stats.height() as f64));
+        }
+
+        rects
+            .into_iter()
+            .filter_map(|rect| self.pixel_rect(rect))
+            .collect()
+    }
+
+    /// Draw the frame into an image of `region`, as premultiplied ARGB
+    /// pixels row by row
+    fn render(
+        &self,
+        (x, y, width, height): PixelRect,
+        cursor: shape_geometry::Point,
+        style: &CursorStyle,
+        frame: &Frame,
+        images: &mut ShapeImages,
+    ) -> Result<Vec<[u8; 4]>> {
+        let surface = ImageSurface::create(Format::ARgb32, width as i32, height as i32)?;
+        {
+            let cr = cairo::Context::new(&surface)?;
+            cr.translate(-x as f64, -y as f64);
+
+            // Root window pixels are device pixels, so images need no scaling
+            HighlightOverlay::draw_frame(&cr, cursor, style, frame, images, 1.0);
+
+            if let Some(label) = &self.label {
+                label.draw(&cr, cursor.0, cursor.1, style.outer_size() / 2.0);
             }
-            Shape::Crosshair => {
-                // Draw crosshair lines
-                let line_width = style.border_weight as u16;
-                self.conn
-                    .change_gc(gc, &ChangeGCAux::new().line_width(line_width as u32))?;
-                self.conn.poly_segment(
-                    self.window,
-                    gc,
-                    &[
-                        // Horizontal line
-                        Segment {
-                            x1: cursor_x - half_size,
-                            y1: cursor_y,
-                            x2: cursor_x + half_size,
-                            y2: cursor_y,
-                        },
-                        // Vertical line
-                        Segment {
-                            x1: cursor_x,
-                            y1: cursor_y - half_size,
-                            x2: cursor_x,
-                            y2: cursor_y + half_size,
-                        },
-                    ],
-                )?;
+            if let Some(stats) = &self.frame_stats {
+                stats.draw_at(&cr, self.frame_stats_origin(cursor));
             }
         }
+        surface.flush();
 
-        // Free the GC
-        self.conn.free_gc(gc)?;
-        self.conn.flush()?;
+        surface_pixels(&surface)
+    }
+
+    /// Top left corner of the frame statistics, in the top left corner of
+    /// the cursor's monitor
+    fn frame_stats_origin(&self, cursor: shape_geometry::Point) -> shape_geometry::Point {
+        let monitors = self.monitor_rects();
+        let (x, y, _, _) = shape_geometry::rect_at(&monitors, cursor).unwrap_or(monitors[0]);
+        (x + FRAME_STATS_MARGIN, y + FRAME_STATS_MARGIN)
+    }
 
+    /// The whole pixels of `rect` that are on the screen, if any
+    fn pixel_rect(&self, (x, y, width, height): shape_geometry::Rect) -> Option<PixelRect> {
+        let left = x.floor().max(0.0);
+        let top = y.floor().max(0.0);
+        let right = (x + width).ceil().min(self.width as f64);
+        let bottom = (y + height).ceil().min(self.height as f64);
+        if right <= left || bottom <= top {
+            return None;
+        }
+        Some((
+            left as i16,
+            top as i16,
+            (right - left) as u16,
+            (bottom - top) as u16,
+        ))
+    }
+
+    /// Upload premultiplied ARGB `pixels` of `size` with their top-left
+    /// corner at `origin`
+    fn put_pixels(
+        &self,
+        gc: Gcontext,
+        (x, y): (i16, i16),
+        (width, height): (u16, u16),
+        pixels: &[[u8; 4]],
+    ) -> Result<()> {
+        // Convert to the server's byte order
+        let lsb_first = self.conn.setup().image_byte_order == ImageOrder::LSB_FIRST;
+        let data: Vec<u8> = pixels
+            .iter()
+            .flat_map(|&[a, r, g, b]| {
+                if lsb_first {
+                    [b, g, r, a]
+                } else {
+                    [a, r, g, b]
+                }
+            })
+            .collect();
+
+        self.conn.put_image(
+            ImageFormat::Z_PIXMAP,
+            self.window,
+            gc,
+            width,
+            height,
+            x,
+            y,
+            0,
+            self.depth,
+            &data,
+        )?;
         Ok(())
     }
 }
 
+/// Frames between background samples for adaptive paints (~15 per second)
+const SAMPLE_EVERY_FRAMES: u32 = 4;
+
+/// Width in pixels of the frame of background sampled around the highlight
+const SAMPLE_MARGIN: i32 = 6;
+
+/// Opacity of the overlay above which a screen pixel shows too little of
+/// the background to sample
+const MAX_SAMPLED_OVERLAY_ALPHA: u8 = 230;
+
+/// Fraction of the way an adaptive color moves to its target each frame
+const SMOOTHING: f64 = 0.15;
+
+/// Length in pixels of the pieces pointer arrow shafts are uploaded in
+const ARROW_PIECE_LENGTH: f64 = 64.0;
+
+/// Font of the frame statistics drawn on the overlay
+const FRAME_STATS_FONT: &str = "Monospace 10";
+
+/// Distance of the frame statistics from the monitor's corner, in pixels
+const FRAME_STATS_MARGIN: f64 = 16.0;
+
+/// Background and smoothed colors for adaptive paints during a highlight
+#[derive(Default)]
+struct Adaptation {
+    /// Frames since the highlight started, whether drawn or skipped
+    frames: u32,
+    background: Option<Color>,
+    /// Last color drawn for each layer
+    colors: Vec<Option<Color>>,
+    /// The frame on screen, to tell the overlay apart from the background
+    shown: Option<Shown>,
+}
+
+/// A frame as it was drawn, with adaptive paints resolved
+struct Shown {
+    cursor: shape_geometry::Point,
+    style: CursorStyle,
+    frame: Frame,
+}
+
+/// An area of the screen as x, y, width and height in whole pixels
+type PixelRect = (i16, i16, u16, u16);
+
+/// Everything that decides how a frame looks, to skip redrawing frames
+/// identical to the last one
+#[derive(PartialEq)]
+struct DrawnFrame {
+    cursor: (i16, i16),
+    layers: Vec<Layer>,
+    /// Animation progress, which decides fade and scale
+    progress: f64,
+    /// Time into the highlight, only for paints that change with it
+    elapsed: Option<f64>,
+}
+
+impl DrawnFrame {
+    fn new(cursor: (i16, i16), layers: &[Layer], progress: f64, elapsed: f64) -> Self {
+        let cycling = layers.iter().any(|layer| layer.color.is_cycling());
+        Self {
+            cursor,
+            layers: layers.to_vec(),
+            progress,
+            elapsed: cycling.then_some(elapsed),
+        }
+    }
+}
+
 impl Drop for X11Overlay {
     fn drop(&mut self) {
         let _ = self.conn.destroy_window(self.window);
@@ -346,6 +748,34 @@ impl Drop for X11Overlay {
     }
 }
 
+/// Bounds of a line from `start` to `end`, widened by `margin` on all sides
+fn line_bounds(
+    start: shape_geometry::Point,
+    end: shape_geometry::Point,
+    margin: f64,
+) -> shape_geometry::Rect {
+    let (left, top) = (start.0.min(end.0) - margin, start.1.min(end.1) - margin);
+    let (right, bottom) = (start.0.max(end.0) + margin, start.1.max(end.1) + margin);
+    (left, top, right - left, bottom - top)
+}
+
+/// Premultiplied ARGB pixels of a cairo image, row by row
+fn surface_pixels(surface: &ImageSurface) -> Result<Vec<[u8; 4]>> {
+    let (width, height) = (surface.width() as usize, surface.height() as usize);
+    let stride = surface.stride() as usize;
+    let mut pixels = Vec::with_capacity(width * height);
+    surface.with_data(|data| {
+        for row in data.chunks(stride).take(height) {
+            for pixel in row[..width * 4].chunks_exact(4) {
+                // Cairo stores each pixel as a native-endian 32-bit ARGB value
+                let argb = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                pixels.push(argb.to_be_bytes());
+            }
+        }
+    })?;
+    Ok(pixels)
+}
+
 /// Find a 32-bit ARGB visual
 fn find_argb_visual(conn: &RustConnection, screen_num: usize) -> Result<(Visualid, u8)> {
     let screen = &conn.setup().roots[screen_num];